@@ -2,8 +2,9 @@ from functools import reduce
 
 from sqlalchemy.inspection import inspect
 from sqlalchemy.orm import Session
-from sqlalchemy.sql import false, true
+from sqlalchemy.sql import false, literal, not_, true
 
+from ...exceptions import UnsupportedError
 from ..filter import Projection
 from .adapter import DataAdapter
 
@@ -55,6 +56,16 @@ class SqlAlchemyAdapter(DataAdapter):
             return lhs in rhs
         elif op == "Nin":
             return lhs not in rhs
+        elif op in ("MatchesRegex", "NotMatchesRegex"):
+            if not hasattr(lhs, "regexp_match"):
+                lhs = literal(lhs)
+            # The regex syntax is the database's, which may differ from Polar's.
+            matches = lhs.regexp_match(rhs)
+            return matches if op == "MatchesRegex" else not_(matches)
+        else:
+            raise UnsupportedError(
+                f"The SQLAlchemy adapter doesn't support the {op} comparison"
+            )
 
     @staticmethod
     def add_side(side):
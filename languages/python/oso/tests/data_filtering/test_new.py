@@ -313,6 +313,17 @@ def test_neq(oso):
         oso.check_authz("steve", bar.id, Foo, expected)
 
 
+def test_matches_regex(oso):
+    oso.load_str(
+        """
+            allow("steve", "get", log: Log) if log.data matches_regex "^s|d$";
+            allow("steve", "put", log: Log) if not log.data matches_regex "^s|d$";
+        """
+    )
+    oso.check_authz("steve", "get", Log, [third_log_b, another_log_c])
+    oso.check_authz("steve", "put", Log, [fourth_log_a])
+
+
 def test_relationship(oso):
     oso.load_str(
         """
@@ -37,10 +37,15 @@ module Oso
           private
 
           def sqlize(cond)
+            op = OPS.fetch(cond.cmp) do
+              # E.g., `MatchesRegex`, which has no portable SQL equivalent.
+              raise ::Oso::Polar::UnsupportedError,
+                    "The ActiveRecord adapter doesn't support the #{cond.cmp} comparison"
+            end
             args = []
             lhs = add_side cond.left, args
             rhs = add_side cond.right, args
-            args.unshift "#{lhs} #{OPS[cond.cmp]} #{rhs}"
+            args.unshift "#{lhs} #{op} #{rhs}"
           end
 
           def add_side(side, args)
@@ -412,6 +412,18 @@ RSpec.describe Oso::Oso do # rubocop:disable Metrics/BlockLength
           expect(query.to_a).to eq [mercury]
         end
 
+        it 'test_unsupported_comparisons' do
+          subject.load_str <<~POL
+            allow(_, "read", planet: Planet) if planet.name matches_regex "^m";
+            allow(_, "write", planet: Planet) if not planet.name matches_regex "^m";
+          POL
+          %w[read write].each do |action|
+            expect { subject.authorized_query 'gwen', action, Planet }.to raise_error(
+              Oso::Polar::UnsupportedError, /doesn't support the \w*MatchesRegex comparison/
+            )
+          end
+        end
+
         it 'test_in_with_scalar' do
           subject.load_str <<~POL
             allow(_, _, planet: Planet) if
@@ -1,6 +1,8 @@
 //! Builtin types supported in Polar
 
 use crate::PolarValue;
+use polar_core::temporal::{Duration, Timestamp};
+use std::any::TypeId;
 use std::collections::HashMap;
 
 use crate::{Class, ClassBuilder};
@@ -159,9 +161,42 @@ fn string() -> ClassBuilder<String> {
         .add_method("repeat", |s: &String, n: i64| s.repeat(n as usize))
 }
 
+fn timestamp() -> ClassBuilder<Timestamp> {
+    Class::builder::<Timestamp>()
+        .with_equality_check()
+        .name("Timestamp")
+        .add_method("unix_secs", Timestamp::unix_secs)
+        .add_method("subsec_nanos", |t: &Timestamp| i64::from(t.subsec_nanos()))
+}
+
+fn duration() -> ClassBuilder<Duration> {
+    Class::builder::<Duration>()
+        .with_equality_check()
+        .name("Duration")
+        .add_method("is_negative", Duration::is_negative)
+}
+
+#[cfg(feature = "bignum")]
+fn decimal() -> ClassBuilder<polar_core::terms::BigDecimal> {
+    Class::builder::<polar_core::terms::BigDecimal>()
+        .with_equality_check()
+        .name("Decimal")
+}
+
+/// Whether an application class may replace the builtin `class`. The `Timestamp`, `Duration` and
+/// `Decimal` classes were added after applications could register classes with those names.
+pub fn is_shadowable(class: &Class) -> bool {
+    #[cfg(feature = "bignum")]
+    if class.type_id == TypeId::of::<polar_core::terms::BigDecimal>() {
+        return true;
+    }
+    class.type_id == TypeId::of::<Timestamp>() || class.type_id == TypeId::of::<Duration>()
+}
+
 /// Returns the builtin types, the name, class, and instance
 pub fn classes() -> Vec<Class> {
-    vec![
+    #[allow(unused_mut)]
+    let mut classes = vec![
         boolean().build(),
         integer().build(),
         float().build(),
@@ -169,5 +204,10 @@ pub fn classes() -> Vec<Class> {
         dictionary().build(),
         string().build(),
         option().build(),
-    ]
+        timestamp().build(),
+        duration().build(),
+    ];
+    #[cfg(feature = "bignum")]
+    classes.push(decimal().build());
+    classes
 }
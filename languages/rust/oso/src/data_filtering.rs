@@ -0,0 +1,344 @@
+//! Data filtering: fetch the resources an actor is allowed to access.
+//!
+//! Data filtering turns an `allow` query over an unbound resource into a [`Filter`] describing
+//! the resources that would be allowed. A [`DataAdapter`] registered with
+//! [`Oso::set_data_filtering_adapter()`](crate::Oso::set_data_filtering_adapter) turns that
+//! [`Filter`] into a query against your data store and executes it.
+//!
+//! Classes that take part in data filtering declare their fields with
+//! [`ClassBuilder::add_field()`](crate::ClassBuilder::add_field) and
+//! [`ClassBuilder::add_relation()`](crate::ClassBuilder::add_relation).
+
+use std::any::Any;
+use std::collections::HashMap;
+
+use polar_core::data_filtering::Type;
+use polar_core::filter;
+
+pub use polar_core::data_filtering::Types;
+pub use polar_core::filter::Comparison;
+
+use crate::host::Host;
+use crate::{OsoError, PolarValue};
+
+/// The cardinality of a [`Relation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RelationKind {
+    /// The relation points to a single record.
+    One,
+    /// The relation points to a collection of records.
+    Many,
+}
+
+impl RelationKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RelationKind::One => "one",
+            RelationKind::Many => "many",
+        }
+    }
+}
+
+/// Represents a relationship between two registered classes, e.g. one-to-one or one-to-many.
+///
+/// A relation named `org` on `Repo` declared as `Relation::one("Org", "org_id", "id")` means that
+/// `repo.org` is the `Org` whose `id` field equals the `org_id` field of `repo`.
+///
+/// # Examples
+///
+/// ```
+/// use oso::{ClassBuilder, Relation};
+///
+/// #[derive(Clone)]
+/// struct Repo {
+///     id: i64,
+///     org_id: i64,
+/// }
+///
+/// let class = ClassBuilder::<Repo>::with_constructor(|id: i64, org_id: i64| Repo { id, org_id })
+///     .add_field::<i64>("id")
+///     .add_field::<i64>("org_id")
+///     .add_relation("org", Relation::one("Org", "org_id", "id"))
+///     .build();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Relation {
+    pub kind: RelationKind,
+    /// The registered name of the class on the other side of the relation.
+    pub other_type: String,
+    /// The field on this class used to join.
+    pub my_field: String,
+    /// The field on the other class used to join.
+    pub other_field: String,
+}
+
+impl Relation {
+    /// A relation to a single record of `other_type`.
+    pub fn one(other_type: &str, my_field: &str, other_field: &str) -> Self {
+        Self::new(RelationKind::One, other_type, my_field, other_field)
+    }
+
+    /// A relation to a collection of records of `other_type`.
+    pub fn many(other_type: &str, my_field: &str, other_field: &str) -> Self {
+        Self::new(RelationKind::Many, other_type, my_field, other_field)
+    }
+
+    fn new(kind: RelationKind, other_type: &str, my_field: &str, other_field: &str) -> Self {
+        Self {
+            kind,
+            other_type: other_type.to_owned(),
+            my_field: my_field.to_owned(),
+            other_field: other_field.to_owned(),
+        }
+    }
+
+    pub(crate) fn to_type(&self) -> Type {
+        Type::Relation {
+            kind: self.kind.as_str().to_owned(),
+            other_class_tag: self.other_type.clone(),
+            my_field: self.my_field.clone(),
+            other_field: self.other_field.clone(),
+        }
+    }
+}
+
+/// Represents an abstract query over a data source.
+///
+/// A record of type `model` passes the filter if, after following `relations`, every condition
+/// in at least one of the inner vectors of `conditions` holds for it.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    /// The registered name of the class being queried.
+    pub model: String,
+    /// Named relations to other data sources ("joins"), in dependency order.
+    pub relations: Vec<FilterRelation>,
+    /// Query conditions: an OR of ANDs.
+    pub conditions: Vec<Vec<FilterCondition>>,
+    /// Field and relation declarations for all registered classes.
+    pub types: Types,
+}
+
+/// A named relation between two data sources, e.g. an organization to its members.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FilterRelation {
+    pub from_type_name: String,
+    pub from_field_name: String,
+    pub to_type_name: String,
+}
+
+/// A boolean condition over a set of data sources.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterCondition {
+    pub lhs: Datum,
+    pub cmp: Comparison,
+    pub rhs: Datum,
+}
+
+/// A side of a [`FilterCondition`]: either a field reference or an immediate value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Datum {
+    Field(Projection),
+    Immediate(PolarValue),
+}
+
+/// A type and an optional field, like `User.name`, `Post.user_id`, or `Tag`.
+///
+/// If the field name is absent, the adapter should substitute the field that uniquely
+/// identifies the record (e.g. `Tag` becomes `Tag.id`).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Projection {
+    pub type_name: String,
+    pub field_name: Option<String>,
+}
+
+impl Filter {
+    /// Convert a filter from polar-core, resolving immediate values using `host`.
+    pub(crate) fn from_core(
+        filter: filter::Filter,
+        types: Types,
+        host: &Host,
+    ) -> crate::Result<Self> {
+        let relations = filter
+            .relations
+            .into_iter()
+            .map(|filter::Relation(from, field, to)| FilterRelation {
+                from_type_name: from,
+                from_field_name: field,
+                to_type_name: to,
+            })
+            .collect();
+
+        let conditions = filter
+            .conditions
+            .into_iter()
+            .map(|conjuncts| {
+                conjuncts
+                    .into_iter()
+                    .map(|filter::Condition(lhs, cmp, rhs)| {
+                        Ok(FilterCondition {
+                            lhs: Datum::from_core(lhs, host)?,
+                            cmp,
+                            rhs: Datum::from_core(rhs, host)?,
+                        })
+                    })
+                    .collect::<crate::Result<Vec<_>>>()
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            model: filter.root,
+            relations,
+            conditions,
+            types,
+        })
+    }
+
+    /// Look up the [`Relation`] declaration backing `relation`.
+    pub fn relation(&self, relation: &FilterRelation) -> Option<Relation> {
+        match self
+            .types
+            .get(&relation.from_type_name)
+            .and_then(|fields| fields.get(&relation.from_field_name))
+        {
+            Some(Type::Relation {
+                kind,
+                other_class_tag,
+                my_field,
+                other_field,
+            }) => Some(Relation {
+                kind: if kind == "many" {
+                    RelationKind::Many
+                } else {
+                    RelationKind::One
+                },
+                other_type: other_class_tag.clone(),
+                my_field: my_field.clone(),
+                other_field: other_field.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Datum {
+    fn from_core(datum: filter::Datum, host: &Host) -> crate::Result<Self> {
+        match datum {
+            filter::Datum::Field(filter::Projection(type_name, field_name)) => {
+                Ok(Datum::Field(Projection {
+                    type_name,
+                    field_name,
+                }))
+            }
+            filter::Datum::Immediate(value) => {
+                let term = polar_core::terms::Term::new_from_ffi(value);
+                PolarValue::from_term(&term, host).map(Datum::Immediate)
+            }
+        }
+    }
+}
+
+/// Builds and executes queries for data filtering.
+///
+/// An adapter turns a [`Filter`] into a query for a particular data store (for example, a SQL
+/// statement or an ORM query builder), and executes such queries to fetch the matching records.
+///
+/// # Examples
+///
+/// An adapter over an in-memory collection, evaluating the filter in `execute_query`:
+///
+/// ```
+/// use oso::data_filtering::{DataAdapter, Datum, Filter};
+/// use oso::{PolarValue, ToPolar};
+///
+/// struct Numbers(Vec<i64>);
+///
+/// impl DataAdapter for Numbers {
+///     type Query = Filter;
+///
+///     fn build_query(&self, filter: &Filter) -> oso::Result<Filter> {
+///         Ok(filter.clone())
+///     }
+///
+///     fn execute_query(&self, filter: Filter) -> oso::Result<Vec<PolarValue>> {
+///         Ok(self
+///             .0
+///             .iter()
+///             .filter(|n| {
+///                 filter.conditions.iter().any(|conjuncts| {
+///                     conjuncts.iter().all(|condition| match &condition.rhs {
+///                         Datum::Immediate(value) => &n.to_polar() == value,
+///                         Datum::Field(_) => false,
+///                     })
+///                 })
+///             })
+///             .map(|n| n.to_polar())
+///             .collect())
+///     }
+/// }
+/// ```
+pub trait DataAdapter: Send + Sync + 'static {
+    /// The query type produced by this adapter.
+    type Query: 'static;
+
+    /// Build a query that fetches the records matching `filter`.
+    fn build_query(&self, filter: &Filter) -> crate::Result<Self::Query>;
+
+    /// Execute a query built by [`DataAdapter::build_query()`].
+    fn execute_query(&self, query: Self::Query) -> crate::Result<Vec<PolarValue>>;
+}
+
+/// Type-erased [`DataAdapter`], so that adapters can be stored on the host.
+pub(crate) trait DynDataAdapter: Send + Sync {
+    fn build_query(&self, filter: &Filter) -> crate::Result<Box<dyn Any>>;
+    fn execute_query(&self, query: Box<dyn Any>) -> crate::Result<Vec<PolarValue>>;
+}
+
+impl<A: DataAdapter> DynDataAdapter for A {
+    fn build_query(&self, filter: &Filter) -> crate::Result<Box<dyn Any>> {
+        DataAdapter::build_query(self, filter).map(|query| Box::new(query) as Box<dyn Any>)
+    }
+
+    fn execute_query(&self, query: Box<dyn Any>) -> crate::Result<Vec<PolarValue>> {
+        let query = query.downcast::<A::Query>().map_err(|_| {
+            crate::errors::TypeError::expected(std::any::type_name::<A::Query>()).user()
+        })?;
+        DataAdapter::execute_query(self, *query)
+    }
+}
+
+/// Field declarations of a class, as used in [`Types`].
+pub(crate) fn serialize_fields(
+    fields: &HashMap<&'static str, FieldType>,
+    host: &Host,
+) -> crate::Result<HashMap<String, Type>> {
+    fields
+        .iter()
+        .map(|(name, field)| {
+            let typ = match field {
+                FieldType::Base { type_id, type_name } => Type::Base {
+                    class_tag: host
+                        .get_class_by_type_id(*type_id)
+                        .map_err(|_| OsoError::MissingClassError {
+                            name: type_name.to_string(),
+                        })?
+                        .name
+                        .clone(),
+                },
+                FieldType::Relation(relation) => relation.to_type(),
+            };
+            Ok((name.to_string(), typ))
+        })
+        .collect()
+}
+
+/// The declared type of a field on a class.
+#[derive(Clone, Debug)]
+pub(crate) enum FieldType {
+    /// A field holding a value of a registered class.
+    Base {
+        type_id: std::any::TypeId,
+        type_name: &'static str,
+    },
+    /// A field relating this class to another registered class.
+    Relation(Relation),
+}
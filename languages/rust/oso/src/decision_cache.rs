@@ -0,0 +1,393 @@
+//! A cache of the authorization decisions made by [`Oso::is_allowed`].
+//!
+//! [`Oso::is_allowed`]: crate::Oso::is_allowed
+
+use std::any::TypeId;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use polar_core::temporal;
+
+use crate::host::Instance;
+use crate::PolarValue;
+
+type KeyFunction = Arc<dyn Fn(&Instance) -> Option<String> + Send + Sync>;
+
+/// A cacheable form of a `PolarValue`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Integer(i64),
+    /// The bits of the float.
+    Float(u64),
+    #[cfg(feature = "bignum")]
+    BigInteger(polar_core::terms::BigInt),
+    #[cfg(feature = "bignum")]
+    Decimal(polar_core::terms::BigDecimal),
+    String(String),
+    Boolean(bool),
+    Timestamp(temporal::Timestamp),
+    Duration(temporal::Duration),
+    List(Vec<CacheKey>),
+    /// Sorted by key.
+    Map(Vec<(String, CacheKey)>),
+    Instance(TypeId, String),
+}
+
+#[derive(Default)]
+struct Decisions {
+    decisions: HashMap<Vec<CacheKey>, (bool, Instant)>,
+    /// The keys of `decisions` in the order the decisions were made, so the oldest are evicted
+    /// first. A key is queued each time its decision is made, and only the latest of its
+    /// queued times evicts it.
+    made: VecDeque<(Vec<CacheKey>, Instant)>,
+    /// The generation of the policy that the decisions were made with. See
+    /// [`Polar::generation`].
+    ///
+    /// [`Polar::generation`]: polar_core::polar::Polar::generation
+    policy: u64,
+    /// Incremented whenever the cache is cleared, so that decisions made before then aren't
+    /// cached afterwards.
+    generation: u64,
+}
+
+impl Decisions {
+    fn clear(&mut self) {
+        self.decisions.clear();
+        self.made.clear();
+        self.generation += 1;
+    }
+
+    /// Cache `decision`, first evicting the expired decisions, then the oldest ones until there
+    /// are fewer than `max_entries`.
+    fn insert(
+        &mut self,
+        key: Vec<CacheKey>,
+        decision: bool,
+        ttl: Option<Duration>,
+        max_entries: Option<usize>,
+    ) {
+        while let Some((_, made)) = self.made.front() {
+            let expired = ttl.is_some_and(|ttl| made.elapsed() >= ttl);
+            let full = max_entries.is_some_and(|max| self.decisions.len() >= max);
+            if !expired && !full {
+                break;
+            }
+            let (key, made) = self.made.pop_front().unwrap();
+            if self.decisions.get(&key).is_some_and(|(_, m)| *m == made) {
+                self.decisions.remove(&key);
+            }
+        }
+        let made = Instant::now();
+        self.made.push_back((key.clone(), made));
+        self.decisions.insert(key, (decision, made));
+    }
+
+    /// Forget the decisions made with a policy older than `policy`. Returns whether decisions
+    /// made with `policy` can be cached, which they can't if the policy has changed since.
+    fn follow(&mut self, policy: u64) -> bool {
+        if policy > self.policy {
+            self.clear();
+            self.policy = policy;
+        }
+        policy == self.policy
+    }
+}
+
+/// Hit and miss counts of a [`DecisionCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Decisions read from the cache.
+    pub hits: u64,
+    /// Decisions that were made and then cached.
+    pub misses: u64,
+    /// Decisions that couldn't be cached, because an argument is an instance without a key
+    /// function or a variable, or because the policy changed while they were being made.
+    pub uncacheable: u64,
+}
+
+/// A cache of the decisions made by [`Oso::is_allowed`], keyed on its arguments.
+///
+/// Strings, numbers, booleans, lists and maps are keys as they are. Instances of an application
+/// type are only cacheable once a key function for the type is provided with
+/// [`DecisionCache::with_key`]; calls with other instances aren't cached.
+///
+/// Set a cache with [`Oso::set_decision_cache`]. The cache is shared by clones of the `Oso`
+/// it is set on, so to scope a cache to a request, set a new one on a clone of your `Oso` for
+/// each request. Otherwise, decisions are cached until they expire (see
+/// [`DecisionCache::with_ttl`]), are evicted from a full cache (see
+/// [`DecisionCache::with_capacity`]), or until the policy changes: when it's loaded, reloaded (also
+/// by a [`PolicyWatcher`]) or cleared, a class or constant is registered, or the clock is set
+/// with [`Oso::set_clock`], on this `Oso` or any of its clones. Decisions aren't cached while a
+/// rule of the loaded policy calls `now()`, since they can change at any time.
+///
+/// ```
+/// use oso::decision_cache::DecisionCache;
+/// # use oso::PolarClass;
+///
+/// #[derive(Clone, PolarClass)]
+/// struct User {
+///     #[polar(attribute)]
+///     id: i64,
+/// }
+///
+/// let mut oso = oso::Oso::new();
+/// oso.register_class(User::get_polar_class()).unwrap();
+/// oso.load_str(r#"allow(user: User, "read", _) if user.id = 1;"#).unwrap();
+///
+/// let cache = DecisionCache::new().with_key(|user: &User| user.id.to_string());
+/// oso.set_decision_cache(Some(cache));
+/// let user = User { id: 1 };
+/// assert!(oso.is_allowed(user.clone(), "read", "doc").unwrap());
+/// assert!(oso.is_allowed(user, "read", "doc").unwrap());
+/// assert_eq!(oso.decision_cache().unwrap().stats().hits, 1);
+/// ```
+///
+/// [`Oso::is_allowed`]: crate::Oso::is_allowed
+/// [`Oso::set_decision_cache`]: crate::Oso::set_decision_cache
+/// [`Oso::set_clock`]: crate::Oso::set_clock
+/// [`PolicyWatcher`]: crate::watch::PolicyWatcher
+#[derive(Default)]
+pub struct DecisionCache {
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+    key_functions: HashMap<TypeId, KeyFunction>,
+    decisions: Mutex<Decisions>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    uncacheable: AtomicU64,
+}
+
+impl DecisionCache {
+    /// A cache whose decisions don't expire.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expire cached decisions `ttl` after they were made.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Cache at most `max_entries` decisions, evicting the oldest when the cache is full.
+    ///
+    /// # Panics
+    ///
+    /// If `max_entries` is 0.
+    pub fn with_capacity(mut self, max_entries: usize) -> Self {
+        assert!(
+            max_entries > 0,
+            "a decision cache must hold at least one entry"
+        );
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Cache decisions involving instances of `T`, identifying each instance by the key that
+    /// `f` returns. Two instances with the same key must get the same decisions.
+    pub fn with_key<T, F>(mut self, f: F) -> Self
+    where
+        T: 'static,
+        F: Fn(&T) -> String + Send + Sync + 'static,
+    {
+        let f: KeyFunction =
+            Arc::new(move |instance: &Instance| instance.downcast(None).ok().map(&f));
+        self.key_functions.insert(TypeId::of::<T>(), f);
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            uncacheable: self.uncacheable.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Forget every cached decision.
+    pub fn clear(&self) {
+        self.decisions.lock().unwrap().clear();
+    }
+
+    /// Return the cached decision for `args`, or make it with `decide` and cache it. `policy` is
+    /// the generation of the policy that `decide` uses.
+    pub(crate) fn get_or_insert_with(
+        &self,
+        args: &[PolarValue],
+        policy: u64,
+        decide: impl FnOnce() -> crate::Result<bool>,
+    ) -> crate::Result<bool> {
+        let key = match args
+            .iter()
+            .map(|arg| self.key(arg))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(key) => key,
+            None => {
+                self.uncacheable.fetch_add(1, Ordering::Relaxed);
+                return decide();
+            }
+        };
+
+        let generation = {
+            let mut decisions = self.decisions.lock().unwrap();
+            if !decisions.follow(policy) {
+                self.uncacheable.fetch_add(1, Ordering::Relaxed);
+                drop(decisions);
+                return decide();
+            }
+            if let Some((decision, made)) = decisions.decisions.get(&key) {
+                if self.ttl.is_none_or(|ttl| made.elapsed() < ttl) {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(*decision);
+                }
+                decisions.decisions.remove(&key);
+            }
+            decisions.generation
+        };
+
+        // Don't hold the lock while querying.
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let decision = decide()?;
+        let mut decisions = self.decisions.lock().unwrap();
+        if decisions.generation == generation {
+            decisions.insert(key, decision, self.ttl, self.max_entries);
+        }
+        Ok(decision)
+    }
+
+    fn key(&self, value: &PolarValue) -> Option<CacheKey> {
+        let key = match value {
+            PolarValue::Integer(i) => CacheKey::Integer(*i),
+            PolarValue::Float(f) => CacheKey::Float(f.to_bits()),
+            #[cfg(feature = "bignum")]
+            PolarValue::BigInteger(i) => CacheKey::BigInteger(i.clone()),
+            #[cfg(feature = "bignum")]
+            PolarValue::Decimal(d) => CacheKey::Decimal(d.clone()),
+            PolarValue::String(s) => CacheKey::String(s.clone()),
+            PolarValue::Boolean(b) => CacheKey::Boolean(*b),
+            PolarValue::Timestamp(t) => CacheKey::Timestamp(*t),
+            PolarValue::Duration(d) => CacheKey::Duration(*d),
+            PolarValue::List(l) => {
+                CacheKey::List(l.iter().map(|v| self.key(v)).collect::<Option<_>>()?)
+            }
+            PolarValue::Map(m) => {
+                let mut fields = m
+                    .iter()
+                    .map(|(k, v)| Some((k.clone(), self.key(v)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+                CacheKey::Map(fields)
+            }
+            PolarValue::Instance(instance) => {
+                let type_id = instance.type_id();
+                let key = self.key_functions.get(&type_id)?(instance)?;
+                CacheKey::Instance(type_id, key)
+            }
+            PolarValue::Variable(_) => return None,
+        };
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cache_keys() {
+        struct Foo(i64);
+        struct Bar;
+
+        let cache = DecisionCache::new().with_key(|foo: &Foo| foo.0.to_string());
+        let key = |value| cache.key(&value);
+
+        assert_eq!(
+            key(PolarValue::Map(
+                [
+                    ("b".to_owned(), PolarValue::Integer(1)),
+                    ("a".to_owned(), PolarValue::Boolean(true)),
+                ]
+                .into()
+            )),
+            Some(CacheKey::Map(vec![
+                ("a".to_owned(), CacheKey::Boolean(true)),
+                ("b".to_owned(), CacheKey::Integer(1)),
+            ]))
+        );
+        assert_eq!(
+            key(PolarValue::Instance(Instance::new(Foo(1)))),
+            key(PolarValue::Instance(Instance::new(Foo(1))))
+        );
+        assert_ne!(
+            key(PolarValue::Instance(Instance::new(Foo(1)))),
+            key(PolarValue::Instance(Instance::new(Foo(2))))
+        );
+        assert_eq!(key(PolarValue::Instance(Instance::new(Bar))), None);
+        assert_eq!(
+            key(PolarValue::List(vec![
+                PolarValue::String("a".to_owned()),
+                PolarValue::Variable("x".to_owned()),
+            ])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cache_ttl() {
+        let cache = DecisionCache::new().with_ttl(Duration::from_millis(0));
+        let args = [PolarValue::Integer(1)];
+        assert!(cache.get_or_insert_with(&args, 0, || Ok(true)).unwrap());
+        assert!(!cache.get_or_insert_with(&args, 0, || Ok(false)).unwrap());
+        assert_eq!(cache.stats().misses, 2);
+
+        // Expired decisions are evicted.
+        let cache = DecisionCache::new().with_ttl(Duration::from_millis(0));
+        for i in 0..10 {
+            cache
+                .get_or_insert_with(&[PolarValue::Integer(i)], 0, || Ok(true))
+                .unwrap();
+        }
+        let decisions = cache.decisions.lock().unwrap();
+        assert_eq!((decisions.decisions.len(), decisions.made.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_cache_capacity() {
+        let cache = DecisionCache::new().with_capacity(2);
+        let decide = |i| cache.get_or_insert_with(&[PolarValue::Integer(i)], 0, || Ok(i > 1));
+        for i in [1, 2, 1, 3, 1] {
+            decide(i).unwrap();
+        }
+        // 1 was the oldest decision when 3 was cached, so it was evicted, and then 2 was.
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 4);
+        let decisions = &cache.decisions.lock().unwrap().decisions;
+        assert_eq!(decisions.len(), 2);
+        assert!(decisions.contains_key(&vec![CacheKey::Integer(1)]));
+        assert!(decisions.contains_key(&vec![CacheKey::Integer(3)]));
+    }
+
+    #[test]
+    fn test_cache_follows_policy() {
+        let cache = DecisionCache::new();
+        let args = [PolarValue::Integer(1)];
+        assert!(cache.get_or_insert_with(&args, 1, || Ok(true)).unwrap());
+        assert!(cache.get_or_insert_with(&args, 1, || Ok(false)).unwrap());
+        // Decisions made with an older policy are forgotten...
+        assert!(!cache.get_or_insert_with(&args, 2, || Ok(false)).unwrap());
+        // ...and decisions made with one aren't cached.
+        assert!(cache.get_or_insert_with(&args, 1, || Ok(true)).unwrap());
+        assert!(!cache.get_or_insert_with(&args, 2, || Ok(true)).unwrap());
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 2,
+                uncacheable: 1
+            }
+        );
+    }
+}
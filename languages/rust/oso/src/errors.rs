@@ -16,6 +16,9 @@ pub enum OsoError {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Polar(#[from] polar::PolarError),
+    #[cfg(feature = "watch")]
+    #[error(transparent)]
+    Watch(#[from] notify::Error),
     #[error("failed to convert type from Polar")]
     FromPolar,
     #[error("policy files must have the .polar extension. {filename} does not.")]
@@ -46,6 +49,9 @@ pub enum OsoError {
     #[error(transparent)]
     InvalidCallError(#[from] InvalidCallError),
 
+    #[error(transparent)]
+    AuthorizationError(#[from] AuthorizationError),
+
     #[error("failed to convert type to Polar")]
     ToPolar,
 
@@ -55,9 +61,19 @@ pub enum OsoError {
     #[error("No class called {name} has been registered")]
     MissingClassError { name: String },
 
+    #[error(
+        "Cannot order the supertypes of {name}: they're cyclic or declared in conflicting orders"
+    )]
+    InconsistentSupertypes { name: String },
+
     #[error("Tried to find an instance that doesn't exist -- internal error")]
     MissingInstanceError,
 
+    #[error(
+        "No data filtering adapter has been set. Use `Oso::set_data_filtering_adapter` to set one."
+    )]
+    MissingDataFilteringAdapter,
+
     /// TODO: replace all these with proper variants
     #[error("{message}")]
     Custom { message: String },
@@ -146,6 +162,26 @@ impl TypeError {
     }
 }
 
+/// Authorization failures returned by the enforcement methods on [`Oso`](crate::Oso).
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorizationError {
+    /// The actor is not allowed to perform the action, nor to read the resource (and thus should
+    /// not know it exists).
+    ///
+    /// Most of the time, your app should handle this error by returning a 404 HTTP error to the
+    /// client. The action used for the distinction between `NotFound` and `Forbidden` can be
+    /// customized with [`Oso::set_read_action()`](crate::Oso::set_read_action).
+    #[error("Oso NotFoundError -- The current user does not have permission to read the given resource. You should handle this error by returning a 404 error to the client.")]
+    NotFound,
+
+    /// The action is not allowed.
+    ///
+    /// Most of the time, your app should handle this error by returning a 403 HTTP error to the
+    /// client.
+    #[error("Oso ForbiddenError -- The requested action was not allowed for the given resource. You should handle this error by returning a 403 error to the client.")]
+    Forbidden,
+}
+
 #[derive(Error, Debug)]
 pub enum InvalidCallError {
     #[error("Class method {method_name} not found on type {type_name}.")]
@@ -163,6 +199,12 @@ pub enum InvalidCallError {
         attribute_name: String,
         type_name: String,
     },
+    #[error("{callee} does not accept keyword arguments.")]
+    KeywordArgumentsNotSupported { callee: String },
+    #[error("{callee} got an unexpected keyword argument {name}.")]
+    UnexpectedKeywordArgument { callee: String, name: String },
+    #[error("{callee} is missing the argument {name}.")]
+    MissingArgument { callee: String, name: String },
 }
 
 pub type Result<T> = std::result::Result<T, OsoError>;
@@ -24,3 +24,52 @@ impl crate::PolarClass for uuid_10::Uuid {
             .with_equality_check()
     }
 }
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use chrono::{DateTime, TimeZone, Utc};
+    use polar_core::temporal::{Duration, Timestamp};
+
+    use crate::{FromPolar, PolarValue, ToPolar};
+
+    impl<Tz: TimeZone> ToPolar for DateTime<Tz> {
+        fn to_polar(self) -> PolarValue {
+            let timestamp = Timestamp::from_unix(self.timestamp(), self.timestamp_subsec_nanos())
+                .expect("a DateTime is in the range of a Timestamp");
+            PolarValue::Timestamp(timestamp)
+        }
+    }
+
+    impl FromPolar for DateTime<Utc> {
+        fn from_polar(val: PolarValue) -> crate::Result<Self> {
+            let timestamp = Timestamp::from_polar(val)?;
+            DateTime::from_timestamp(timestamp.unix_secs(), timestamp.subsec_nanos())
+                .ok_or(crate::OsoError::FromPolar)
+        }
+    }
+
+    impl ToPolar for chrono::Duration {
+        fn to_polar(self) -> PolarValue {
+            let nanos =
+                i128::from(self.num_seconds()) * 1_000_000_000 + i128::from(self.subsec_nanos());
+            PolarValue::Duration(
+                Duration::from_nanos(nanos)
+                    .expect("a chrono Duration is in the range of a Duration"),
+            )
+        }
+    }
+
+    impl FromPolar for chrono::Duration {
+        fn from_polar(val: PolarValue) -> crate::Result<Self> {
+            let nanos = Duration::from_polar(val)?.as_nanos();
+            let (secs, nanos) = (
+                nanos.div_euclid(1_000_000_000),
+                nanos.rem_euclid(1_000_000_000),
+            );
+            i64::try_from(secs)
+                .ok()
+                .and_then(|secs| Self::new(secs, nanos as u32))
+                .ok_or(crate::OsoError::FromPolar)
+        }
+    }
+}
@@ -1,14 +1,17 @@
 //! Support for dynamic class objects in Rust
 
 use std::any::TypeId;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::sync::Arc;
 
+use crate::data_filtering::{FieldType, Relation};
 use crate::errors::{InvalidCallError, OsoError};
 
 use super::class_method::{
-    AttributeGetter, ClassMethod, Constructor, InstanceMethod, RegisterHook,
+    AsyncAttributeGetter, AsyncInstanceMethod, AttributeGetter, ClassMethod, Constructor,
+    InstanceMethod, PolarFuture, RegisterHook,
 };
 use super::from_polar::FromPolarList;
 use super::method::{Function, Method};
@@ -17,11 +20,32 @@ use super::Host;
 use super::PolarValue;
 
 type Attributes = HashMap<&'static str, AttributeGetter>;
+type Fields = HashMap<&'static str, FieldType>;
 type RegisterHooks = Vec<RegisterHook>;
 type ClassMethods = HashMap<&'static str, ClassMethod>;
 type InstanceMethods = HashMap<&'static str, InstanceMethod>;
+type AsyncAttributes = HashMap<&'static str, AsyncAttributeGetter>;
+type AsyncInstanceMethods = HashMap<&'static str, AsyncInstanceMethod>;
+/// Parameter names of a constructor or method, in order.
+type Parameters = Arc<[&'static str]>;
+/// Keyword arguments of a call.
+pub(crate) type Kwargs = BTreeMap<String, PolarValue>;
+
+/// A declared supertype of a class, resolved to a registered class when policies are loaded.
+#[derive(Clone, Debug)]
+pub(crate) enum Supertype {
+    /// The class registered for a Rust type.
+    Type {
+        type_id: TypeId,
+        type_name: &'static str,
+    },
+    /// The class registered under a name.
+    Name(String),
+}
 
 type EqualityMethod = Arc<dyn Fn(&Host, &Instance, &Instance) -> crate::Result<bool> + Send + Sync>;
+type ComparisonMethod =
+    Arc<dyn Fn(&Host, &Instance, &Instance) -> crate::Result<Option<Ordering>> + Send + Sync>;
 type IteratorMethod =
     Arc<dyn Fn(&Host, &Instance) -> crate::Result<crate::host::PolarIterator> + Send + Sync>;
 
@@ -36,6 +60,65 @@ fn equality_not_supported() -> EqualityMethod {
     Arc::new(eq)
 }
 
+fn comparison_not_supported() -> ComparisonMethod {
+    let cmp = move |host: &Host, lhs: &Instance, _: &Instance| -> crate::Result<Option<Ordering>> {
+        Err(OsoError::UnsupportedOperation {
+            operation: String::from("comparison"),
+            type_name: lhs.name(host).to_owned(),
+        })
+    };
+
+    Arc::new(cmp)
+}
+
+/// Check that `params` names each of the `arity` arguments of the function registered as
+/// `callee` of `class`.
+fn assert_params(class: &str, callee: &str, params: &[&str], arity: usize) {
+    assert_eq!(
+        params.len(),
+        arity,
+        "{} {} needs a parameter name for each of its {} arguments, but got {}",
+        class,
+        callee,
+        arity,
+        params.len()
+    );
+}
+
+/// Arrange positional arguments `args` and keyword arguments `kwargs` in the order of `params`,
+/// the parameter names of `callee`. Every parameter after the positional arguments must be
+/// passed by keyword.
+fn bind_kwargs(
+    callee: impl Fn() -> String,
+    params: Option<&[&'static str]>,
+    mut args: Vec<PolarValue>,
+    mut kwargs: Kwargs,
+) -> crate::Result<Vec<PolarValue>> {
+    if kwargs.is_empty() {
+        return Ok(args);
+    }
+    let params = params
+        .ok_or_else(|| InvalidCallError::KeywordArgumentsNotSupported { callee: callee() })?;
+    for name in params.iter().skip(args.len()) {
+        let arg = kwargs
+            .remove(*name)
+            .ok_or_else(|| InvalidCallError::MissingArgument {
+                callee: callee(),
+                name: name.to_string(),
+            })?;
+        args.push(arg);
+    }
+    // Keyword arguments that are left are either unknown or were also passed positionally.
+    if let Some(name) = kwargs.into_keys().next() {
+        return Err(InvalidCallError::UnexpectedKeywordArgument {
+            callee: callee(),
+            name,
+        }
+        .into());
+    }
+    Ok(args)
+}
+
 fn iterator_not_supported() -> IteratorMethod {
     let into_iter = move |host: &Host, instance: &Instance| {
         Err(OsoError::UnsupportedOperation {
@@ -64,17 +147,33 @@ pub struct Class {
     pub type_id: TypeId,
     /// A wrapped method that constructs an instance of `T` from `PolarValue`s
     constructor: Option<Constructor>,
+    /// Parameter names of the constructor, if it accepts keyword arguments
+    constructor_params: Option<Parameters>,
     /// Methods that return simple attribute lookups on an instance of `T`
     attributes: Attributes,
     /// Instance methods on `T` that expect a list of `PolarValue`s, and an instance of `&T`
     instance_methods: InstanceMethods,
+    /// Parameter names of the instance methods that accept keyword arguments
+    method_params: HashMap<&'static str, Parameters>,
     /// Class methods on `T`
     class_methods: ClassMethods,
+    /// Attribute getters on `T` that return futures, awaited by [`AsyncQuery`](crate::AsyncQuery)
+    async_attributes: AsyncAttributes,
+    /// Instance methods on `T` that return futures, awaited by [`AsyncQuery`](crate::AsyncQuery)
+    async_methods: AsyncInstanceMethods,
+    /// Declared field types of `T`, used for data filtering
+    pub(crate) fields: Fields,
+    /// Declared supertypes of `T`, nearest first
+    pub(crate) supertypes: Vec<Supertype>,
 
     /// A function that accepts arguments of this class and compares them for equality.
     /// Limitation: Only works on comparisons of the same type.
     equality_check: EqualityMethod,
 
+    /// A function that orders two arguments of this class, for `<`, `<=`, `>` and `>=`.
+    /// Limitation: Only works on comparisons of the same type.
+    comparison: ComparisonMethod,
+
     into_iter: IteratorMethod,
 
     /// Hooks to be called on the class once it's been registered with host.
@@ -92,8 +191,21 @@ impl Class {
 
     /// Initialize new class instance.
     pub fn init(&self, fields: Vec<PolarValue>) -> crate::Result<Instance> {
+        self.init_with_kwargs(fields, Kwargs::new())
+    }
+
+    /// Initialize new class instance with positional arguments `args` and keyword arguments
+    /// `kwargs`, which the constructor must accept (see
+    /// [`ClassBuilder::set_constructor_with_kwargs`]).
+    pub(crate) fn init_with_kwargs(
+        &self,
+        args: Vec<PolarValue>,
+        kwargs: Kwargs,
+    ) -> crate::Result<Instance> {
         if let Some(constructor) = &self.constructor {
-            constructor.invoke(fields)
+            let callee = || format!("{} constructor", self.name);
+            let args = bind_kwargs(callee, self.constructor_params.as_deref(), args, kwargs)?;
+            constructor.invoke(args)
         } else {
             Err(crate::OsoError::Custom {
                 message: format!("MissingConstructorError: {} has no constructor", self.name),
@@ -126,6 +238,12 @@ impl Class {
         }
     }
 
+    /// Whether `name` is an async attribute or method, which only an
+    /// [`AsyncQuery`](crate::AsyncQuery) can call.
+    fn has_async(&self, name: &str) -> bool {
+        self.async_attributes.contains_key(name) || self.async_methods.contains_key(name)
+    }
+
     fn equals(&self, host: &Host, lhs: &Instance, rhs: &Instance) -> crate::Result<bool> {
         // equality checking is currently only supported for exactly matching types
         // TODO: support multiple dispatch for equality
@@ -135,6 +253,20 @@ impl Class {
             (self.equality_check)(host, lhs, rhs)
         }
     }
+
+    fn compare(
+        &self,
+        host: &Host,
+        lhs: &Instance,
+        rhs: &Instance,
+    ) -> crate::Result<Option<Ordering>> {
+        // Instances of different types are unordered.
+        if lhs.type_id() != rhs.type_id() {
+            Ok(None)
+        } else {
+            (self.comparison)(host, lhs, rhs)
+        }
+    }
 }
 
 /// Builder for new Oso [`Class`].
@@ -166,10 +298,17 @@ where
             class: Class {
                 name: short_name.to_string(),
                 constructor: None,
+                constructor_params: None,
                 attributes: HashMap::new(),
                 instance_methods: InstanceMethods::new(),
+                method_params: HashMap::new(),
                 class_methods: ClassMethods::new(),
+                async_attributes: AsyncAttributes::new(),
+                async_methods: AsyncInstanceMethods::new(),
+                fields: Fields::new(),
+                supertypes: vec![],
                 equality_check: equality_not_supported(),
+                comparison: comparison_not_supported(),
                 into_iter: iterator_not_supported(),
                 type_id: TypeId::of::<T>(),
                 register_hooks: RegisterHooks::new(),
@@ -242,6 +381,43 @@ where
         Args: FromPolarList,
     {
         self.class.constructor = Some(Constructor::new(f));
+        self.class.constructor_params = None;
+        self
+    }
+
+    /// Set the constructor function to use for polar `new` statements, which may pass its
+    /// arguments by keyword, as in `new Foo(1, name: "foo")`. `params` are the names of the
+    /// arguments of `f`, in order. Arguments after the positional ones must all be passed by
+    /// keyword.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use oso::PolarClass;
+    ///
+    /// #[derive(PolarClass)]
+    /// struct User {
+    ///     name: String,
+    ///     age: i64,
+    /// }
+    ///
+    /// let class = User::get_polar_class_builder()
+    ///     .set_constructor_with_kwargs(&["name", "age"], |name, age| User { name, age })
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If there isn't one name in `params` for each argument of `f`.
+    pub fn set_constructor_with_kwargs<F, Args>(mut self, params: &[&'static str], f: F) -> Self
+    where
+        F: Function<Args, Result = T>,
+        T: Send + Sync,
+        Args: FromPolarList,
+    {
+        assert_params(&self.class.name, "constructor", params, Args::arity());
+        self.class.constructor = Some(Constructor::new(f));
+        self.class.constructor_params = Some(params.into());
         self
     }
 
@@ -328,6 +504,66 @@ where
         self.set_equality_check(|a, b| PartialEq::eq(a, b))
     }
 
+    /// Set a comparison function for Polar `<`, `<=`, `>` and `>=` statements. A comparison
+    /// that returns `None` is false.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use oso::ClassBuilder;
+    ///
+    /// #[derive(Default)]
+    /// struct Version(u64, u64);
+    ///
+    /// let class = ClassBuilder::<Version>::with_default()
+    ///     .set_comparison(|left, right| Some((left.0, left.1).cmp(&(right.0, right.1))))
+    ///     .build();
+    /// ```
+    pub fn set_comparison<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&T, &T) -> Option<Ordering> + Send + Sync + 'static,
+    {
+        self.class.comparison = Arc::new(move |host, a, b| {
+            tracing::trace!("comparison");
+
+            let a = a.downcast(Some(host)).map_err(|e| e.user())?;
+            let b = b.downcast(Some(host)).map_err(|e| e.user())?;
+
+            Ok((f)(a, b))
+        });
+
+        self
+    }
+
+    /// Use [`PartialOrd`] as the comparison for Polar `<`, `<=`, `>` and `>=` statements.
+    ///
+    /// `==` and `!=` still use the equality check; see
+    /// [`ClassBuilder::with_equality_check`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use oso::ClassBuilder;
+    ///
+    /// #[derive(Default, PartialEq, PartialOrd)]
+    /// struct Timestamp(u64);
+    ///
+    /// let class = ClassBuilder::<Timestamp>::with_default()
+    ///     .with_equality_check()
+    ///     .with_ord()
+    ///     .build();
+    /// ```
+    pub fn with_ord(self) -> Self
+    where
+        T: PartialOrd<T>,
+    {
+        self.set_comparison(|a, b| PartialOrd::partial_cmp(a, b))
+    }
+
     /// Add an attribute getter.
     ///
     /// An attribute getter allows you to write statements like `foo.bar`, where `foo` is a class
@@ -364,12 +600,145 @@ where
         self
     }
 
+    /// Add an attribute getter that returns a future.
+    ///
+    /// The future is awaited when the attribute is looked up by an [`AsyncQuery`](crate::AsyncQuery),
+    /// e.g. one created with [`Oso::query_async()`](crate::Oso::query_async). Looking up the
+    /// attribute from a synchronous [`Query`](crate::Query) is an error.
+    ///
+    /// The future may not borrow from the instance, so copy what it needs out of the instance
+    /// before creating it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use oso::ClassBuilder;
+    ///
+    /// #[derive(Default)]
+    /// struct User {
+    ///     id: u64,
+    /// };
+    ///
+    /// async fn fetch_team(user_id: u64) -> String {
+    ///     format!("team-{}", user_id)
+    /// }
+    ///
+    /// let class = ClassBuilder::<User>::with_default()
+    ///     .add_async_attribute_getter("team", |user| fetch_team(user.id))
+    ///     .build();
+    /// ```
+    pub fn add_async_attribute_getter<F, Fut>(mut self, name: &'static str, f: F) -> Self
+    where
+        F: Fn(&T) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future + 'static,
+        Fut::Output: ToPolarResult,
+    {
+        self.class
+            .async_attributes
+            .insert(name, AsyncAttributeGetter::new(f));
+        self
+    }
+
+    /// Declare the type of a field, for use with data filtering.
+    ///
+    /// The field type `F` must be registered as a class (or be one of the builtin types, like
+    /// `i64` or `String`) by the time data filtering is used.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use oso::ClassBuilder;
+    ///
+    /// #[derive(Default)]
+    /// struct MyClass {
+    ///     id: i64,
+    ///     name: String,
+    /// };
+    ///
+    /// let class = ClassBuilder::<MyClass>::with_default()
+    ///     .add_field::<i64>("id")
+    ///     .add_field::<String>("name")
+    ///     .build();
+    /// ```
+    pub fn add_field<F: 'static>(mut self, name: &'static str) -> Self {
+        self.class.fields.insert(
+            name,
+            FieldType::Base {
+                type_id: TypeId::of::<F>(),
+                type_name: std::any::type_name::<F>(),
+            },
+        );
+        self
+    }
+
+    /// Declare a relation to another class, for use with data filtering.
+    ///
+    /// See [`Relation`] for an example.
+    pub fn add_relation(mut self, name: &'static str, relation: Relation) -> Self {
+        self.class
+            .fields
+            .insert(name, FieldType::Relation(relation));
+        self
+    }
+
     /// Set the name of the polar class.
     pub fn name(mut self, name: &str) -> Self {
         self.class.name = name.to_string();
         self
     }
 
+    /// Declare the class as a subtype of the class registered for `P`, so that instances of
+    /// `T` match `P` specializers in rules and `matches P` checks. Rules specialized on `T` are
+    /// more specific than rules specialized on `P`.
+    ///
+    /// A class may extend several supertypes. Their specificity follows the C3 linearization of
+    /// the hierarchy, as in Python: a class is more specific than its supertypes, which are
+    /// ordered as declared. Loading a policy fails if the supertypes are cyclic or can't be
+    /// ordered consistently.
+    ///
+    /// Only the type relationship is declared: attributes and methods of `P` aren't inherited.
+    /// `P` must be registered before a policy is loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use oso::{Oso, PolarClass};
+    ///
+    /// #[derive(Clone, PolarClass)]
+    /// struct User;
+    ///
+    /// #[derive(Clone, PolarClass)]
+    /// struct Admin;
+    ///
+    /// let mut oso = Oso::new();
+    /// oso.register_class(User::get_polar_class()).unwrap();
+    /// oso.register_class(Admin::get_polar_class_builder().extends::<User>().build())
+    ///     .unwrap();
+    /// oso.load_str(r#"allow(_: User, "read", _);"#).unwrap();
+    /// assert!(oso.is_allowed(Admin, "read", "doc").unwrap());
+    /// ```
+    pub fn extends<P: 'static>(mut self) -> Self {
+        self.class.supertypes.push(Supertype::Type {
+            type_id: TypeId::of::<P>(),
+            type_name: std::any::type_name::<P>(),
+        });
+        self
+    }
+
+    /// Declare the class as a subtype of the class registered as `name`, like
+    /// [`ClassBuilder::extends`]. Useful for naming a trait that several classes implement,
+    /// backed by a marker type registered under that name.
+    pub fn implements(mut self, name: &str) -> Self {
+        self.class
+            .supertypes
+            .push(Supertype::Name(name.to_string()));
+        self
+    }
+
     /// Add a RegisterHook on the class that will register the given constant once the class is registered.
     pub fn add_constant<V: crate::ToPolar + Clone + Send + Sync + 'static>(
         mut self,
@@ -394,6 +763,51 @@ where
         self.class
             .instance_methods
             .insert(name, InstanceMethod::new(f));
+        self.class.method_params.remove(name);
+        self
+    }
+
+    /// Add a method for polar method calls that may pass arguments by keyword, like
+    /// `foo.greet("hello", name: "alice")`. `params` are the names of the arguments of `f`
+    /// after the receiver, in order. Arguments after the positional ones must all be passed by
+    /// keyword.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use oso::ClassBuilder;
+    ///
+    /// #[derive(Default)]
+    /// struct Foo;
+    ///
+    /// let class = ClassBuilder::<Foo>::with_default()
+    ///     .add_method_with_kwargs(
+    ///         "greet",
+    ///         &["greeting", "name"],
+    ///         |_: &Foo, greeting: String, name: String| format!("{}, {}", greeting, name),
+    ///     )
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If there isn't one name in `params` for each argument of `f` after the receiver.
+    pub fn add_method_with_kwargs<F, Args, R>(
+        mut self,
+        name: &'static str,
+        params: &[&'static str],
+        f: F,
+    ) -> Self
+    where
+        Args: FromPolarList,
+        F: Method<T, Args, Result = R>,
+        R: ToPolarResult + 'static,
+    {
+        assert_params(&self.class.name, name, params, Args::arity());
+        self.class
+            .instance_methods
+            .insert(name, InstanceMethod::new(f));
+        self.class.method_params.insert(name, params.into());
         self
     }
 
@@ -415,6 +829,66 @@ where
         self
     }
 
+    /// Add a method that returns a future, for polar method calls like `foo.fetch(1)`.
+    ///
+    /// The future is awaited when the method is called by an [`AsyncQuery`](crate::AsyncQuery).
+    /// Calling the method from a synchronous [`Query`](crate::Query) is an error. As with
+    /// [`ClassBuilder::add_async_attribute_getter()`], the future may not borrow from the
+    /// instance.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use oso::ClassBuilder;
+    ///
+    /// #[derive(Default)]
+    /// struct Repo {
+    ///     id: u64,
+    /// };
+    ///
+    /// async fn has_role(repo_id: u64, user: String, role: String) -> bool {
+    ///     repo_id == 1 && user == "alice" && role == "admin"
+    /// }
+    ///
+    /// let class = ClassBuilder::<Repo>::with_default()
+    ///     .add_async_method("has_role", |repo: &Repo, user: String, role: String| {
+    ///         has_role(repo.id, user, role)
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn add_async_method<F, Args>(mut self, name: &'static str, f: F) -> Self
+    where
+        Args: FromPolarList,
+        F: Method<T, Args>,
+        F::Result: std::future::Future + 'static,
+        <F::Result as std::future::Future>::Output: ToPolarResult,
+    {
+        self.class
+            .async_methods
+            .insert(name, AsyncInstanceMethod::new(f));
+        self
+    }
+
+    /// An async method whose future resolves to multiple values. Every element in the iterator
+    /// it resolves to will be a separate polar return value.
+    pub fn add_async_iterator_method<F, Args, I>(mut self, name: &'static str, f: F) -> Self
+    where
+        Args: FromPolarList,
+        F: Method<T, Args>,
+        F::Result: std::future::Future + 'static,
+        <F::Result as std::future::Future>::Output: IntoIterator<Item = I>,
+        <<F::Result as std::future::Future>::Output as IntoIterator>::IntoIter:
+            Iterator<Item = I> + Clone + Send + Sync + 'static,
+        I: ToPolarResult + 'static,
+    {
+        self.class
+            .async_methods
+            .insert(name, AsyncInstanceMethod::new_iterator(f));
+        self
+    }
+
     /// A method that's called on the type instead of an instance.
     /// eg `Foo.pi`
     pub fn add_class_method<F, Args, R>(mut self, name: &'static str, f: F) -> Self
@@ -499,6 +973,9 @@ impl Instance {
         let attr = self
             .class(host)
             .and_then(|c| {
+                if c.has_async(name) {
+                    return Err(self.async_only(name, host));
+                }
                 c.attributes.get(name).ok_or_else(|| {
                     InvalidCallError::AttributeNotFound {
                         attribute_name: name.to_owned(),
@@ -520,19 +997,72 @@ impl Instance {
         args: Vec<PolarValue>,
         host: &mut Host,
     ) -> crate::Result<PolarValue> {
-        tracing::trace!({method = %name, ?args}, "call");
-        let method = self.class(host).and_then(|c| {
-            c.get_method(name).ok_or_else(|| {
-                InvalidCallError::MethodNotFound {
+        self.call_with_kwargs(name, args, Kwargs::new(), host)
+    }
+
+    /// Call the named method on the instance with positional arguments `args` and keyword
+    /// arguments `kwargs`, which the method must accept (see
+    /// [`ClassBuilder::add_method_with_kwargs`]).
+    pub(crate) fn call_with_kwargs(
+        &self,
+        name: &str,
+        args: Vec<PolarValue>,
+        kwargs: Kwargs,
+        host: &mut Host,
+    ) -> crate::Result<PolarValue> {
+        tracing::trace!({method = %name, ?args, ?kwargs}, "call");
+        let (method, args) = self.class(host).and_then(|c| {
+            if c.has_async(name) {
+                return Err(self.async_only(name, host));
+            }
+            let method = c.get_method(name).ok_or_else(|| {
+                crate::OsoError::from(InvalidCallError::MethodNotFound {
                     method_name: name.to_owned(),
                     type_name: self.name(host).to_owned(),
-                }
-                .into()
-            })
+                })
+            })?;
+            let callee = || format!("{}.{}", self.name(host), name);
+            let params = c.method_params.get(name).map(|params| &params[..]);
+            Ok((method, bind_kwargs(callee, params, args, kwargs)?))
         })?;
         method.invoke(self, args, host)
     }
 
+    /// Start a call to the named async attribute (if `args` is `None`) or method on the
+    /// instance.
+    ///
+    /// Returns `None` if the class has no async attribute or method with that name.
+    pub(crate) fn call_async(
+        &self,
+        name: &str,
+        args: Option<Vec<PolarValue>>,
+        host: &mut Host,
+    ) -> Option<crate::Result<PolarFuture>> {
+        let class = self.class(host).ok()?;
+        match args {
+            None => {
+                let attr = class.async_attributes.get(name)?.clone();
+                tracing::trace!({ method = %name }, "get_attr_async");
+                Some(attr.invoke(self, host))
+            }
+            Some(args) => {
+                let method = class.async_methods.get(name)?.clone();
+                tracing::trace!({method = %name, ?args}, "call_async");
+                Some(method.invoke(self, args, host))
+            }
+        }
+    }
+
+    fn async_only(&self, name: &str, host: &Host) -> OsoError {
+        OsoError::Custom {
+            message: format!(
+                "{}.{} is async and can only be used from an async query, e.g. Oso::query_async",
+                self.name(host),
+                name
+            ),
+        }
+    }
+
     pub fn as_iter(&self, host: &Host) -> crate::Result<crate::host::PolarIterator> {
         self.class(host).and_then(|c| (c.into_iter)(host, self))
     }
@@ -544,6 +1074,13 @@ impl Instance {
             .and_then(|class| class.equals(host, self, other))
     }
 
+    /// Order the `instance` of self and the instance of `other`, if they can be ordered.
+    pub fn compare(&self, other: &Self, host: &Host) -> crate::Result<Option<Ordering>> {
+        tracing::trace!("compare");
+        self.class(host)
+            .and_then(|class| class.compare(host, self, other))
+    }
+
     /// Attempt to downcast the inner type of the instance to a reference to the type `T`
     /// This should be the _only_ place using downcast to avoid mistakes.
     ///
@@ -589,4 +1126,30 @@ mod test {
         assert!(foo_instance.instance_of(&foo_class));
         assert!(!foo_instance.instance_of(&bar_class));
     }
+
+    #[test]
+    #[should_panic(
+        expected = "Foo constructor needs a parameter name for each of its 2 arguments, but got 1"
+    )]
+    fn test_constructor_params_match_arity() {
+        struct Foo;
+        assert_eq!(<()>::arity(), 0);
+        assert_eq!(<(i64, String)>::arity(), 2);
+        Class::builder::<Foo>()
+            .name("Foo")
+            .set_constructor_with_kwargs(&["a"], |_: i64, _: String| Foo);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Foo greet needs a parameter name for each of its 1 arguments, but got 2"
+    )]
+    fn test_method_params_match_arity() {
+        struct Foo;
+        Class::builder::<Foo>().name("Foo").add_method_with_kwargs(
+            "greet",
+            &["greeting", "name"],
+            |_: &Foo, _: String| true,
+        );
+    }
 }
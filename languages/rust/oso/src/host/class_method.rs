@@ -1,4 +1,6 @@
 //! Wrapper structs for the generic `Function` and `Method` traits
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 
 use crate::host::from_polar::FromPolarList;
@@ -16,6 +18,10 @@ type TypeErasedFunction<R> = Arc<dyn Fn(Vec<PolarValue>) -> crate::Result<R> + S
 type TypeErasedMethod<R> =
     Arc<dyn Fn(&Instance, Vec<PolarValue>, &mut Host) -> crate::Result<R> + Send + Sync>;
 
+/// The result of calling an async method or attribute getter, awaited by
+/// [`AsyncQuery`](crate::AsyncQuery).
+pub type PolarFuture = Pin<Box<dyn Future<Output = crate::Result<PolarValue>>>>;
+
 type RegisterMethod = Arc<dyn Fn(&mut crate::Oso) -> crate::Result<()> + Send + Sync + 'static>;
 
 #[derive(Clone)]
@@ -80,6 +86,36 @@ impl AttributeGetter {
     }
 }
 
+type AsyncAttributeGetterMethod =
+    Arc<dyn Fn(&Instance, &mut Host) -> crate::Result<PolarFuture> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct AsyncAttributeGetter(AsyncAttributeGetterMethod);
+
+impl AsyncAttributeGetter {
+    pub fn new<T, F, Fut>(f: F) -> Self
+    where
+        T: 'static,
+        F: Fn(&T) -> Fut + Send + Sync + 'static,
+        Fut: Future + 'static,
+        Fut::Output: ToPolarResult,
+    {
+        Self(Arc::new(move |receiver, host: &mut Host| {
+            let receiver = receiver
+                .downcast(Some(host))
+                .map_err(|e| e.invariant().into());
+            receiver.map(|receiver| {
+                let future = f(receiver);
+                Box::pin(async move { future.await.to_polar_result() }) as PolarFuture
+            })
+        }))
+    }
+
+    pub fn invoke(&self, receiver: &Instance, host: &mut Host) -> crate::Result<PolarFuture> {
+        self.0(receiver, host)
+    }
+}
+
 #[derive(Clone)]
 pub struct InstanceMethod(TypeErasedMethod<PolarValue>);
 
@@ -156,6 +192,73 @@ impl InstanceMethod {
     }
 }
 
+#[derive(Clone)]
+pub struct AsyncInstanceMethod(TypeErasedMethod<PolarFuture>);
+
+impl AsyncInstanceMethod {
+    pub fn new<T, F, Args>(f: F) -> Self
+    where
+        Args: FromPolarList,
+        F: Method<T, Args>,
+        F::Result: Future + 'static,
+        <F::Result as Future>::Output: ToPolarResult,
+        T: 'static,
+    {
+        Self(Arc::new(
+            move |receiver: &Instance, args: Vec<PolarValue>, host: &mut Host| {
+                let receiver = receiver
+                    .downcast(Some(host))
+                    .map_err(|e| e.invariant().into());
+
+                let args = Args::from_polar_list(&args);
+
+                join(receiver, args).map(|(receiver, args)| {
+                    let future = f.invoke(receiver, args);
+                    Box::pin(async move { future.await.to_polar_result() }) as PolarFuture
+                })
+            },
+        ))
+    }
+
+    pub fn new_iterator<T, F, Args, I>(f: F) -> Self
+    where
+        Args: FromPolarList,
+        F: Method<T, Args>,
+        F::Result: Future + 'static,
+        <F::Result as Future>::Output: IntoIterator<Item = I>,
+        <<F::Result as Future>::Output as IntoIterator>::IntoIter:
+            Iterator<Item = I> + Clone + Send + Sync + 'static,
+        I: ToPolarResult,
+        T: 'static,
+    {
+        Self(Arc::new(
+            move |receiver: &Instance, args: Vec<PolarValue>, host: &mut Host| {
+                let receiver = receiver
+                    .downcast(Some(host))
+                    .map_err(|e| e.invariant().into());
+
+                let args = Args::from_polar_list(&args);
+
+                join(receiver, args).map(|(receiver, args)| {
+                    let future = f.invoke(receiver, args);
+                    Box::pin(
+                        async move { Ok(PolarIterator::new(future.await.into_iter()).to_polar()) },
+                    ) as PolarFuture
+                })
+            },
+        ))
+    }
+
+    pub fn invoke(
+        &self,
+        receiver: &Instance,
+        args: Vec<PolarValue>,
+        host: &mut Host,
+    ) -> crate::Result<PolarFuture> {
+        self.0(receiver, args, host)
+    }
+}
+
 #[derive(Clone)]
 pub struct ClassMethod(TypeErasedFunction<PolarValue>);
 
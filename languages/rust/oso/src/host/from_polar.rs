@@ -4,6 +4,11 @@
 
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
 use std::hash::Hash;
+use std::time::SystemTime;
+
+use polar_core::temporal::{Duration, Timestamp};
+#[cfg(feature = "bignum")]
+use polar_core::terms::{BigDecimal, BigInt};
 
 use impl_trait_for_tuples::*;
 
@@ -106,6 +111,63 @@ impl FromPolar for bool {
     }
 }
 
+#[cfg(feature = "bignum")]
+impl FromPolar for BigInt {
+    fn from_polar(val: PolarValue) -> crate::Result<Self> {
+        match val {
+            PolarValue::Integer(i) => Ok(i.into()),
+            PolarValue::BigInteger(i) => Ok(i),
+            _ => Err(TypeError::expected("Integer").user()),
+        }
+    }
+}
+
+/// Integers are converted to decimals too.
+#[cfg(feature = "bignum")]
+impl FromPolar for BigDecimal {
+    fn from_polar(val: PolarValue) -> crate::Result<Self> {
+        match val {
+            PolarValue::Integer(i) => Ok(i.into()),
+            PolarValue::BigInteger(i) => Ok(i.into()),
+            PolarValue::Decimal(d) => Ok(d),
+            _ => Err(TypeError::expected("Decimal").user()),
+        }
+    }
+}
+
+impl FromPolar for Timestamp {
+    fn from_polar(val: PolarValue) -> crate::Result<Self> {
+        if let PolarValue::Timestamp(t) = val {
+            Ok(t)
+        } else {
+            Err(TypeError::expected("Timestamp").user())
+        }
+    }
+}
+
+impl FromPolar for Duration {
+    fn from_polar(val: PolarValue) -> crate::Result<Self> {
+        if let PolarValue::Duration(d) = val {
+            Ok(d)
+        } else {
+            Err(TypeError::expected("Duration").user())
+        }
+    }
+}
+
+impl FromPolar for SystemTime {
+    fn from_polar(val: PolarValue) -> crate::Result<Self> {
+        SystemTime::try_from(Timestamp::from_polar(val)?).map_err(|_| crate::OsoError::FromPolar)
+    }
+}
+
+/// Fails for negative durations.
+impl FromPolar for std::time::Duration {
+    fn from_polar(val: PolarValue) -> crate::Result<Self> {
+        Self::try_from(Duration::from_polar(val)?).map_err(|_| crate::OsoError::FromPolar)
+    }
+}
+
 impl<T: FromPolar> FromPolar for HashMap<String, T> {
     fn from_polar(val: PolarValue) -> crate::Result<Self> {
         if let PolarValue::Map(map) = val {
@@ -264,6 +326,8 @@ try_from_polar!(i64);
 try_from_polar!(f64);
 try_from_polar!(String);
 try_from_polar!(bool);
+try_from_polar!(SystemTime);
+try_from_polar!(std::time::Duration);
 
 impl<T: FromPolar> TryFrom<PolarValue> for HashMap<String, T> {
     type Error = crate::OsoError;
@@ -287,6 +351,11 @@ mod private {
 }
 
 pub trait FromPolarList: private::Sealed {
+    /// The number of values in the list.
+    fn arity() -> usize
+    where
+        Self: Sized;
+
     fn from_polar_list(values: &[PolarValue]) -> crate::Result<Self>
     where
         Self: Sized;
@@ -301,8 +370,14 @@ impl FromPolar for Instance {
             PolarValue::Boolean(b) => Instance::new(b),
             PolarValue::Integer(i) => Instance::new(i),
             PolarValue::Float(f) => Instance::new(f),
+            #[cfg(feature = "bignum")]
+            PolarValue::BigInteger(i) => Instance::new(i),
+            #[cfg(feature = "bignum")]
+            PolarValue::Decimal(d) => Instance::new(d),
             PolarValue::List(v) => Instance::new(v),
             PolarValue::String(s) => Instance::new(s),
+            PolarValue::Timestamp(t) => Instance::new(t),
+            PolarValue::Duration(d) => Instance::new(d),
             PolarValue::Map(d) => Instance::new(d),
             PolarValue::Instance(instance) => instance,
             v => {
@@ -317,6 +392,12 @@ impl FromPolar for Instance {
 #[impl_for_tuples(16)]
 #[tuple_types_custom_trait_bound(FromPolar)]
 impl FromPolarList for Tuple {
+    fn arity() -> usize {
+        let mut arity = 0;
+        for_tuples!( #( arity += 1; )* );
+        arity
+    }
+
     fn from_polar_list(values: &[PolarValue]) -> crate::Result<Self> {
         let mut iter = values.iter();
         let result = Ok((for_tuples!(
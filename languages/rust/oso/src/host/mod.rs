@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
+use crate::data_filtering::{serialize_fields, DynDataAdapter, FieldType, RelationKind, Types};
 use crate::errors::OsoError;
 use crate::Polar;
 
@@ -11,9 +12,12 @@ mod method;
 mod to_polar;
 mod value;
 
+pub(crate) use class::Kwargs;
+use class::Supertype;
 pub use class::{Class, ClassBuilder, Instance};
+pub(crate) use class_method::PolarFuture;
 pub use from_polar::{FromPolar, FromPolarList};
-use polar_core::terms::{Operator, Symbol};
+use polar_core::terms::{Operator, Symbol, Term, Value};
 pub use to_polar::{PolarIterator, ToPolar, ToPolarList};
 pub use value::PolarValue;
 
@@ -47,16 +51,24 @@ pub struct Host {
     /// class name it is registered as
     class_names: HashMap<std::any::TypeId, String>,
 
+    /// Map from class names to the class IDs of the classes registered as constants
+    class_ids: HashMap<String, u64>,
+
     pub accept_expression: bool,
+
+    /// Adapter used to build and execute data filtering queries
+    pub(crate) adapter: Option<Arc<dyn DynDataAdapter>>,
 }
 
 impl Host {
     pub fn new(polar: Arc<Polar>) -> Self {
         let mut host = Self {
             class_names: HashMap::new(),
+            class_ids: HashMap::new(),
             classes: HashMap::new(),
             instances: HashMap::new(),
             accept_expression: false,
+            adapter: None,
             polar,
         };
         let type_class = metaclass();
@@ -103,23 +115,100 @@ impl Host {
             .entry(class.type_id)
             .or_insert_with(|| class.clone());
 
-        if self.classes.contains_key(&name) {
-            Err(OsoError::DuplicateClassError { name })
-        } else {
-            self.class_names.insert(class.type_id, name.clone());
-            self.classes.insert(name.clone(), class);
-            Ok(name)
+        match self.classes.get(&name) {
+            Some(existing) if !crate::builtins::is_shadowable(existing) => {
+                return Err(OsoError::DuplicateClassError { name });
+            }
+            Some(existing) => {
+                self.class_names.remove(&existing.type_id);
+            }
+            None => {}
         }
+        self.class_names.insert(class.type_id, name.clone());
+        self.classes.insert(name.clone(), class);
+        Ok(name)
     }
 
-    /// Register an MRO list for every registered class.
-    /// Since inheritance is not supported, all lists are empty.
-    pub fn register_mros(&self) -> crate::Result<()> {
-        for name in self.classes.keys() {
-            if name != "oso::host::Class" {
-                self.polar.register_mro(Symbol(name.clone()), vec![])?;
+    /// Convert the registered class `name` to a term to register as a constant. Its instance ID
+    /// is its class ID, which identifies it in MROs.
+    pub fn class_term(&mut self, name: &str) -> crate::Result<Term> {
+        let class = self.get_class(name)?.clone();
+        let mut term = PolarValue::Instance(class::Instance::new(class)).to_term(self);
+        if let Value::ExternalInstance(instance) = term.mut_value() {
+            instance.class_id = Some(instance.instance_id);
+            self.class_ids.insert(name.to_owned(), instance.instance_id);
+        }
+        Ok(term)
+    }
+
+    /// The method resolution order of the registered class `name`: the C3 linearization of the
+    /// class and its declared supertypes, as in Python. A class comes before all of its
+    /// supertypes, and supertypes keep the order they were declared in. E.g., if `D` extends `B`
+    /// and then `C`, which both extend `A`, the MRO of `D` is `[D, B, C, A]`.
+    pub fn mro(&self, name: &str) -> crate::Result<Vec<String>> {
+        self.linearize(name, &mut vec![])
+    }
+
+    /// The C3 linearization of `name`. `visiting` holds the classes being linearized, to detect
+    /// cycles.
+    fn linearize(&self, name: &str, visiting: &mut Vec<String>) -> crate::Result<Vec<String>> {
+        let inconsistent = || OsoError::InconsistentSupertypes {
+            name: name.to_owned(),
+        };
+        if visiting.iter().any(|visited| visited == name) {
+            return Err(inconsistent());
+        }
+
+        let mut supertypes: Vec<String> = vec![];
+        for supertype in &self.get_class(name)?.supertypes {
+            let supertype = match supertype {
+                Supertype::Type { type_id, type_name } => {
+                    self.class_names.get(type_id).cloned().ok_or_else(|| {
+                        OsoError::MissingClassError {
+                            name: type_name.to_string(),
+                        }
+                    })?
+                }
+                Supertype::Name(name) => name.clone(),
+            };
+            if !supertypes.contains(&supertype) {
+                supertypes.push(supertype);
             }
         }
+
+        visiting.push(name.to_owned());
+        let mut sequences = supertypes
+            .iter()
+            .map(|supertype| self.linearize(supertype, visiting))
+            .collect::<crate::Result<Vec<_>>>()?;
+        visiting.pop();
+        sequences.push(supertypes);
+
+        let mut mro = vec![name.to_owned()];
+        mro.extend(merge(sequences).ok_or_else(inconsistent)?);
+        Ok(mro)
+    }
+
+    /// Check whether the registered class `left_tag` is `right_tag` or one of its subtypes.
+    pub fn is_subclass(&self, left_tag: &str, right_tag: &str) -> crate::Result<bool> {
+        Ok(left_tag == right_tag || self.mro(left_tag)?.iter().any(|name| name == right_tag))
+    }
+
+    /// Register an MRO list of class IDs for every class registered as a constant.
+    pub fn register_mros(&self) -> crate::Result<()> {
+        for name in self.class_ids.keys() {
+            let mro = self
+                .mro(name)?
+                .iter()
+                .map(|name| {
+                    self.class_ids
+                        .get(name)
+                        .copied()
+                        .ok_or_else(|| OsoError::MissingClassError { name: name.clone() })
+                })
+                .collect::<crate::Result<_>>()?;
+            self.polar.register_mro(Symbol(name.clone()), mro)?;
+        }
         Ok(())
     }
 
@@ -158,12 +247,12 @@ impl Host {
         &mut self,
         name: &str,
         fields: Vec<PolarValue>,
+        kwargs: Kwargs,
         id: u64,
     ) -> crate::Result<()> {
         let class = self.get_class(name)?.clone();
         debug_assert!(self.instances.get(&id).is_none());
-        let fields = fields;
-        let instance = class.init(fields)?;
+        let instance = class.init_with_kwargs(fields, kwargs)?;
         self.cache_instance(instance, Some(id));
         Ok(())
     }
@@ -173,31 +262,126 @@ impl Host {
             PolarValue::Instance(instance) => {
                 let class = self.get_class(class_tag)?;
                 instance.instance_of(class)
+                    || match instance.class(self) {
+                        Ok(class) => self.is_subclass(&class.name, class_tag)?,
+                        Err(_) => false,
+                    }
             }
             PolarValue::Boolean(_) => class_tag == "Boolean",
             PolarValue::Map(_) => class_tag == "Dictionary",
             PolarValue::List(_) => class_tag == "List",
             PolarValue::Integer(_) => class_tag == "Integer",
             PolarValue::Float(_) => class_tag == "Float",
+            #[cfg(feature = "bignum")]
+            PolarValue::BigInteger(_) => class_tag == "Integer",
+            #[cfg(feature = "bignum")]
+            PolarValue::Decimal(_) => class_tag == "Decimal",
             PolarValue::String(_) => class_tag == "String",
+            PolarValue::Timestamp(_) => class_tag == "Timestamp",
+            PolarValue::Duration(_) => class_tag == "Duration",
             _ => false,
         };
         Ok(res)
     }
 
-    pub fn is_subspecializer(&self, _id: u64, _left_tag: &str, _right_tag: &str) -> bool {
-        // Rust has no notion of inheritance, so there are no subspecializers.
-        false
+    /// Starting from the class `base_tag`, follow the declared fields in `path` and check
+    /// whether the resulting type is `class_tag`.
+    pub fn isa_with_path(
+        &self,
+        base_tag: &str,
+        path: &[Term],
+        class_tag: &str,
+    ) -> crate::Result<bool> {
+        let mut tag = base_tag.to_owned();
+        for field in path {
+            let field = match field.value() {
+                Value::String(field) => field,
+                _ => return lazy_error!("invalid field in path: {}", field),
+            };
+            let class = self.get_class(&tag)?;
+            tag = match class.fields.get(field.as_str()) {
+                Some(FieldType::Base { type_id, .. }) => {
+                    self.get_class_by_type_id(*type_id)?.name.clone()
+                }
+                Some(FieldType::Relation(relation)) => match relation.kind {
+                    RelationKind::One => relation.other_type.clone(),
+                    RelationKind::Many => "List".to_owned(),
+                },
+                None => return lazy_error!("No field {} on {}", field, tag),
+            };
+        }
+        Ok(tag == class_tag)
+    }
+
+    /// Collect the declared fields of every registered class for data filtering.
+    pub fn serialize_types(&self) -> crate::Result<Types> {
+        self.classes
+            .iter()
+            .filter(|(name, _)| *name != "oso::host::Class")
+            .map(|(name, class)| Ok((name.clone(), serialize_fields(&class.fields, self)?)))
+            .collect()
+    }
+
+    /// Check whether the class `left_tag` is a more specific specializer than `right_tag` for
+    /// the instance `id`, i.e., it comes first in the MRO of the instance's class.
+    pub fn is_subspecializer(
+        &self,
+        id: u64,
+        left_tag: &str,
+        right_tag: &str,
+    ) -> crate::Result<bool> {
+        let instance = self.get_instance(id)?;
+        let mro = self.mro(&instance.class(self)?.name)?;
+        let position = |tag| mro.iter().position(|name| name == tag);
+        Ok(matches!(
+            (position(left_tag), position(right_tag)),
+            (Some(left), Some(right)) if left < right
+        ))
     }
 
     pub fn operator(&self, op: Operator, args: [class::Instance; 2]) -> crate::Result<bool> {
+        use std::cmp::Ordering::*;
+        let ordered = |orderings: &[std::cmp::Ordering]| -> crate::Result<bool> {
+            let ordering = args[0].compare(&args[1], self)?;
+            Ok(ordering.is_some_and(|o| orderings.contains(&o)))
+        };
         match op {
             Operator::Eq => args[0].equals(&args[1], self),
+            Operator::Neq => args[0].equals(&args[1], self).map(|eq| !eq),
+            Operator::Lt => ordered(&[Less]),
+            Operator::Leq => ordered(&[Less, Equal]),
+            Operator::Gt => ordered(&[Greater]),
+            Operator::Geq => ordered(&[Greater, Equal]),
             _ => Err(OsoError::UnimplementedOperation {
-                operation: String::from("comparison operators"),
+                operation: format!("{} operators", op),
             }),
         }
-        // Operators are not supported
-        // TODO (dhatch): Implement.
+    }
+}
+
+/// The C3 merge of `sequences`: repeatedly take the first head of a sequence that isn't in the
+/// tail of any sequence. `None` if no such head exists.
+fn merge(mut sequences: Vec<Vec<String>>) -> Option<Vec<String>> {
+    let mut merged = vec![];
+    loop {
+        sequences.retain(|sequence| !sequence.is_empty());
+        if sequences.is_empty() {
+            return Some(merged);
+        }
+        let head = sequences
+            .iter()
+            .map(|sequence| &sequence[0])
+            .find(|head| {
+                sequences
+                    .iter()
+                    .all(|sequence| !sequence[1..].contains(head))
+            })?
+            .clone();
+        for sequence in &mut sequences {
+            if sequence[0] == head {
+                sequence.remove(0);
+            }
+        }
+        merged.push(head);
     }
 }
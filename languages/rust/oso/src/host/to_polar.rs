@@ -6,6 +6,11 @@
 use impl_trait_for_tuples::*;
 
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::time::SystemTime;
+
+use polar_core::temporal::{Duration, Timestamp};
+#[cfg(feature = "bignum")]
+use polar_core::terms::{BigDecimal, BigInt};
 
 use super::DEFAULT_CLASSES;
 use crate::PolarValue;
@@ -210,6 +215,51 @@ impl ToPolar for String {
     }
 }
 
+/// Integers that fit in an `i64` are converted to `PolarValue::Integer`.
+#[cfg(feature = "bignum")]
+impl ToPolar for BigInt {
+    fn to_polar(self) -> PolarValue {
+        match i64::try_from(&self) {
+            Ok(i) => PolarValue::Integer(i),
+            Err(_) => PolarValue::BigInteger(self),
+        }
+    }
+}
+
+#[cfg(feature = "bignum")]
+impl ToPolar for BigDecimal {
+    fn to_polar(self) -> PolarValue {
+        PolarValue::Decimal(self)
+    }
+}
+
+impl ToPolar for Timestamp {
+    fn to_polar(self) -> PolarValue {
+        PolarValue::Timestamp(self)
+    }
+}
+
+impl ToPolar for Duration {
+    fn to_polar(self) -> PolarValue {
+        PolarValue::Duration(self)
+    }
+}
+
+impl ToPolar for SystemTime {
+    fn to_polar(self) -> PolarValue {
+        PolarValue::Timestamp(
+            Timestamp::try_from(self).expect("a SystemTime is in the range of a Timestamp"),
+        )
+    }
+}
+
+/// Durations longer than [`Duration::MAX`] are truncated to it.
+impl ToPolar for std::time::Duration {
+    fn to_polar(self) -> PolarValue {
+        PolarValue::Duration(Duration::try_from(self).unwrap_or(Duration::MAX))
+    }
+}
+
 impl<'a> ToPolar for &'a str {
     fn to_polar(self) -> PolarValue {
         PolarValue::String(self.to_string())
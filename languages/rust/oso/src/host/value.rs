@@ -11,12 +11,23 @@ use crate::host::{Host, Instance};
 /// Any other types can be wrapped using `PolarValue::new_from_instance`.
 /// If the instance has a registered `Class`, then this can be used
 /// from the policy too.
+///
+/// The `BigInteger` and `Decimal` variants only exist with the `bignum` feature, so matches on
+/// a `PolarValue` need a wildcard arm.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum PolarValue {
     Integer(i64),
     Float(f64),
+    /// An integer outside the range of an `i64`.
+    #[cfg(feature = "bignum")]
+    BigInteger(BigInt),
+    #[cfg(feature = "bignum")]
+    Decimal(BigDecimal),
     String(String),
     Boolean(bool),
+    Timestamp(Timestamp),
+    Duration(Duration),
     Map(HashMap<String, PolarValue>),
     List(Vec<PolarValue>),
     Variable(String),
@@ -29,9 +40,15 @@ impl PartialEq for PolarValue {
             (PolarValue::Boolean(b1), PolarValue::Boolean(b2)) => b1 == b2,
             (PolarValue::Float(f1), PolarValue::Float(f2)) => f1 == f2,
             (PolarValue::Integer(i1), PolarValue::Integer(i2)) => i1 == i2,
+            #[cfg(feature = "bignum")]
+            (PolarValue::BigInteger(i1), PolarValue::BigInteger(i2)) => i1 == i2,
+            #[cfg(feature = "bignum")]
+            (PolarValue::Decimal(d1), PolarValue::Decimal(d2)) => d1 == d2,
             (PolarValue::List(l1), PolarValue::List(l2)) => l1 == l2,
             (PolarValue::Map(m1), PolarValue::Map(m2)) => m1 == m2,
             (PolarValue::String(s1), PolarValue::String(s2)) => s1 == s2,
+            (PolarValue::Timestamp(t1), PolarValue::Timestamp(t2)) => t1 == t2,
+            (PolarValue::Duration(d1), PolarValue::Duration(d2)) => d1 == d2,
             _ => false,
         }
     }
@@ -50,8 +67,14 @@ impl PolarValue {
         let val = match term.value() {
             Value::Number(Numeric::Integer(i)) => PolarValue::Integer(*i),
             Value::Number(Numeric::Float(f)) => PolarValue::Float(*f),
+            #[cfg(feature = "bignum")]
+            Value::Number(Numeric::BigInteger(i)) => PolarValue::BigInteger(i.clone()),
+            #[cfg(feature = "bignum")]
+            Value::Number(Numeric::Decimal(d)) => PolarValue::Decimal(d.clone()),
             Value::String(s) => PolarValue::String(s.clone()),
             Value::Boolean(b) => PolarValue::Boolean(*b),
+            Value::Timestamp(t) => PolarValue::Timestamp(*t),
+            Value::Duration(d) => PolarValue::Duration(*d),
             Value::Dictionary(dict) => {
                 let mut map = HashMap::new();
                 for (k, v) in &dict.fields {
@@ -95,8 +118,14 @@ This may mean you performed an operation in your policy over an unbound variable
         let value = match self {
             PolarValue::Integer(i) => Value::Number(Numeric::Integer(*i)),
             PolarValue::Float(f) => Value::Number(Numeric::Float(*f)),
+            #[cfg(feature = "bignum")]
+            PolarValue::BigInteger(i) => Value::Number(Numeric::from(i.clone())),
+            #[cfg(feature = "bignum")]
+            PolarValue::Decimal(d) => Value::Number(Numeric::Decimal(d.clone())),
             PolarValue::String(s) => Value::String(s.clone()),
             PolarValue::Boolean(b) => Value::Boolean(*b),
+            PolarValue::Timestamp(t) => Value::Timestamp(*t),
+            PolarValue::Duration(d) => Value::Duration(*d),
             PolarValue::Map(map) => {
                 let mut dict = Dictionary::new();
                 for (k, v) in map {
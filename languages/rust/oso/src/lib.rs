@@ -77,16 +77,35 @@
 pub mod macros;
 
 pub(crate) mod builtins;
+pub mod data_filtering;
+pub mod decision_cache;
 pub mod errors;
 mod extras;
 mod host;
 mod oso;
+pub mod policy_test;
 mod query;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 pub use crate::oso::{Action, Oso};
+pub use data_filtering::{DataAdapter, Relation};
 pub use errors::{OsoError, Result};
 pub use host::{Class, ClassBuilder, FromPolar, FromPolarList, PolarValue, ToPolar, ToPolarList};
-pub use query::{Query, ResultSet};
+pub use query::{AsyncQuery, Query, ResultSet};
+
+/// Limits on how long and how far a query may run, see [`Oso::query_with_options()`].
+pub use polar_core::query_options::{CancellationHandle, QueryOptions};
+
+/// Timestamp and duration values, and the clocks that `now()` reads, see [`Oso::set_clock()`].
+pub use polar_core::temporal::{Clock, Duration, FixedClock, SystemClock, Timestamp};
+
+/// Big integers and decimals, with the `bignum` feature.
+#[cfg(feature = "bignum")]
+pub use polar_core::terms::{BigDecimal, BigInt};
+
+/// Explanations of authorization decisions, see [`Oso::explain_allowed()`].
+pub use polar_core::explain;
 
 use polar_core::polar::Polar;
 
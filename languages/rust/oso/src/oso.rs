@@ -1,6 +1,11 @@
 //! Communicate with the Polar virtual machine: load rules, make queries, etc/
+use polar_core::events::ResultEvent;
+use polar_core::explain::Explanation;
+use polar_core::polar::StagedPolicy;
 use polar_core::sources::Source;
-use polar_core::terms::{Call, Symbol, Term, Value};
+use polar_core::terms::{
+    Call, Dictionary, InstanceLiteral, Operation, Operator, Pattern, Symbol, Term, Value,
+};
 
 use std::collections::HashSet;
 use std::fs::File;
@@ -8,16 +13,28 @@ use std::hash::Hash;
 use std::io::Read;
 use std::sync::Arc;
 
+use crate::data_filtering::{DataAdapter, Filter};
+use crate::decision_cache::DecisionCache;
+use crate::errors::AuthorizationError;
 use crate::host::Host;
-use crate::query::Query;
-use crate::{FromPolar, OsoError, PolarValue, ToPolar, ToPolarList};
+use crate::policy_test::{AssertionFailure, TestOutcome};
+use crate::query::{AsyncQuery, Query};
+use crate::{FromPolar, OsoError, PolarValue, QueryOptions, ToPolar, ToPolarList};
 
 /// Oso is the main struct you interact with. It is an instance of the Oso authorization library
 /// and contains the polar language knowledge base and query engine.
 #[derive(Clone)]
 pub struct Oso {
     inner: Arc<polar_core::polar::Polar>,
-    host: Host,
+    pub(crate) host: Host,
+    /// The action used by [`Oso::authorize()`] to decide between
+    /// [`AuthorizationError::NotFound`] and [`AuthorizationError::Forbidden`].
+    read_action: PolarValue,
+    /// Caches the decisions of [`Oso::is_allowed()`], if set.
+    decision_cache: Option<Arc<DecisionCache>>,
+    /// Shared with the watchers of this `Oso` and its clones. See [`Oso::watch()`].
+    #[cfg(feature = "watch")]
+    pub(crate) watchers: crate::watch::Watchers,
 }
 
 impl Default for Oso {
@@ -55,7 +72,14 @@ impl Oso {
         let inner = Arc::new(polar_core::polar::Polar::new());
         let host = Host::new(inner.clone());
 
-        let mut oso = Self { inner, host };
+        let mut oso = Self {
+            inner,
+            host,
+            read_action: PolarValue::String("read".to_owned()),
+            decision_cache: None,
+            #[cfg(feature = "watch")]
+            watchers: Default::default(),
+        };
 
         for class in crate::builtins::classes() {
             oso.register_class(class)
@@ -67,6 +91,10 @@ impl Oso {
     }
 
     /// High level interface for authorization decisions. Makes an allow query with the given actor, action and resource and returns true or false.
+    ///
+    /// If a decision cache is set with [`Oso::set_decision_cache()`], decisions are read from and
+    /// saved to it, unless a rule of the policy calls `now()`. Decisions made before the policy
+    /// changed are never read.
     pub fn is_allowed<Actor, Action, Resource>(
         &self,
         actor: Actor,
@@ -78,14 +106,281 @@ impl Oso {
         Action: ToPolar,
         Resource: ToPolar,
     {
-        let mut query = self.query_rule("allow", (actor, action, resource)).unwrap();
-        match query.next() {
-            Some(Ok(_)) => Ok(true),
-            Some(Err(e)) => Err(e),
-            None => Ok(false),
+        match &self.decision_cache {
+            // The decisions of a policy that calls `now()` can change at any time.
+            Some(cache) if !self.inner.calls_now() => {
+                let args = [actor.to_polar(), action.to_polar(), resource.to_polar()];
+                cache.get_or_insert_with(&args, self.inner.generation(), || {
+                    let [actor, action, resource] = args.clone();
+                    self.query_rule_once("allow", (actor, action, resource))
+                })
+            }
+            _ => self.query_rule_once("allow", (actor, action, resource)),
         }
     }
 
+    /// Cache the decisions of [`Oso::is_allowed()`] in `cache`, which is shared with clones of
+    /// this `Oso`, or stop caching them. See [`DecisionCache`].
+    pub fn set_decision_cache(&mut self, cache: Option<DecisionCache>) {
+        self.decision_cache = cache.map(Arc::new);
+    }
+
+    pub fn decision_cache(&self) -> Option<&DecisionCache> {
+        self.decision_cache.as_deref()
+    }
+
+    // Let the watchers of this `Oso` reload the files the policy is now loaded from, with the
+    // classes and constants registered now.
+    fn update_watchers(&self) {
+        #[cfg(feature = "watch")]
+        {
+            self.watchers.sync(self.loaded_files());
+            self.watchers.set_host(&self.host);
+        }
+    }
+
+    /// Explain an authorization decision: why `allow(actor, action, resource)` succeeds, or
+    /// why it fails.
+    ///
+    /// For an allowed request, the explanation is the proof tree of rules and conditions
+    /// behind the decision. For a denied request, it lists every `allow` rule that was a
+    /// candidate and the first condition that failed in each. Explanations are serializable,
+    /// e.g. to JSON with `serde_json`.
+    ///
+    /// ```
+    /// use oso::explain::{Explanation, Outcome};
+    ///
+    /// let mut oso = oso::Oso::new();
+    /// oso.load_str(r#"allow(actor, "read", _) if actor = "alice";"#).unwrap();
+    /// match oso.explain_allowed("bob", "read", "doc").unwrap() {
+    ///     Explanation::Failure { candidates, .. } => assert!(matches!(
+    ///         &candidates[0].outcome,
+    ///         Outcome::ConditionFailed { condition, .. } if condition == r#"actor = "alice""#
+    ///     )),
+    ///     Explanation::Success { .. } => unreachable!(),
+    /// }
+    /// ```
+    pub fn explain_allowed<Actor, Action, Resource>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource: Resource,
+    ) -> crate::Result<Explanation>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+        Resource: ToPolar,
+    {
+        let (term, host) = self.rule_query_term("allow", (actor, action, resource));
+        let query = self.inner.new_explained_query_from_term(term);
+        check_messages!(self.inner);
+        let mut query = Query::new(query, host);
+        if let Some(Err(e)) = query.next() {
+            return Err(e);
+        }
+        Ok(query
+            .explanation()
+            .expect("explained queries have an explanation"))
+    }
+
+    /// Run the policy tests in the loaded policy, in the order they were loaded. A test fails
+    /// if any of its assertions doesn't hold; an assertion whose query fails with an error
+    /// doesn't hold either.
+    ///
+    /// ```
+    /// let mut oso = oso::Oso::new();
+    /// oso.load_str(
+    ///     r#"allow(actor, "read", _) if actor.name = "alice";
+    ///        test "only alice can read" {
+    ///            alice = {name: "alice"};
+    ///            bob = {name: "bob"};
+    ///            assert allow(alice, "read", "doc");
+    ///            assert_not allow(bob, "read", "doc");
+    ///        }"#,
+    /// )
+    /// .unwrap();
+    /// let outcomes = oso.run_tests();
+    /// assert!(outcomes[0].passed());
+    /// ```
+    pub fn run_tests(&self) -> Vec<TestOutcome> {
+        self.inner
+            .tests()
+            .iter()
+            .map(|test| {
+                let failures = test
+                    .assertions
+                    .iter()
+                    .filter_map(|assertion| {
+                        let query = self.inner.new_test_query(test, assertion);
+                        let location = query.source_info();
+                        let error = match Query::new(query, self.host.clone()).next() {
+                            Some(Err(error)) => Some(error),
+                            result if result.is_some() == assertion.expected => return None,
+                            _ => None,
+                        };
+                        Some(AssertionFailure {
+                            location,
+                            expected: assertion.expected,
+                            error,
+                        })
+                    })
+                    .collect();
+                TestOutcome {
+                    name: test.name().to_owned(),
+                    failures,
+                }
+            })
+            .collect()
+    }
+
+    /// Set the action used by [`Oso::authorize()`] to determine whether an authorization
+    /// failure should return [`AuthorizationError::NotFound`] or
+    /// [`AuthorizationError::Forbidden`]. Defaults to `"read"`.
+    pub fn set_read_action<Action: ToPolar>(&mut self, action: Action) {
+        self.read_action = action.to_polar();
+    }
+
+    /// Ensure that `actor` is allowed to perform `action` on `resource`.
+    ///
+    /// Returns `Ok(())` if the action is permitted by an `allow` rule in the policy. Otherwise,
+    /// returns [`AuthorizationError::NotFound`] if the actor is also not allowed to read the
+    /// resource (see [`Oso::set_read_action()`]), and [`AuthorizationError::Forbidden`] if they
+    /// are.
+    /// # Examples
+    /// ```ignore
+    /// match oso.authorize(user, "delete", repo) {
+    ///     Ok(()) => delete(repo),
+    ///     Err(OsoError::AuthorizationError(AuthorizationError::NotFound)) => not_found(),
+    ///     Err(OsoError::AuthorizationError(AuthorizationError::Forbidden)) => forbidden(),
+    ///     Err(e) => return Err(e),
+    /// }
+    /// ```
+    pub fn authorize<Actor, Action, Resource>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource: Resource,
+    ) -> crate::Result<()>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+        Resource: ToPolar,
+    {
+        let (actor, action, resource) = (actor.to_polar(), action.to_polar(), resource.to_polar());
+        if self.query_rule_once("allow", (actor.clone(), action.clone(), resource.clone()))? {
+            return Ok(());
+        }
+
+        let is_not_found = action == self.read_action
+            || !self.query_rule_once("allow", (actor, self.read_action.clone(), resource))?;
+        if is_not_found {
+            Err(AuthorizationError::NotFound.into())
+        } else {
+            Err(AuthorizationError::Forbidden.into())
+        }
+    }
+
+    /// Ensure that `actor` is allowed to send `request` to the server.
+    ///
+    /// Checks the `allow_request` rule of the policy, returning
+    /// [`AuthorizationError::Forbidden`] if the request is not permitted.
+    pub fn authorize_request<Actor, Request>(
+        &self,
+        actor: Actor,
+        request: Request,
+    ) -> crate::Result<()>
+    where
+        Actor: ToPolar,
+        Request: ToPolar,
+    {
+        if self.query_rule_once("allow_request", (actor, request))? {
+            Ok(())
+        } else {
+            Err(AuthorizationError::Forbidden.into())
+        }
+    }
+
+    /// Ensure that `actor` is allowed to perform `action` on the `field` of `resource`.
+    ///
+    /// Checks the `allow_field` rule of the policy, returning
+    /// [`AuthorizationError::Forbidden`] if the action is not permitted.
+    pub fn authorize_field<Actor, Action, Resource, Field>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource: Resource,
+        field: Field,
+    ) -> crate::Result<()>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+        Resource: ToPolar,
+        Field: ToPolar,
+    {
+        if self.query_rule_once("allow_field", (actor, action, resource, field))? {
+            Ok(())
+        } else {
+            Err(AuthorizationError::Forbidden.into())
+        }
+    }
+
+    /// Determine the actions `actor` is allowed to take on `resource`.
+    ///
+    /// If the policy allows _any_ action (e.g. `allow(_actor, _action, _resource)`), the result
+    /// is `{"*"}` when `allow_wildcard` is `true`, and an error otherwise. To receive
+    /// [`Action::Any`] instead, use [`Oso::get_allowed_actions()`].
+    /// # Examples
+    /// ```ignore
+    /// let actions: HashSet<String> = oso.authorized_actions(actor, resource, false)?;
+    /// ```
+    pub fn authorized_actions<Actor, Resource, T>(
+        &self,
+        actor: Actor,
+        resource: Resource,
+        allow_wildcard: bool,
+    ) -> crate::Result<HashSet<T>>
+    where
+        Actor: ToPolar,
+        Resource: ToPolar,
+        T: FromPolar + Eq + Hash,
+    {
+        let query = self.query_rule(
+            "allow",
+            (actor, PolarValue::Variable("action".to_owned()), resource),
+        )?;
+        collect_bindings(query, "action", allow_wildcard, "authorized_actions")
+    }
+
+    /// Determine the fields of `resource` on which `actor` is allowed to perform `action`.
+    ///
+    /// Uses `allow_field` rules in the policy. If the policy allows _any_ field (e.g.
+    /// `allow_field(_actor, _action, _resource, _field)`), the result is `{"*"}` when
+    /// `allow_wildcard` is `true`, and an error otherwise.
+    pub fn authorized_fields<Actor, Action, Resource, T>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource: Resource,
+        allow_wildcard: bool,
+    ) -> crate::Result<HashSet<T>>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+        Resource: ToPolar,
+        T: FromPolar + Eq + Hash,
+    {
+        let query = self.query_rule(
+            "allow_field",
+            (
+                actor,
+                action,
+                resource,
+                PolarValue::Variable("field".to_owned()),
+            ),
+        )?;
+        collect_bindings(query, "field", allow_wildcard, "authorized_fields")
+    }
+
     /// Get the actions actor is allowed to take on resource.
     /// Returns a [std::collections::HashSet] of actions, typed according the return value.
     /// # Examples
@@ -135,12 +430,17 @@ impl Oso {
     /// Clear out all files and rules that have been loaded.
     pub fn clear_rules(&mut self) -> crate::Result<()> {
         self.inner.clear_rules();
+        self.update_watchers();
         check_messages!(self.inner);
         Ok(())
     }
 
-    fn check_inline_queries(&self) -> crate::Result<()> {
-        while let Some(q) = self.inner.next_inline_query(false) {
+    /// Check the inline queries that `next` returns, which must all succeed.
+    fn check_inline_queries(
+        &self,
+        mut next: impl FnMut() -> Option<polar_core::query::Query>,
+    ) -> crate::Result<()> {
+        while let Some(q) = next() {
             let location = q.source_info();
             let query = Query::new(q, self.host.clone());
             match query.collect::<crate::Result<Vec<_>>>() {
@@ -156,8 +456,46 @@ impl Oso {
     // Register MROs, load Polar code, and check inline queries.
     fn load_sources(&mut self, sources: Vec<Source>) -> crate::Result<()> {
         self.host.register_mros()?;
-        self.inner.load(sources)?;
-        self.check_inline_queries()
+        let result = self.inner.load(sources);
+        self.update_watchers();
+        result?;
+        self.check_inline_queries(|| self.inner.next_inline_query(false))
+    }
+
+    // Register MROs, stage a new policy with `reload`, and check its inline queries. The policy
+    // is only swapped in if they succeed, so queries never use a policy that is rolled back.
+    pub(crate) fn reload_sources(
+        &mut self,
+        reload: impl FnOnce(&mut StagedPolicy) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        self.host.register_mros()?;
+        let mut staged = self.inner.stage();
+        reload(&mut staged)?;
+        self.check_inline_queries(|| staged.next_inline_query(false))?;
+        staged.commit();
+        self.update_watchers();
+        Ok(())
+    }
+
+    // Read Polar files, which must end in `.polar`.
+    pub(crate) fn read_sources<P: AsRef<std::path::Path>>(
+        filenames: Vec<P>,
+    ) -> crate::Result<Vec<Source>> {
+        let mut sources = Vec::with_capacity(filenames.len());
+
+        for file in filenames {
+            let file = file.as_ref();
+            let filename = file.to_string_lossy().into_owned();
+            if !file.extension().map_or(false, |ext| ext == "polar") {
+                return Err(crate::OsoError::IncorrectFileType { filename });
+            }
+            let mut f = File::open(file)?;
+            let mut src = String::new();
+            f.read_to_string(&mut src)?;
+            sources.push(Source::new_with_name(filename, src));
+        }
+
+        Ok(sources)
     }
 
     /// Load a file containing Polar rules. All Polar files must end in `.polar`.
@@ -178,21 +516,71 @@ impl Oso {
             return Ok(());
         }
 
-        let mut sources = Vec::with_capacity(filenames.len());
+        let sources = Self::read_sources(filenames)?;
+        self.load_sources(sources)
+    }
 
-        for file in filenames {
-            let file = file.as_ref();
-            let filename = file.to_string_lossy().into_owned();
-            if !file.extension().map_or(false, |ext| ext == "polar") {
-                return Err(crate::OsoError::IncorrectFileType { filename });
-            }
-            let mut f = File::open(file)?;
-            let mut src = String::new();
-            f.read_to_string(&mut src)?;
-            sources.push(Source::new_with_name(filename, src));
-        }
+    /// Replace the loaded policy with the Polar rules in `filenames`, whether or not a policy
+    /// has been loaded already.
+    ///
+    /// The reload is atomic: the new policy is loaded and validated, and its inline queries are
+    /// checked, on its own, and only then is it swapped in. Queries made in the meantime,
+    /// including by clones of this `Oso`, use the previous policy, which stays loaded if loading
+    /// fails. Concurrent reloads, e.g. by clones, are made one at a time.
+    pub fn reload_files<P: AsRef<std::path::Path>>(
+        &mut self,
+        filenames: Vec<P>,
+    ) -> crate::Result<()> {
+        let sources = Self::read_sources(filenames)?;
+        self.reload_sources(|staged| Ok(staged.reload(sources)?))
+    }
 
-        self.load_sources(sources)
+    /// Reload the Polar file `filename` on its own, leaving the rest of the loaded policy as it
+    /// is. If it hasn't been loaded, it's added to the policy. `filename` must be the same path
+    /// that the file was loaded with. Atomic like [`Oso::reload_files`].
+    pub fn replace_file<P: AsRef<std::path::Path>>(&mut self, filename: P) -> crate::Result<()> {
+        let source = Self::read_sources(vec![filename])?.remove(0);
+        self.reload_sources(|staged| Ok(staged.replace_source(source)?))
+    }
+
+    /// Remove the rules of the Polar file `filename` from the loaded policy, which is reloaded
+    /// without them. `filename` must be the same path that the file was loaded with. Atomic like
+    /// [`Oso::reload_files`].
+    pub fn remove_file<P: AsRef<std::path::Path>>(&mut self, filename: P) -> crate::Result<()> {
+        let filename = filename.as_ref().to_string_lossy().into_owned();
+        self.reload_sources(|staged| Ok(staged.remove_source(&filename)?))
+    }
+
+    /// The files the loaded policy was loaded from, in order.
+    pub fn loaded_files(&self) -> Vec<String> {
+        self.inner
+            .loaded_sources()
+            .into_iter()
+            .filter_map(|source| source.filename)
+            .collect()
+    }
+
+    /// Watch the files the loaded policy was loaded from, and reload it when they change, until
+    /// the returned [`PolicyWatcher`] is dropped. Requires the `watch` feature.
+    ///
+    /// The policy is reloaded from all of its files, like [`Oso::reload_files`], so it is only
+    /// replaced if the changed files load without errors. After each reload, `on_reload` is
+    /// called with the changed files, the errors and warnings from loading them, and whether
+    /// the new policy was loaded. The policy is reloaded on a background thread, for this `Oso`
+    /// and all of its clones.
+    ///
+    /// The watched files follow the files the policy is loaded from, so files added with
+    /// [`Oso::replace_file`] or [`Oso::reload_files`] while watching are watched as soon as
+    /// they're loaded, and files removed with [`Oso::remove_file`] aren't. Reloads use the
+    /// classes and constants registered most recently on this `Oso` or any of its clones.
+    ///
+    /// [`PolicyWatcher`]: crate::watch::PolicyWatcher
+    #[cfg(feature = "watch")]
+    pub fn watch<F>(&self, on_reload: F) -> crate::Result<crate::watch::PolicyWatcher>
+    where
+        F: FnMut(crate::watch::WatchEvent) + Send + 'static,
+    {
+        crate::watch::PolicyWatcher::new(self.clone(), on_reload)
     }
 
     /// Load a string of polar source directly.
@@ -225,6 +613,46 @@ impl Oso {
     /// ```
     #[must_use = "Query that is not consumed does nothing."]
     pub fn query_rule(&self, name: &str, args: impl ToPolarList) -> crate::Result<Query> {
+        let (query_term, query_host) = self.rule_query_term(name, args);
+        let query = self.inner.new_query_from_term(query_term, false);
+        check_messages!(self.inner);
+        let query = Query::new(query, query_host);
+        Ok(query)
+    }
+
+    /// Query the knowledge base with limits on how long and how far the query may run.
+    /// # Examples
+    /// ```ignore
+    /// let options = QueryOptions { timeout_ms: Some(100), ..Default::default() };
+    /// oso.query_with_options("x = 1 or x = 2", &options);
+    /// ```
+    pub fn query_with_options(&self, s: &str, options: &QueryOptions) -> crate::Result<Query> {
+        let query = self.inner.new_query_with_options(s, false, options)?;
+        check_messages!(self.inner);
+        let query = Query::new(query, self.host.clone());
+        Ok(query)
+    }
+
+    /// Query the knowledge base for a rule, like [`Oso::query_rule()`], with limits on how long
+    /// and how far the query may run.
+    #[must_use = "Query that is not consumed does nothing."]
+    pub fn query_rule_with_options(
+        &self,
+        name: &str,
+        args: impl ToPolarList,
+        options: &QueryOptions,
+    ) -> crate::Result<Query> {
+        let (query_term, query_host) = self.rule_query_term(name, args);
+        let query = self
+            .inner
+            .new_query_from_term_with_options(query_term, false, options);
+        check_messages!(self.inner);
+        let query = Query::new(query, query_host);
+        Ok(query)
+    }
+
+    /// The term for a query for rule `name`, and the host its arguments are cached in.
+    fn rule_query_term(&self, name: &str, args: impl ToPolarList) -> (Term, Host) {
         let mut query_host = self.host.clone();
         let args = args
             .to_polar_list()
@@ -236,11 +664,192 @@ impl Oso {
             args,
             kwargs: None,
         });
-        let query_term = Term::new_from_ffi(query_value);
-        let query = self.inner.new_query_from_term(query_term, false);
-        check_messages!(self.inner);
-        let query = Query::new(query, query_host);
-        Ok(query)
+        (Term::new_from_ffi(query_value), query_host)
+    }
+
+    /// Query the knowledge base, awaiting async attributes and methods.
+    ///
+    /// Like [`Oso::query()`], but the results are a [`Stream`](futures_core::Stream). The query
+    /// is `!Send`, so it must run on a local or current-thread executor; see
+    /// [`AsyncQuery`#executors].
+    pub fn query_async(&self, s: &str) -> crate::Result<AsyncQuery> {
+        self.query(s).map(AsyncQuery::new)
+    }
+
+    /// Query the knowledge base for a rule, awaiting async attributes and methods.
+    ///
+    /// Like [`Oso::query_rule()`], but the results are a [`Stream`](futures_core::Stream). The
+    /// query is `!Send`, so it must run on a local or current-thread executor; see
+    /// [`AsyncQuery`#executors].
+    #[must_use = "Query that is not consumed does nothing."]
+    pub fn query_rule_async(
+        &self,
+        name: &str,
+        args: impl ToPolarList,
+    ) -> crate::Result<AsyncQuery> {
+        self.query_rule(name, args).map(AsyncQuery::new)
+    }
+
+    /// Check whether an actor is allowed to perform an action on a resource, awaiting async
+    /// attributes and methods.
+    ///
+    /// The returned future is `!Send`, so it must run on a local or current-thread executor
+    /// rather than with `tokio::spawn`; see [`AsyncQuery`#executors].
+    pub async fn is_allowed_async<Actor, Action, Resource>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource: Resource,
+    ) -> crate::Result<bool>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+        Resource: ToPolar,
+    {
+        let mut query = self.query_rule_async("allow", (actor, action, resource))?;
+        match query.next_result().await {
+            Some(Ok(_)) => Ok(true),
+            Some(Err(e)) => Err(e),
+            None => Ok(false),
+        }
+    }
+
+    /// Set the clock read by `now()` in policies. Defaults to [`crate::SystemClock`]; a
+    /// [`crate::FixedClock`] makes time-dependent rules deterministic in tests.
+    pub fn set_clock(&self, clock: Arc<dyn crate::Clock>) {
+        self.inner.set_clock(clock);
+    }
+
+    /// Set the adapter used by [`Oso::authorized_query()`] and [`Oso::authorized_resources()`]
+    /// to build and execute data filtering queries.
+    pub fn set_data_filtering_adapter<A: DataAdapter>(&mut self, adapter: A) {
+        self.host.adapter = Some(Arc::new(adapter));
+        self.update_watchers();
+    }
+
+    /// Create a query for resources of type `resource_type` that `actor` is allowed to perform
+    /// `action` on.
+    ///
+    /// The query is built by the adapter set with [`Oso::set_data_filtering_adapter()`], and
+    /// `Q` must be the adapter's [`DataAdapter::Query`] type.
+    /// # Examples
+    /// ```ignore
+    /// let query: MyQuery = oso.authorized_query(user, "read", "Repo")?;
+    /// ```
+    pub fn authorized_query<Actor, Action, Q>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource_type: &str,
+    ) -> crate::Result<Q>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+        Q: 'static,
+    {
+        let adapter = self
+            .host
+            .adapter
+            .as_ref()
+            .ok_or(OsoError::MissingDataFilteringAdapter)?;
+        let filter = self.data_filter(actor, action, resource_type)?;
+        adapter
+            .build_query(&filter)?
+            .downcast::<Q>()
+            .map(|query| *query)
+            .map_err(|_| crate::errors::TypeError::expected(std::any::type_name::<Q>()).user())
+    }
+
+    /// Determine the resources of type `resource_type` that `actor` is allowed to perform
+    /// `action` on.
+    ///
+    /// The resources are fetched by the adapter set with [`Oso::set_data_filtering_adapter()`].
+    /// # Examples
+    /// ```ignore
+    /// let repos: Vec<Repo> = oso.authorized_resources(user, "read", "Repo")?;
+    /// ```
+    pub fn authorized_resources<Actor, Action, T>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource_type: &str,
+    ) -> crate::Result<Vec<T>>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+        T: FromPolar,
+    {
+        let adapter = self
+            .host
+            .adapter
+            .as_ref()
+            .ok_or(OsoError::MissingDataFilteringAdapter)?;
+        let filter = self.data_filter(actor, action, resource_type)?;
+        adapter
+            .execute_query(adapter.build_query(&filter)?)?
+            .into_iter()
+            .map(T::from_polar)
+            .collect()
+    }
+
+    /// Partially evaluate an allow query over an unbound resource of type `resource_type` and
+    /// build a [`Filter`] from the results.
+    fn data_filter<Actor, Action>(
+        &self,
+        actor: Actor,
+        action: Action,
+        resource_type: &str,
+    ) -> crate::Result<Filter>
+    where
+        Actor: ToPolar,
+        Action: ToPolar,
+    {
+        self.host.get_class(resource_type)?;
+
+        let resource = "resource";
+        let mut query = self.query_rule(
+            "allow",
+            (actor, action, PolarValue::Variable(resource.to_owned())),
+        )?;
+        query.accept_expression();
+
+        // Constrain the resource to be an instance of `resource_type`.
+        let isa = Value::Expression(Operation {
+            operator: Operator::Isa,
+            args: vec![
+                Term::new_from_ffi(Value::Variable(Symbol(resource.to_owned()))),
+                Term::new_from_ffi(Value::Pattern(Pattern::Instance(InstanceLiteral {
+                    tag: Symbol(resource_type.to_owned()),
+                    fields: Dictionary::new(),
+                }))),
+            ],
+        });
+        let constraint = Value::Expression(Operation {
+            operator: Operator::And,
+            args: vec![Term::new_from_ffi(isa)],
+        });
+        query.bind(resource, Term::new_from_ffi(constraint))?;
+
+        let mut results = vec![];
+        for result in query.by_ref() {
+            results.push(ResultEvent::from(result?));
+        }
+
+        let types = self.host.serialize_types()?;
+        let filter =
+            self.inner
+                .build_data_filter(types.clone(), results, resource, resource_type)?;
+        Filter::from_core(filter, types, query.host())
+    }
+
+    /// Query the knowledge base for a rule, returning `true` if it has at least one result.
+    pub(crate) fn query_rule_once(&self, name: &str, args: impl ToPolarList) -> crate::Result<bool> {
+        let mut query = self.query_rule(name, args)?;
+        match query.next() {
+            Some(Ok(_)) => Ok(true),
+            Some(Err(e)) => Err(e),
+            None => Ok(false),
+        }
     }
 
     /// Register a rust type as a Polar class.
@@ -252,7 +861,10 @@ impl Oso {
         for hook in &class.register_hooks {
             hook.call(self)?;
         }
-        self.register_constant(class, &class_name)
+        let term = self.host.class_term(&class_name)?;
+        self.inner.register_constant(Symbol(class_name), term)?;
+        self.update_watchers();
+        Ok(())
     }
 
     /// Register a rust type as a Polar constant.
@@ -266,8 +878,55 @@ impl Oso {
             Symbol(name.to_string()),
             value.to_polar().to_term(&mut self.host),
         )?;
+        self.update_watchers();
         Ok(())
     }
+
+    /// Table the rule `name`: during each query, its answers are computed once for each
+    /// distinct call and reused, and recursive calls with the same arguments terminate.
+    ///
+    /// Useful for recursive rules over data that may contain cycles (e.g., a group hierarchy),
+    /// or left-recursive rules. Calls whose arguments have constraints are evaluated as usual.
+    pub fn table_rule(&mut self, name: &str) {
+        self.inner.table_rule(Symbol(name.to_string()))
+    }
+}
+
+/// Collect the values bound to `name` in each result of `query`.
+///
+/// An unbound value is a wildcard, which is returned as `"*"` if `allow_wildcard` is set and is
+/// an error otherwise.
+fn collect_bindings<T>(
+    query: Query,
+    name: &str,
+    allow_wildcard: bool,
+    method: &str,
+) -> crate::Result<HashSet<T>>
+where
+    T: FromPolar + Eq + Hash,
+{
+    let mut set = HashSet::new();
+    for result in query {
+        match result?.get(name) {
+            Some(PolarValue::Variable(_)) if allow_wildcard => {
+                return Ok(
+                    std::iter::once(T::from_polar(PolarValue::String("*".to_owned()))?).collect(),
+                );
+            }
+            Some(PolarValue::Variable(_)) => {
+                return lazy_error!(
+                    r#"The result of {method}() contained an "unconstrained" {name} that could represent any {name}, but allow_wildcard was set to false. To fix, set allow_wildcard to true and compare with the "*" string."#,
+                    method = method,
+                    name = name
+                );
+            }
+            Some(value) => {
+                set.insert(T::from_polar(value)?);
+            }
+            None => {}
+        }
+    }
+    Ok(set)
 }
 
 // Make sure the `Oso` object is threadsafe
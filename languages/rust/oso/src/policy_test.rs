@@ -0,0 +1,50 @@
+//! Results of the policy tests written in Polar with `test` blocks. See [`Oso::run_tests`].
+//!
+//! [`Oso::run_tests`]: crate::Oso::run_tests
+
+use std::fmt;
+
+use crate::OsoError;
+
+/// An assertion that didn't hold.
+#[derive(Debug)]
+pub struct AssertionFailure {
+    /// The source of the assertion and its location.
+    pub location: String,
+    /// Whether the assertion was an `assert` or an `assert_not`.
+    pub expected: bool,
+    /// The error the assertion's query failed with, if any.
+    pub error: Option<OsoError>,
+}
+
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keyword = if self.expected {
+            "assert"
+        } else {
+            "assert_not"
+        };
+        match &self.error {
+            Some(error) => write!(
+                f,
+                "{} {} failed with an error: {}",
+                keyword, self.location, error
+            ),
+            None => write!(f, "{} {} failed", keyword, self.location),
+        }
+    }
+}
+
+/// The outcome of a policy test.
+#[derive(Debug)]
+pub struct TestOutcome {
+    pub name: String,
+    /// The assertions of the test that didn't hold, in order.
+    pub failures: Vec<AssertionFailure>,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
@@ -1,11 +1,17 @@
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
 
 use crate::errors::OsoError;
-use crate::host::{Host, Instance, PolarIterator};
-use crate::{FromPolar, PolarValue};
+use crate::host::{Host, Instance, Kwargs, PolarFuture, PolarIterator};
+use crate::{CancellationHandle, FromPolar, PolarValue};
 
 use polar_core::events::*;
+use polar_core::explain::Explanation;
 use polar_core::terms::*;
 
 impl Iterator for Query {
@@ -35,77 +41,187 @@ impl Query {
         self.inner.source_info()
     }
 
+    /// A handle that cancels the query from any thread. The query then fails with a
+    /// `QueryCancelled` error.
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        self.inner.cancellation_handle()
+    }
+
+    /// Explain the query's first result, or why it has none. Returns `None` unless the query
+    /// was created by [`Oso::explain_allowed()`](crate::Oso::explain_allowed).
+    pub fn explanation(&self) -> Option<Explanation> {
+        self.inner.explanation()
+    }
+
+    /// Bind `name` to `value` before running the query.
+    pub(crate) fn bind(&mut self, name: &str, value: Term) -> crate::Result<()> {
+        Ok(self.inner.bind(Symbol(name.to_string()), value)?)
+    }
+
+    /// Allow results to contain expressions, e.g. for partial evaluation.
+    pub(crate) fn accept_expression(&mut self) {
+        self.host.accept_expression = true;
+    }
+
+    pub(crate) fn host(&self) -> &Host {
+        &self.host
+    }
+
     pub fn next_result(&mut self) -> Option<crate::Result<ResultSet>> {
         loop {
-            let event = self.inner.next()?;
-            check_messages!(self.inner);
-            if let Err(e) = event {
-                return Some(Err(e.into()));
-            }
-            let event = event.unwrap();
-            tracing::debug!(event=?event);
-            let result = match event {
-                QueryEvent::None => Ok(()),
-                QueryEvent::Done { .. } => return None,
-                QueryEvent::Result { bindings, .. } => {
-                    return Some(ResultSet::from_bindings(bindings, self.host.clone()));
-                }
-                QueryEvent::MakeExternal {
-                    instance_id,
-                    constructor,
-                } => self.handle_make_external(instance_id, constructor),
-                QueryEvent::NextExternal { call_id, iterable } => {
-                    self.handle_next_external(call_id, iterable)
-                }
-                QueryEvent::ExternalCall {
-                    call_id,
-                    instance,
-                    attribute,
-                    args,
-                    kwargs,
-                } => self.handle_external_call(call_id, instance, attribute, args, kwargs),
-                QueryEvent::ExternalOp {
-                    call_id,
-                    operator,
-                    args,
-                } => self.handle_external_op(call_id, operator, args),
-                QueryEvent::ExternalIsa {
-                    call_id,
-                    instance,
-                    class_tag,
-                } => self.handle_external_isa(call_id, instance, class_tag),
-                QueryEvent::ExternalIsSubSpecializer {
-                    call_id,
-                    instance_id,
-                    left_class_tag,
-                    right_class_tag,
-                } => self.handle_external_is_subspecializer(
-                    call_id,
-                    instance_id,
-                    left_class_tag,
-                    right_class_tag,
-                ),
-                QueryEvent::Debug { message } => self.handle_debug(message),
-                QueryEvent::ExternalIsSubclass {
-                    call_id,
-                    left_class_tag,
-                    right_class_tag,
-                } => self.handle_external_is_subclass(call_id, left_class_tag, right_class_tag),
-                event => unimplemented!("Unhandled event {:?}", event),
+            let event = match self.next_event()? {
+                Ok(event) => event,
+                Err(e) => return Some(Err(e)),
             };
+            if let ControlFlow::Break(result) = self.handle_event(event) {
+                return result;
+            }
+        }
+    }
 
-            match result {
-                // Only call errors get passed back.
-                Err(call_error @ OsoError::InvalidCallError { .. }) => {
-                    tracing::error!("application invalid call error {}", call_error);
-                    if let Err(e) = self.application_error(call_error) {
-                        return Some(Err(e));
-                    }
+    /// Get the next event from the VM.
+    pub(crate) fn next_event(&mut self) -> Option<crate::Result<QueryEvent>> {
+        let event = self.inner.next()?;
+        check_messages!(self.inner);
+        let event = event.map_err(OsoError::from);
+        tracing::debug!(event=?event);
+        Some(event)
+    }
+
+    /// Handle an event from the VM. Breaks with the value to return from
+    /// [`Query::next_result()`], or continues if the query should keep running.
+    pub(crate) fn handle_event(
+        &mut self,
+        event: QueryEvent,
+    ) -> ControlFlow<Option<crate::Result<ResultSet>>> {
+        let result = match event {
+            QueryEvent::None => Ok(()),
+            QueryEvent::Done { .. } => return ControlFlow::Break(None),
+            QueryEvent::Result { bindings, .. } => {
+                return ControlFlow::Break(Some(ResultSet::from_bindings(
+                    bindings,
+                    self.host.clone(),
+                )));
+            }
+            QueryEvent::MakeExternal {
+                instance_id,
+                constructor,
+            } => {
+                // There is no call to pass an invalid call error back to, so every error making
+                // an instance is returned.
+                return match self.handle_make_external(instance_id, constructor) {
+                    Ok(()) => ControlFlow::Continue(()),
+                    Err(e) => ControlFlow::Break(Some(Err(e))),
+                };
+            }
+            QueryEvent::NextExternal { call_id, iterable } => {
+                self.handle_next_external(call_id, iterable)
+            }
+            QueryEvent::ExternalCall {
+                call_id,
+                instance,
+                attribute,
+                args,
+                kwargs,
+            } => self.handle_external_call(call_id, instance, attribute, args, kwargs),
+            QueryEvent::ExternalOp {
+                call_id,
+                operator,
+                args,
+            } => self.handle_external_op(call_id, operator, args),
+            QueryEvent::ExternalIsa {
+                call_id,
+                instance,
+                class_tag,
+            } => self.handle_external_isa(call_id, instance, class_tag),
+            QueryEvent::ExternalIsaWithPath {
+                call_id,
+                base_tag,
+                path,
+                class_tag,
+            } => self.handle_external_isa_with_path(call_id, base_tag, path, class_tag),
+            QueryEvent::ExternalIsSubSpecializer {
+                call_id,
+                instance_id,
+                left_class_tag,
+                right_class_tag,
+            } => self.handle_external_is_subspecializer(
+                call_id,
+                instance_id,
+                left_class_tag,
+                right_class_tag,
+            ),
+            QueryEvent::Debug { message } => self.handle_debug(message),
+            QueryEvent::ExternalIsSubclass {
+                call_id,
+                left_class_tag,
+                right_class_tag,
+            } => self.handle_external_is_subclass(call_id, left_class_tag, right_class_tag),
+            event => unimplemented!("Unhandled event {:?}", event),
+        };
+        self.handle_result(result)
+    }
+
+    /// Handle the outcome of answering an event.
+    pub(crate) fn handle_result(
+        &mut self,
+        result: crate::Result<()>,
+    ) -> ControlFlow<Option<crate::Result<ResultSet>>> {
+        match result {
+            // Only call errors get passed back.
+            Err(call_error @ OsoError::InvalidCallError { .. }) => {
+                tracing::error!("application invalid call error {}", call_error);
+                if let Err(e) = self.application_error(call_error) {
+                    return ControlFlow::Break(Some(Err(e)));
                 }
-                // All others get returned.
-                Err(err) => return Some(Err(err)),
-                // Continue on ok
-                Ok(_) => {}
+                ControlFlow::Continue(())
+            }
+            // All others get returned.
+            Err(err) => ControlFlow::Break(Some(Err(err))),
+            // Continue on ok
+            Ok(_) => ControlFlow::Continue(()),
+        }
+    }
+
+    /// Start a call to an async attribute or method for an `ExternalCall` event.
+    ///
+    /// Returns `None` if the call is not to an async attribute or method, in which case the
+    /// event should be handled by [`Query::handle_event()`].
+    pub(crate) fn start_async_call(
+        &mut self,
+        instance: &Term,
+        name: &Symbol,
+        args: Option<&[Term]>,
+    ) -> Option<crate::Result<PolarFuture>> {
+        let instance = PolarValue::from_term(instance, &self.host)
+            .and_then(Instance::from_polar)
+            .ok()?;
+        let args = match args {
+            None => None,
+            Some(args) => match args
+                .iter()
+                .map(|v| PolarValue::from_term(v, &self.host))
+                .collect::<crate::Result<Vec<PolarValue>>>()
+            {
+                Ok(args) => Some(args),
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        tracing::trace!(name = %name, args = ?args, "call_async");
+        instance.call_async(&name.0, args, &mut self.host)
+    }
+
+    /// Return the result of an external call to Polar.
+    pub(crate) fn finish_call(
+        &mut self,
+        call_id: u64,
+        result: crate::Result<PolarValue>,
+    ) -> crate::Result<()> {
+        match result {
+            Ok(t) => self.call_result(call_id, t),
+            Err(e) => {
+                self.call_result_none(call_id)?;
+                Err(e)
             }
         }
     }
@@ -137,20 +253,25 @@ impl Query {
     fn handle_make_external(&mut self, instance_id: u64, constructor: Term) -> crate::Result<()> {
         match constructor.value() {
             Value::Call(Call { name, args, kwargs }) => {
-                if !kwargs.is_none() {
-                    lazy_error!("keyword args for constructor not supported.")
-                } else {
-                    let args = args
-                        .iter()
-                        .map(|term| PolarValue::from_term(term, &self.host))
-                        .collect::<crate::Result<Vec<PolarValue>>>()?;
-                    self.host.make_instance(&name.0, args, instance_id)
-                }
+                let args = args
+                    .iter()
+                    .map(|term| PolarValue::from_term(term, &self.host))
+                    .collect::<crate::Result<Vec<PolarValue>>>()?;
+                let kwargs = self.kwargs_from_terms(kwargs.as_ref())?;
+                self.host.make_instance(&name.0, args, kwargs, instance_id)
             }
             _ => lazy_error!("invalid type for constructing an instance -- internal error"),
         }
     }
 
+    fn kwargs_from_terms(&self, kwargs: Option<&BTreeMap<Symbol, Term>>) -> crate::Result<Kwargs> {
+        kwargs
+            .into_iter()
+            .flatten()
+            .map(|(name, term)| Ok((name.0.clone(), PolarValue::from_term(term, &self.host)?)))
+            .collect()
+    }
+
     fn next_call_result(&mut self, call_id: u64) -> Option<crate::Result<PolarValue>> {
         self.iterators.get_mut(&call_id).and_then(|c| c.next())
     }
@@ -181,27 +302,19 @@ impl Query {
         args: Option<Vec<Term>>,
         kwargs: Option<BTreeMap<Symbol, Term>>,
     ) -> crate::Result<()> {
-        if kwargs.is_some() {
-            return lazy_error!("Invalid call error: kwargs not supported in Rust.");
-        }
-        tracing::trace!(call_id, name = %name, args = ?args, "call");
+        tracing::trace!(call_id, name = %name, args = ?args, kwargs = ?kwargs, "call");
         let instance = Instance::from_polar(PolarValue::from_term(&instance, &self.host)?)?;
         let result = if let Some(args) = args {
             let args = args
                 .iter()
                 .map(|v| PolarValue::from_term(v, &self.host))
                 .collect::<crate::Result<Vec<PolarValue>>>()?;
-            instance.call(&name.0, args, &mut self.host)
+            let kwargs = self.kwargs_from_terms(kwargs.as_ref())?;
+            instance.call_with_kwargs(&name.0, args, kwargs, &mut self.host)
         } else {
             instance.get_attr(&name.0, &mut self.host)
         };
-        match result {
-            Ok(t) => self.call_result(call_id, t),
-            Err(e) => {
-                self.call_result_none(call_id)?;
-                Err(e)
-            }
-        }
+        self.finish_call(call_id, result)
     }
 
     fn handle_external_op(
@@ -236,6 +349,19 @@ impl Query {
         Ok(())
     }
 
+    fn handle_external_isa_with_path(
+        &mut self,
+        call_id: u64,
+        base_tag: Symbol,
+        path: TermList,
+        class_tag: Symbol,
+    ) -> crate::Result<()> {
+        tracing::debug!(base = %base_tag, path = ?path, class = %class_tag, "isa_with_path");
+        let res = self.host.isa_with_path(&base_tag.0, &path, &class_tag.0)?;
+        self.question_result(call_id, res)?;
+        Ok(())
+    }
+
     fn handle_external_is_subspecializer(
         &mut self,
         call_id: u64,
@@ -243,9 +369,9 @@ impl Query {
         left_class_tag: Symbol,
         right_class_tag: Symbol,
     ) -> crate::Result<()> {
-        let res = self
-            .host
-            .is_subspecializer(instance_id, &left_class_tag.0, &right_class_tag.0);
+        let res =
+            self.host
+                .is_subspecializer(instance_id, &left_class_tag.0, &right_class_tag.0)?;
         self.question_result(call_id, res)?;
         Ok(())
     }
@@ -256,7 +382,9 @@ impl Query {
         left_class_tag: Symbol,
         right_class_tag: Symbol,
     ) -> crate::Result<()> {
-        let res = left_class_tag == right_class_tag;
+        let res = self
+            .host
+            .is_subclass(&left_class_tag.0, &right_class_tag.0)?;
         self.question_result(call_id, res)?;
         Ok(())
     }
@@ -269,6 +397,135 @@ impl Query {
     }
 }
 
+/// A query whose results are a [`Stream`], created with
+/// [`Oso::query_async()`](crate::Oso::query_async) or
+/// [`Oso::query_rule_async()`](crate::Oso::query_rule_async).
+///
+/// Calls to attributes and methods added with
+/// [`ClassBuilder::add_async_attribute_getter()`](crate::ClassBuilder::add_async_attribute_getter)
+/// and [`ClassBuilder::add_async_method()`](crate::ClassBuilder::add_async_method) are awaited
+/// without blocking the executor. All other calls are answered synchronously, as in [`Query`].
+///
+/// # Examples
+/// ```ignore
+/// use futures::StreamExt;
+///
+/// let mut query = oso.query_rule_async("allow", (user, "read", repo))?;
+/// while let Some(result) = query.next().await {
+///     println!("{:?}", result?);
+/// }
+/// ```
+///
+/// # Executors
+///
+/// Like [`Query`], an `AsyncQuery` is `!Send`, and so is the future returned by
+/// [`Oso::is_allowed_async()`](crate::Oso::is_allowed_async). It can't be passed to
+/// `tokio::spawn` or awaited in a handler that a multi-threaded runtime requires to be `Send`.
+/// Drive it on the current thread instead, e.g., with `tokio::task::spawn_local` inside a
+/// `LocalSet`, a current-thread runtime, or `futures::executor::block_on`:
+///
+/// ```
+/// use futures::executor::block_on;
+/// use oso::Oso;
+///
+/// let mut oso = Oso::new();
+/// oso.load_str(r#"allow(actor, "read", _resource) if actor = "alice";"#)?;
+///
+/// assert!(block_on(oso.is_allowed_async("alice", "read", 1))?);
+/// assert!(!block_on(oso.is_allowed_async("bob", "read", 1))?);
+/// # Ok::<(), oso::OsoError>(())
+/// ```
+pub struct AsyncQuery {
+    query: Query,
+    /// The async call the VM is waiting on, if any.
+    pending: Option<(u64, PolarFuture)>,
+}
+
+impl AsyncQuery {
+    pub fn new(query: Query) -> Self {
+        Self {
+            query,
+            pending: None,
+        }
+    }
+
+    pub fn source(&self) -> String {
+        self.query.source()
+    }
+
+    /// A handle that cancels the query, like [`Query::cancellation_handle()`].
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        self.query.cancellation_handle()
+    }
+
+    /// Wait for the next result of the query, like [`Query::next_result()`].
+    pub async fn next_result(&mut self) -> Option<crate::Result<ResultSet>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl From<Query> for AsyncQuery {
+    fn from(query: Query) -> Self {
+        Self::new(query)
+    }
+}
+
+impl Stream for AsyncQuery {
+    type Item = crate::Result<ResultSet>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some((call_id, future)) = this.pending.as_mut() {
+                let result = match future.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(result) => result,
+                };
+                let call_id = *call_id;
+                this.pending = None;
+                let result = this.query.finish_call(call_id, result);
+                if let ControlFlow::Break(result) = this.query.handle_result(result) {
+                    return Poll::Ready(result);
+                }
+                continue;
+            }
+
+            let event = match this.query.next_event() {
+                None => return Poll::Ready(None),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                Some(Ok(event)) => event,
+            };
+            let call = match &event {
+                QueryEvent::ExternalCall {
+                    call_id,
+                    instance,
+                    attribute,
+                    args,
+                    kwargs: None,
+                } => this
+                    .query
+                    .start_async_call(instance, attribute, args.as_deref())
+                    .map(|future| (*call_id, future)),
+                _ => None,
+            };
+            match call {
+                Some((call_id, Ok(future))) => this.pending = Some((call_id, future)),
+                Some((call_id, Err(e))) => {
+                    let result = this.query.finish_call(call_id, Err(e));
+                    if let ControlFlow::Break(result) = this.query.handle_result(result) {
+                        return Poll::Ready(result);
+                    }
+                }
+                None => {
+                    if let ControlFlow::Break(result) = this.query.handle_event(event) {
+                        return Poll::Ready(result);
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ResultSet {
     bindings: polar_core::kb::Bindings,
@@ -327,6 +584,12 @@ This may mean you performed an operation in your policy over an unbound variable
     }
 }
 
+impl From<ResultSet> for ResultEvent {
+    fn from(result: ResultSet) -> Self {
+        result.into_event()
+    }
+}
+
 impl std::fmt::Debug for ResultSet {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(fmt, "{:#?}", self.bindings)
@@ -348,3 +611,5 @@ impl<S: AsRef<str>, T: crate::host::FromPolar + PartialEq<T>> PartialEq<HashMap<
 // Make sure the `Query` object is _not_ threadsafe
 #[cfg(test)]
 static_assertions::assert_not_impl_any!(Query: Send, Sync);
+#[cfg(test)]
+static_assertions::assert_not_impl_any!(AsyncQuery: Send, Sync);
@@ -17,12 +17,61 @@ fn build_app() -> Command<'static> {
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about("Oso REPL. See https://docs.osohq.com/rust/reference/tooling/repl.html")
+        .args_conflicts_with_subcommands(true)
         .arg(
             Arg::with_name("FILES")
                 .multiple(true)
                 .multiple_values(true)
                 .help("Specify one or more .polar files to load"),
         )
+        .subcommand(
+            Command::new("test")
+                .about("Run the tests in one or more .polar files")
+                .arg(
+                    Arg::with_name("FILES")
+                        .required(true)
+                        .multiple(true)
+                        .multiple_values(true)
+                        .help("Specify one or more .polar files to load"),
+                ),
+        )
+}
+
+/// Run the policy tests in `files`, print a report, and exit with a failure status if any
+/// test failed.
+fn run_tests(files: Vec<&str>) -> anyhow::Result<()> {
+    let mut oso = Oso::new();
+    oso.load_files(files)?;
+
+    let outcomes = oso.run_tests();
+    println!("running {} tests", outcomes.len());
+    for outcome in &outcomes {
+        let status = if outcome.passed() { "ok" } else { "FAILED" };
+        println!("test {} ... {}", outcome.name, status);
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).collect::<Vec<_>>();
+    if !failed.is_empty() {
+        println!("\nfailures:");
+        for outcome in &failed {
+            println!("\n---- {} ----", outcome.name);
+            for failure in &outcome.failures {
+                println!("{}", failure);
+            }
+        }
+    }
+
+    let status = if failed.is_empty() { "ok" } else { "FAILED" };
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        status,
+        outcomes.len() - failed.len(),
+        failed.len()
+    );
+    if !failed.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
 }
 
 /// Attempt to create a new temporary directory to store
@@ -111,10 +160,14 @@ impl Drop for Repl {
 
 pub fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
+    let matches = build_app().get_matches();
+    if let Some(matches) = matches.subcommand_matches("test") {
+        return run_tests(matches.values_of("FILES").unwrap().collect());
+    }
+
     let mut repl = Repl::new();
     let mut oso = Oso::new();
 
-    let matches = build_app().get_matches();
     if matches.is_present("FILES") {
         oso.load_files(matches.values_of("FILES").unwrap().collect())?;
     }
@@ -0,0 +1,231 @@
+//! Reloading a policy when its files change, with the `watch` feature. See [`Oso::watch`].
+//!
+//! [`Oso::watch`]: crate::Oso::watch
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use polar_core::diagnostic::Diagnostic;
+
+use crate::host::Host;
+use crate::Oso;
+
+/// How long to wait for more changes before reloading, since saving a file can change it
+/// several times.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// A reload of the policy after some of its files changed.
+#[derive(Debug)]
+pub struct WatchEvent {
+    /// The policy files that changed.
+    pub changed: Vec<PathBuf>,
+    /// The errors and warnings from loading the changed policy.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether the changed policy was loaded. If not, the previous policy stays loaded.
+    pub result: crate::Result<()>,
+}
+
+/// Watches the files of a policy and reloads it when they change, until dropped.
+pub struct PolicyWatcher {
+    // The reloading thread only holds a weak reference to the watcher, so dropping this drops
+    // the watcher, which disconnects the channel its events are sent on and stops the thread.
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+impl PolicyWatcher {
+    pub(crate) fn new<F>(oso: Oso, on_reload: F) -> crate::Result<Self>
+    where
+        F: FnMut(WatchEvent) + Send + 'static,
+    {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })?;
+        let watcher = Arc::new(Mutex::new(watcher));
+        let mut files = WatchedFiles {
+            watcher: Arc::downgrade(&watcher),
+            files: vec![],
+            dirs: HashSet::new(),
+        };
+        files.sync(oso.loaded_files())?;
+        let files = Arc::new(Mutex::new(files));
+        oso.watchers.add(&files, oso.host.clone());
+
+        thread::spawn(move || reload_on_change(oso, files, events, on_reload));
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+/// The state that an `Oso` and its clones share with the threads of the watchers of any of
+/// them, which reload the policy with their own clone.
+#[derive(Clone, Default)]
+pub(crate) struct Watchers {
+    inner: Arc<Mutex<WatchersInner>>,
+}
+
+#[derive(Default)]
+struct WatchersInner {
+    /// The files watched by each watcher whose thread is running.
+    files: Vec<Weak<Mutex<WatchedFiles>>>,
+    /// The classes and constants registered most recently, which reloads must use rather than
+    /// those of the clone they were made with. `None` until the policy is watched.
+    host: Option<Host>,
+}
+
+impl Watchers {
+    fn add(&self, files: &Arc<Mutex<WatchedFiles>>, host: Host) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.files.push(Arc::downgrade(files));
+        inner.host = Some(host);
+    }
+
+    /// Watch the files named `filenames` with every watcher, after they're loaded, so that
+    /// changes to them right afterwards are picked up.
+    pub(crate) fn sync(&self, filenames: Vec<String>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.files.retain(|files| files.strong_count() > 0);
+        for files in inner.files.iter().filter_map(Weak::upgrade) {
+            if let Err(error) = files.lock().unwrap().sync(filenames.clone()) {
+                tracing::error!("error watching policy files: {}", error);
+            }
+        }
+    }
+
+    /// Reload with `host` from now on, if the policy is watched.
+    pub(crate) fn set_host(&self, host: &Host) {
+        if let Some(watched) = &mut self.inner.lock().unwrap().host {
+            *watched = host.clone();
+        }
+    }
+
+    fn host(&self) -> Option<Host> {
+        self.inner.lock().unwrap().host.clone()
+    }
+}
+
+/// The files of the policy being watched.
+struct WatchedFiles {
+    watcher: Weak<Mutex<RecommendedWatcher>>,
+    /// The absolute paths of the files, which events for them are reported with, and the names
+    /// they were loaded with.
+    files: Vec<(PathBuf, String)>,
+    /// The directories being watched. Editors often save a file by replacing it, so the
+    /// directories of the files are watched rather than the files themselves.
+    dirs: HashSet<PathBuf>,
+}
+
+impl WatchedFiles {
+    /// Watch the files named `filenames` instead, if they've changed.
+    fn sync(&mut self, filenames: Vec<String>) -> crate::Result<()> {
+        if self
+            .files
+            .iter()
+            .map(|(_, filename)| filename)
+            .eq(&filenames)
+        {
+            return Ok(());
+        }
+        let files = filenames
+            .into_iter()
+            .map(|filename| Ok((watched_path(Path::new(&filename))?, filename)))
+            .collect::<crate::Result<Vec<_>>>()?;
+        let dirs = files
+            .iter()
+            .filter_map(|(path, _)| path.parent())
+            .map(Path::to_path_buf)
+            .collect::<HashSet<_>>();
+
+        if let Some(watcher) = self.watcher.upgrade() {
+            let mut watcher = watcher.lock().unwrap();
+            for dir in dirs.difference(&self.dirs) {
+                watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            }
+            for dir in self.dirs.difference(&dirs) {
+                watcher.unwatch(dir)?;
+            }
+        }
+        self.files = files;
+        self.dirs = dirs;
+        Ok(())
+    }
+
+    /// Add the files that `event` changed to `changed`.
+    fn collect(&self, event: notify::Result<Event>, changed: &mut Vec<PathBuf>) {
+        match event {
+            Ok(event) if !event.kind.is_access() => {
+                for path in event.paths {
+                    if self.files.iter().any(|(file, _)| *file == path) && !changed.contains(&path)
+                    {
+                        changed.push(path);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(error) => tracing::error!("error watching policy files: {}", error),
+        }
+    }
+}
+
+/// The absolute path of `path` that events for it are reported with.
+fn watched_path(path: &Path) -> crate::Result<PathBuf> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    Ok(dir
+        .canonicalize()?
+        .join(path.file_name().unwrap_or_default()))
+}
+
+fn reload_on_change<F>(
+    mut oso: Oso,
+    files: Arc<Mutex<WatchedFiles>>,
+    events: Receiver<notify::Result<Event>>,
+    mut on_reload: F,
+) where
+    F: FnMut(WatchEvent),
+{
+    let mut changed = vec![];
+    while let Ok(event) = events.recv() {
+        files.lock().unwrap().collect(event, &mut changed);
+        loop {
+            match events.recv_timeout(DEBOUNCE) {
+                Ok(event) => files.lock().unwrap().collect(event, &mut changed),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        if let Some(host) = oso.watchers.host() {
+            oso.host = host;
+        }
+        // Reload the files the policy is loaded from while it's staged, since they can't
+        // change until it's swapped in.
+        let mut diagnostics = vec![];
+        let result = oso.reload_sources(|staged| {
+            let filenames = staged
+                .loaded_sources()
+                .into_iter()
+                .filter_map(|source| source.filename)
+                .collect();
+            diagnostics = staged.diagnostic_reload(Oso::read_sources(filenames)?);
+            match diagnostics.iter().find(|d| d.is_error()) {
+                Some(Diagnostic::Error(error)) => Err(error.clone().into()),
+                _ => Ok(()),
+            }
+        });
+        on_reload(WatchEvent {
+            changed: std::mem::take(&mut changed),
+            diagnostics,
+            result,
+        });
+    }
+}
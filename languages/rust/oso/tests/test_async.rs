@@ -0,0 +1,169 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::executor::block_on;
+use futures::StreamExt;
+use oso::{Oso, OsoError, PolarClass};
+
+/// A future that is pending on its first poll, like one waiting on I/O.
+struct Delayed<T>(Option<T>, bool);
+
+impl<T: Unpin> Future for Delayed<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        if self.1 {
+            Poll::Ready(self.0.take().unwrap())
+        } else {
+            self.1 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+fn delayed<T>(value: T) -> Delayed<T> {
+    Delayed(Some(value), false)
+}
+
+#[derive(PolarClass, Clone, Debug)]
+struct User {
+    #[polar(attribute)]
+    name: String,
+}
+
+#[derive(PolarClass, Clone, Debug)]
+struct Repo {
+    #[polar(attribute)]
+    id: i64,
+}
+
+fn test_oso() -> Oso {
+    let mut oso = Oso::new();
+    oso.register_class(
+        User::get_polar_class_builder()
+            .add_async_attribute_getter("team", |user: &User| {
+                delayed(format!("{}-team", user.name))
+            })
+            .add_async_iterator_method("repo_ids", |user: &User| {
+                let ids = if user.name == "alice" {
+                    vec![1, 2]
+                } else {
+                    vec![3]
+                };
+                delayed(ids)
+            })
+            .build(),
+    )
+    .unwrap();
+    oso.register_class(
+        Repo::get_polar_class_builder()
+            .add_async_method("role_for", |repo: &Repo, user: User| {
+                let role = match (repo.id, user.name.as_str()) {
+                    (1, "alice") => Ok("admin".to_owned()),
+                    (_, "mallory") => Err(OsoError::Custom {
+                        message: "lookup failed".to_owned(),
+                    }),
+                    _ => Ok("guest".to_owned()),
+                };
+                delayed(role)
+            })
+            .build(),
+    )
+    .unwrap();
+    oso
+}
+
+fn user(name: &str) -> User {
+    User {
+        name: name.to_owned(),
+    }
+}
+
+#[test]
+fn test_async_attribute_getter() -> oso::Result<()> {
+    let mut oso = test_oso();
+    oso.load_str(r#"allow(user: User, "read", _: Repo) if user.team = "alice-team";"#)?;
+
+    block_on(async {
+        assert!(
+            oso.is_allowed_async(user("alice"), "read", Repo { id: 1 })
+                .await?
+        );
+        assert!(
+            !oso.is_allowed_async(user("bob"), "read", Repo { id: 1 })
+                .await?
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn test_async_method() -> oso::Result<()> {
+    let mut oso = test_oso();
+    oso.load_str(
+        r#"allow(user: User, "delete", repo: Repo) if
+               user.name != "bob" and repo.role_for(user) = "admin";"#,
+    )?;
+
+    block_on(async {
+        assert!(
+            oso.is_allowed_async(user("alice"), "delete", Repo { id: 1 })
+                .await?
+        );
+        assert!(
+            !oso.is_allowed_async(user("alice"), "delete", Repo { id: 2 })
+                .await?
+        );
+
+        let err = oso
+            .is_allowed_async(user("mallory"), "delete", Repo { id: 1 })
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("lookup failed"),
+            "unexpected error: {}",
+            err
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn test_async_iterator_method() -> oso::Result<()> {
+    let mut oso = test_oso();
+    oso.load_str("repo_id(user: User, id) if id in user.repo_ids();")?;
+
+    block_on(async {
+        let results = oso
+            .query_rule_async(
+                "repo_id",
+                (user("alice"), oso::PolarValue::Variable("id".to_owned())),
+            )?
+            .collect::<Vec<_>>()
+            .await;
+        let ids = results
+            .into_iter()
+            .map(|result| result?.get_typed::<i64>("id"))
+            .collect::<oso::Result<Vec<_>>>()?;
+        assert_eq!(ids, vec![1, 2]);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_async_members_in_sync_query() -> oso::Result<()> {
+    let mut oso = test_oso();
+    oso.load_str(r#"allow(user: User, "read", _: Repo) if user.team = "alice-team";"#)?;
+
+    let err = oso
+        .is_allowed(user("alice"), "read", Repo { id: 1 })
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("User.team is async"),
+        "unexpected error: {}",
+        err
+    );
+    Ok(())
+}
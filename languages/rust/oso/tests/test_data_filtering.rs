@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+
+use oso::data_filtering::{Comparison, DataAdapter, Datum, Filter, RelationKind};
+use oso::{Oso, OsoError, PolarClass, PolarValue, Relation, ToPolar};
+
+#[derive(PolarClass, Clone, Debug, PartialEq)]
+struct Org {
+    #[polar(attribute)]
+    id: i64,
+    #[polar(attribute)]
+    name: String,
+}
+
+#[derive(PolarClass, Clone, Debug, PartialEq)]
+struct Repo {
+    #[polar(attribute)]
+    id: i64,
+    #[polar(attribute)]
+    org_id: i64,
+    #[polar(attribute)]
+    is_public: bool,
+}
+
+#[derive(PolarClass, Clone, Debug)]
+struct User {
+    #[polar(attribute)]
+    org_name: String,
+    #[polar(attribute)]
+    repo_ids: Vec<i64>,
+}
+
+/// A record in the in-memory database.
+trait Row: Send + Sync {
+    fn get(&self, field: &str) -> PolarValue;
+    fn value(&self) -> PolarValue;
+}
+
+impl Row for Org {
+    fn get(&self, field: &str) -> PolarValue {
+        match field {
+            "id" => self.id.to_polar(),
+            "name" => self.name.clone().to_polar(),
+            _ => panic!("no field {} on Org", field),
+        }
+    }
+
+    fn value(&self) -> PolarValue {
+        self.clone().to_polar()
+    }
+}
+
+impl Row for Repo {
+    fn get(&self, field: &str) -> PolarValue {
+        match field {
+            "id" => self.id.to_polar(),
+            "org_id" => self.org_id.to_polar(),
+            "is_public" => self.is_public.to_polar(),
+            _ => panic!("no field {} on Repo", field),
+        }
+    }
+
+    fn value(&self) -> PolarValue {
+        self.clone().to_polar()
+    }
+}
+
+/// Evaluates filters against in-memory tables, keyed by class name.
+struct MemoryAdapter {
+    tables: HashMap<&'static str, Vec<Box<dyn Row>>>,
+}
+
+type Env<'a> = HashMap<String, &'a dyn Row>;
+
+impl MemoryAdapter {
+    fn datum(env: &Env, datum: &Datum) -> PolarValue {
+        match datum {
+            Datum::Immediate(value) => value.clone(),
+            Datum::Field(projection) => {
+                env[&projection.type_name].get(projection.field_name.as_deref().unwrap_or("id"))
+            }
+        }
+    }
+
+    fn holds(env: &Env, filter: &Filter) -> bool {
+        filter.conditions.iter().any(|conjuncts| {
+            conjuncts.iter().all(|condition| {
+                let lhs = Self::datum(env, &condition.lhs);
+                let rhs = Self::datum(env, &condition.rhs);
+                let contains = || match &rhs {
+                    PolarValue::List(values) => values.contains(&lhs),
+                    _ => false,
+                };
+                match condition.cmp {
+                    Comparison::Eq => lhs == rhs,
+                    Comparison::Neq => lhs != rhs,
+                    Comparison::In => contains(),
+                    Comparison::Nin => !contains(),
+                    cmp => panic!("unsupported comparison {:?}", cmp),
+                }
+            })
+        })
+    }
+
+    /// Extend `env` with every combination of related records and check the filter.
+    fn matches<'a>(&'a self, env: &mut Env<'a>, filter: &Filter, depth: usize) -> bool {
+        let relation = match filter.relations.get(depth) {
+            None => return Self::holds(env, filter),
+            Some(relation) => relation,
+        };
+        let declared = filter.relation(relation).unwrap();
+        let from = env[&relation.from_type_name].get(&declared.my_field);
+        for row in &self.tables[relation.to_type_name.as_str()] {
+            if row.get(&declared.other_field) == from {
+                env.insert(relation.to_type_name.clone(), row.as_ref());
+                if self.matches(env, filter, depth + 1) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl DataAdapter for MemoryAdapter {
+    type Query = Filter;
+
+    fn build_query(&self, filter: &Filter) -> oso::Result<Filter> {
+        Ok(filter.clone())
+    }
+
+    fn execute_query(&self, filter: Filter) -> oso::Result<Vec<PolarValue>> {
+        Ok(self.tables[filter.model.as_str()]
+            .iter()
+            .filter(|row| {
+                let mut env = Env::new();
+                env.insert(filter.model.clone(), row.as_ref());
+                self.matches(&mut env, &filter, 0)
+            })
+            .map(|row| row.value())
+            .collect())
+    }
+}
+
+fn orgs() -> Vec<Org> {
+    vec![
+        Org {
+            id: 1,
+            name: "apple".to_owned(),
+        },
+        Org {
+            id: 2,
+            name: "osohq".to_owned(),
+        },
+    ]
+}
+
+fn repos() -> Vec<Repo> {
+    vec![
+        Repo {
+            id: 1,
+            org_id: 1,
+            is_public: false,
+        },
+        Repo {
+            id: 2,
+            org_id: 2,
+            is_public: false,
+        },
+        Repo {
+            id: 3,
+            org_id: 2,
+            is_public: true,
+        },
+        Repo {
+            id: 4,
+            org_id: 1,
+            is_public: false,
+        },
+    ]
+}
+
+fn test_oso() -> Oso {
+    let mut oso = Oso::new();
+    oso.register_class(
+        Org::get_polar_class_builder()
+            .add_field::<i64>("id")
+            .add_field::<String>("name")
+            .add_relation("repos", Relation::many("Repo", "id", "org_id"))
+            .build(),
+    )
+    .unwrap();
+    oso.register_class(
+        Repo::get_polar_class_builder()
+            .add_field::<i64>("id")
+            .add_field::<i64>("org_id")
+            .add_field::<bool>("is_public")
+            .add_relation("org", Relation::one("Org", "org_id", "id"))
+            .build(),
+    )
+    .unwrap();
+    oso.register_class(User::get_polar_class()).unwrap();
+
+    let mut tables: HashMap<&'static str, Vec<Box<dyn Row>>> = HashMap::new();
+    tables.insert(
+        "Org",
+        orgs().into_iter().map(|o| Box::new(o) as _).collect(),
+    );
+    tables.insert(
+        "Repo",
+        repos().into_iter().map(|r| Box::new(r) as _).collect(),
+    );
+    oso.set_data_filtering_adapter(MemoryAdapter { tables });
+    oso
+}
+
+fn repo_ids(repos: Vec<Repo>) -> Vec<i64> {
+    let mut ids = repos.into_iter().map(|r| r.id).collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn test_authorized_resources_field_conditions() -> oso::Result<()> {
+    let mut oso = test_oso();
+    oso.load_str(
+        r#"allow(_: User, "read", repo: Repo) if repo.is_public;
+           allow(user: User, "write", repo: Repo) if repo.id in user.repo_ids;"#,
+    )?;
+
+    let user = User {
+        org_name: "apple".to_owned(),
+        repo_ids: vec![1, 2],
+    };
+
+    let readable: Vec<Repo> = oso.authorized_resources(user.clone(), "read", "Repo")?;
+    assert_eq!(repo_ids(readable), vec![3]);
+
+    let writable: Vec<Repo> = oso.authorized_resources(user.clone(), "write", "Repo")?;
+    assert_eq!(repo_ids(writable), vec![1, 2]);
+
+    let deletable: Vec<Repo> = oso.authorized_resources(user, "delete", "Repo")?;
+    assert!(deletable.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_authorized_resources_relations() -> oso::Result<()> {
+    let mut oso = test_oso();
+    oso.load_str(
+        r#"allow(user: User, "read", repo: Repo) if repo.org.name = user.org_name;
+           allow(user: User, "read", org: Org) if repo in org.repos and repo.id in user.repo_ids;"#,
+    )?;
+
+    let user = User {
+        org_name: "osohq".to_owned(),
+        repo_ids: vec![4],
+    };
+
+    let repos: Vec<Repo> = oso.authorized_resources(user.clone(), "read", "Repo")?;
+    assert_eq!(repo_ids(repos), vec![2, 3]);
+
+    let orgs: Vec<Org> = oso.authorized_resources(user, "read", "Org")?;
+    assert_eq!(orgs, vec![self::orgs()[0].clone()]);
+    Ok(())
+}
+
+#[test]
+fn test_authorized_query() -> oso::Result<()> {
+    let mut oso = test_oso();
+    oso.load_str(r#"allow(_: User, "read", repo: Repo) if repo.org.name = "apple";"#)?;
+
+    let user = User {
+        org_name: "apple".to_owned(),
+        repo_ids: vec![],
+    };
+    let filter: Filter = oso.authorized_query(user.clone(), "read", "Repo")?;
+    assert_eq!(filter.model, "Repo");
+    assert_eq!(filter.relations.len(), 1);
+    let relation = filter.relation(&filter.relations[0]).unwrap();
+    assert_eq!(relation.kind, RelationKind::One);
+    assert_eq!(relation.other_type, "Org");
+    assert_eq!(filter.conditions.len(), 1);
+    let condition = &filter.conditions[0][0];
+    assert_eq!(condition.cmp, Comparison::Eq);
+    assert!(
+        condition
+            .lhs
+            .eq(&Datum::Immediate(PolarValue::String("apple".to_owned())))
+            || condition
+                .rhs
+                .eq(&Datum::Immediate(PolarValue::String("apple".to_owned())))
+    );
+
+    // Asking for the wrong query type is a type error.
+    let err = oso
+        .authorized_query::<_, _, String>(user, "read", "Repo")
+        .unwrap_err();
+    assert!(matches!(err, OsoError::TypeError(_)), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn test_data_filtering_errors() -> oso::Result<()> {
+    let mut oso = Oso::new();
+    oso.register_class(User::get_polar_class())?;
+    oso.load_str(r#"allow(_: User, "read", _: User);"#)?;
+    let user = User {
+        org_name: "apple".to_owned(),
+        repo_ids: vec![],
+    };
+
+    let err = oso
+        .authorized_resources::<_, _, User>(user.clone(), "read", "User")
+        .unwrap_err();
+    assert!(
+        matches!(err, OsoError::MissingDataFilteringAdapter),
+        "{}",
+        err
+    );
+
+    oso.set_data_filtering_adapter(MemoryAdapter {
+        tables: HashMap::new(),
+    });
+    let err = oso
+        .authorized_resources::<_, _, User>(user, "read", "Unknown")
+        .unwrap_err();
+    assert!(matches!(err, OsoError::MissingClassError { .. }), "{}", err);
+    Ok(())
+}
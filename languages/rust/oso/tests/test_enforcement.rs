@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use oso::errors::AuthorizationError;
+use oso::{Oso, OsoError, PolarClass};
+
+#[derive(PolarClass, Clone, Debug)]
+struct User {
+    #[polar(attribute)]
+    name: String,
+}
+
+#[derive(PolarClass, Clone, Debug)]
+struct Widget {
+    #[polar(attribute)]
+    id: i64,
+}
+
+#[derive(PolarClass, Clone, Debug)]
+struct Request {
+    #[polar(attribute)]
+    path: String,
+}
+
+fn test_oso() -> Oso {
+    let mut oso = Oso::new();
+    oso.register_class(User::get_polar_class()).unwrap();
+    oso.register_class(Widget::get_polar_class()).unwrap();
+    oso.register_class(Request::get_polar_class()).unwrap();
+    oso.load_str(
+        r#"allow(_: User{name: "guest"}, "read", _: Widget{id: 1});
+           allow(_: User{name: "admin"}, _action, _: Widget);
+           allow(_: User{name: "reader"}, "fetch", _: Widget);
+           allow_request(_: User{name: "guest"}, request: Request) if request.path = "/";
+           allow_field(_: User{name: "guest"}, "read", _: Widget{id: 1}, "name");
+           allow_field(_: User{name: "guest"}, "read", _: Widget{id: 1}, "purpose");
+           allow_field(_: User{name: "admin"}, "read", _: Widget, _field);"#,
+    )
+    .unwrap();
+    oso
+}
+
+fn user(name: &str) -> User {
+    User {
+        name: name.to_owned(),
+    }
+}
+
+fn assert_authorization_error(result: oso::Result<()>, expected: AuthorizationError) {
+    match result {
+        Err(OsoError::AuthorizationError(err)) => assert_eq!(err, expected),
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    }
+}
+
+#[test]
+fn test_authorize() -> oso::Result<()> {
+    let oso = test_oso();
+    let (public, private) = (Widget { id: 1 }, Widget { id: 2 });
+
+    oso.authorize(user("guest"), "read", public.clone())?;
+    oso.authorize(user("admin"), "delete", private.clone())?;
+
+    assert_authorization_error(
+        oso.authorize(user("guest"), "delete", public),
+        AuthorizationError::Forbidden,
+    );
+    assert_authorization_error(
+        oso.authorize(user("guest"), "read", private.clone()),
+        AuthorizationError::NotFound,
+    );
+    assert_authorization_error(
+        oso.authorize(user("guest"), "delete", private),
+        AuthorizationError::NotFound,
+    );
+    Ok(())
+}
+
+#[test]
+fn test_authorize_read_action() -> oso::Result<()> {
+    let mut oso = test_oso();
+    let widget = Widget { id: 1 };
+
+    assert_authorization_error(
+        oso.authorize(user("reader"), "delete", widget.clone()),
+        AuthorizationError::NotFound,
+    );
+
+    oso.set_read_action("fetch");
+    assert_authorization_error(
+        oso.authorize(user("reader"), "delete", widget),
+        AuthorizationError::Forbidden,
+    );
+    Ok(())
+}
+
+#[test]
+fn test_authorize_request() -> oso::Result<()> {
+    let oso = test_oso();
+    let request = |path: &str| Request {
+        path: path.to_owned(),
+    };
+
+    oso.authorize_request(user("guest"), request("/"))?;
+    assert_authorization_error(
+        oso.authorize_request(user("guest"), request("/admin")),
+        AuthorizationError::Forbidden,
+    );
+    assert_authorization_error(
+        oso.authorize_request(user("admin"), request("/")),
+        AuthorizationError::Forbidden,
+    );
+    Ok(())
+}
+
+#[test]
+fn test_authorize_field() -> oso::Result<()> {
+    let oso = test_oso();
+
+    oso.authorize_field(user("guest"), "read", Widget { id: 1 }, "name")?;
+    assert_authorization_error(
+        oso.authorize_field(user("guest"), "update", Widget { id: 1 }, "name"),
+        AuthorizationError::Forbidden,
+    );
+    assert_authorization_error(
+        oso.authorize_field(user("guest"), "read", Widget { id: 1 }, "owner"),
+        AuthorizationError::Forbidden,
+    );
+    Ok(())
+}
+
+#[test]
+fn test_authorized_actions() -> oso::Result<()> {
+    let oso = test_oso();
+
+    let actions: HashSet<String> =
+        oso.authorized_actions(user("guest"), Widget { id: 1 }, false)?;
+    assert_eq!(actions, vec!["read".to_owned()].into_iter().collect());
+
+    let actions: HashSet<String> =
+        oso.authorized_actions(user("guest"), Widget { id: 2 }, false)?;
+    assert!(actions.is_empty());
+
+    let actions: HashSet<String> = oso.authorized_actions(user("admin"), Widget { id: 1 }, true)?;
+    assert_eq!(actions, vec!["*".to_owned()].into_iter().collect());
+
+    let err = oso
+        .authorized_actions::<_, _, String>(user("admin"), Widget { id: 1 }, false)
+        .unwrap_err();
+    assert!(err.to_string().contains("allow_wildcard"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn test_authorized_fields() -> oso::Result<()> {
+    let oso = test_oso();
+
+    let fields: HashSet<String> =
+        oso.authorized_fields(user("guest"), "read", Widget { id: 1 }, false)?;
+    assert_eq!(
+        fields,
+        vec!["name".to_owned(), "purpose".to_owned()]
+            .into_iter()
+            .collect()
+    );
+
+    let fields: HashSet<String> =
+        oso.authorized_fields(user("guest"), "update", Widget { id: 1 }, false)?;
+    assert!(fields.is_empty());
+
+    let fields: HashSet<String> =
+        oso.authorized_fields(user("admin"), "read", Widget { id: 2 }, true)?;
+    assert_eq!(fields, vec!["*".to_owned()].into_iter().collect());
+
+    let err = oso
+        .authorized_fields::<_, _, _, String>(user("admin"), "read", Widget { id: 2 }, false)
+        .unwrap_err();
+    assert!(err.to_string().contains("allow_wildcard"), "{}", err);
+    Ok(())
+}
@@ -93,6 +93,33 @@ fn test_unify_external_not_supported() -> oso::Result<()> {
 }
 
 /// Test that failing inline query sends a sane error message
+#[test]
+fn test_comparison_external_not_supported() -> oso::Result<()> {
+    common::setup();
+
+    #[derive(PolarClass)]
+    struct Foo;
+
+    let mut oso = OsoTest::new();
+    oso.oso.register_class(Foo::get_polar_class())?;
+    oso.load_str("lt(x, y) if x < y;");
+
+    let mut query = oso.oso.query_rule("lt", (Foo, Foo))?;
+    let error = query.next().unwrap().unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            OsoError::UnsupportedOperation {
+                operation,
+                type_name
+            } if operation == "comparison" && type_name == "Foo"),
+        "{} doesn't match expected error",
+        error
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_failing_inline_query() {
     common::setup();
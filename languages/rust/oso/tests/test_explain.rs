@@ -0,0 +1,108 @@
+use oso::explain::{Explanation, Outcome, Step};
+use oso::{Oso, PolarClass};
+
+#[derive(PolarClass, Clone)]
+struct User {
+    #[polar(attribute)]
+    name: String,
+    #[polar(attribute)]
+    is_admin: bool,
+}
+
+#[derive(PolarClass, Clone)]
+struct Repo {
+    #[polar(attribute)]
+    owner: String,
+    #[polar(attribute)]
+    is_public: bool,
+}
+
+fn test_oso() -> oso::Result<Oso> {
+    let mut oso = Oso::new();
+    oso.register_class(User::get_polar_class())?;
+    oso.register_class(Repo::get_polar_class())?;
+    oso.load_str(
+        r#"allow(_: User, "read", repo: Repo) if repo.is_public;
+           allow(user: User, "read", repo: Repo) if repo.owner = user.name;
+           allow(user: User, _action, _: Repo) if user.is_admin;"#,
+    )?;
+    Ok(oso)
+}
+
+fn user(name: &str) -> User {
+    User {
+        name: name.to_owned(),
+        is_admin: false,
+    }
+}
+
+fn repo(owner: &str) -> Repo {
+    Repo {
+        owner: owner.to_owned(),
+        is_public: false,
+    }
+}
+
+#[test]
+fn test_explain_allowed() -> oso::Result<()> {
+    let oso = test_oso()?;
+    let explanation = oso.explain_allowed(user("alice"), "read", repo("alice"))?;
+    assert!(oso.is_allowed(user("alice"), "read", repo("alice"))?);
+
+    let proof = match explanation {
+        Explanation::Success { proof, .. } => proof,
+        failure => panic!("expected success, got {:?}", failure),
+    };
+    assert!(matches!(
+        &proof.children[0].step,
+        Step::Rule { rule, .. } if rule == r#"allow(user: User, "read", repo: Repo)"#
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_explain_denied() -> oso::Result<()> {
+    let oso = test_oso()?;
+    let explanation = oso.explain_allowed(user("bob"), "read", repo("alice"))?;
+    assert!(!oso.is_allowed(user("bob"), "read", repo("alice"))?);
+    assert!(!explanation.is_success());
+
+    // Explanations serialize for consumption outside of Rust.
+    let json = serde_json::to_value(&explanation).unwrap();
+    assert_eq!(json["result"], "failure");
+
+    let candidates = match explanation {
+        Explanation::Failure { candidates, .. } => candidates,
+        success => panic!("expected failure, got {:?}", success),
+    };
+    let conditions = candidates
+        .iter()
+        .map(|candidate| match &candidate.outcome {
+            Outcome::ConditionFailed { condition, .. } => condition.as_str(),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        conditions,
+        vec!["repo.is_public", "repo.owner = user.name", "user.is_admin"]
+    );
+
+    // Rules whose parameters don't match the arguments are reported as such.
+    let explanation = oso.explain_allowed(user("bob"), "read", user("alice"))?;
+    let candidates = match explanation {
+        Explanation::Failure { candidates, .. } => candidates,
+        success => panic!("expected failure, got {:?}", success),
+    };
+    let parameters = candidates
+        .iter()
+        .map(|candidate| match &candidate.outcome {
+            Outcome::ParameterMismatch {
+                index: 2,
+                parameter,
+            } => parameter.as_str(),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(parameters, vec!["repo: Repo", "repo: Repo", "_: Repo"]);
+    Ok(())
+}
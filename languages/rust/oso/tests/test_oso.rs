@@ -1,6 +1,8 @@
-use oso::{Action, Oso, PolarClass};
+use oso::decision_cache::{CacheStats, DecisionCache};
+use oso::{Action, Clock, FixedClock, Oso, PolarClass, Timestamp};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 mod common;
 
@@ -102,6 +104,85 @@ fn test_is_allowed() -> oso::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_is_allowed_decision_cache() -> oso::Result<()> {
+    common::setup();
+    let mut oso = test_oso();
+    let cache = DecisionCache::new()
+        .with_key(|user: &User| user.name.clone())
+        .with_key(|widget: &Widget| widget.id.to_string());
+    oso.set_decision_cache(Some(cache));
+    let stats = |oso: &Oso| oso.decision_cache().unwrap().stats();
+
+    let guest = User::new(String::from("guest"));
+    assert!(oso.is_allowed(guest.clone(), "get", Widget::new(1))?);
+    assert!(oso.is_allowed(guest.clone(), "get", Widget::new(1))?);
+    assert!(!oso.is_allowed(guest.clone(), "delete", Widget::new(1))?);
+    assert_eq!(
+        stats(&oso),
+        CacheStats {
+            hits: 1,
+            misses: 2,
+            uncacheable: 0
+        }
+    );
+
+    // Companies have no key function.
+    oso.is_allowed(guest.clone(), "create", Company::new(1))?;
+    assert_eq!(stats(&oso).uncacheable, 1);
+
+    // Clones share the cache.
+    assert!(oso
+        .clone()
+        .is_allowed(guest.clone(), "get", Widget::new(1))?);
+    assert_eq!(stats(&oso).hits, 2);
+
+    // Loading a policy clears the cache.
+    oso.clear_rules()?;
+    oso.load_str(r#"allow(_: User, "delete", _: Widget);"#)?;
+    assert!(oso.is_allowed(guest.clone(), "delete", Widget::new(1))?);
+    assert!(!oso.is_allowed(guest, "get", Widget::new(1))?);
+    assert_eq!(stats(&oso).hits, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_decision_cache_with_now() -> oso::Result<()> {
+    common::setup();
+
+    /// A clock that tests can move forward.
+    #[derive(Clone, Default)]
+    struct TestClock(Arc<Mutex<i64>>);
+
+    impl Clock for TestClock {
+        fn now(&self) -> Timestamp {
+            Timestamp::from_unix(*self.0.lock().unwrap(), 0).unwrap()
+        }
+    }
+
+    let at = |secs| Timestamp::from_unix(secs, 0).unwrap();
+    let mut oso = Oso::new();
+    oso.set_decision_cache(Some(DecisionCache::new()));
+    oso.set_clock(Arc::new(FixedClock(at(10))));
+    oso.load_str(r#"allow(_, "read", expires_at: Timestamp) if now() < expires_at;"#)?;
+    assert!(oso.is_allowed("alice", "read", at(20))?);
+    assert!(oso.is_allowed("alice", "read", at(20))?);
+
+    // A decision cached as `true` expires with the grant.
+    oso.set_clock(Arc::new(FixedClock(at(30))));
+    assert!(!oso.is_allowed("alice", "read", at(20))?);
+
+    let clock = TestClock::default();
+    oso.set_clock(Arc::new(clock.clone()));
+    assert!(oso.is_allowed("alice", "read", at(20))?);
+    *clock.0.lock().unwrap() = 30;
+    assert!(!oso.is_allowed("alice", "read", at(20))?);
+    assert_eq!(oso.decision_cache().unwrap().stats().hits, 0);
+
+    Ok(())
+}
+
 #[test]
 fn test_query_rule() -> oso::Result<()> {
     common::setup();
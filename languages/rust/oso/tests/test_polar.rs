@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use oso::{Class, FromPolar, Oso, OsoError, PolarClass, PolarValue};
+use oso::{Class, FromPolar, Oso, OsoError, PolarClass, PolarValue, QueryOptions};
 use polar_core::error as polar_error;
 
 use maplit::hashmap;
@@ -213,6 +213,105 @@ fn test_load_file_error_contains_filename() {
     }
 }
 
+#[test]
+fn test_reload_files() {
+    common::setup();
+    let mut oso = test_oso();
+
+    let policy = |src: &str| {
+        let mut tempfile = tempfile::Builder::new()
+            .suffix(".polar")
+            .tempfile()
+            .unwrap();
+        write!(tempfile.as_file_mut(), "{}", src).unwrap();
+        tempfile
+    };
+    let rewrite = |tempfile: &tempfile::NamedTempFile, src: &str| {
+        std::fs::write(tempfile.path(), src).unwrap();
+    };
+    let a = policy("f(1);");
+    let b = policy("f(2);");
+
+    oso.oso.load_files(vec![a.path(), b.path()]).unwrap();
+    assert_eq!(oso.qvar::<i64>("f(x)", "x"), vec![1, 2]);
+
+    // Reloading doesn't need the rules to be cleared first.
+    rewrite(&a, "f(3);");
+    oso.oso.reload_files(vec![a.path(), b.path()]).unwrap();
+    assert_eq!(oso.qvar::<i64>("f(x)", "x"), vec![3, 2]);
+
+    rewrite(&b, "f(4);");
+    oso.oso.replace_file(b.path()).unwrap();
+    assert_eq!(oso.qvar::<i64>("f(x)", "x"), vec![3, 4]);
+
+    // A file that fails to load, or whose inline queries fail, leaves the policy as it was.
+    rewrite(&b, "f(5)");
+    oso.oso.replace_file(b.path()).unwrap_err();
+    rewrite(&b, "f(5); ?= f(6);");
+    let err = oso.oso.replace_file(b.path()).unwrap_err();
+    assert!(matches!(err, OsoError::InlineQueryFailedError { .. }));
+    assert_eq!(oso.qvar::<i64>("f(x)", "x"), vec![3, 4]);
+
+    // Inline queries are checked before the new policy is swapped in, so other queries, e.g.
+    // by clones, never see a policy that might be rolled back.
+    #[derive(PolarClass)]
+    struct Probe;
+    let clone = oso.oso.clone();
+    let probe = Probe::get_polar_class_builder().add_class_method("has_f", move |x: i64| {
+        clone.query_rule("f", (x,)).unwrap().next().is_some()
+    });
+    oso.oso.register_class(probe.build()).unwrap();
+    rewrite(&b, "f(7); ?= not Probe.has_f(7);");
+    oso.oso.replace_file(b.path()).unwrap();
+    assert_eq!(oso.qvar::<i64>("f(x)", "x"), vec![3, 7]);
+    // Inline queries are checked on every reload.
+    oso.oso.remove_file(a.path()).unwrap_err();
+    rewrite(&b, "f(7);");
+    oso.oso.replace_file(b.path()).unwrap();
+
+    oso.oso.remove_file(a.path()).unwrap();
+    assert_eq!(oso.qvar::<i64>("f(x)", "x"), vec![7]);
+}
+
+#[test]
+fn test_query_options() {
+    common::setup();
+    let mut oso = test_oso();
+    oso.load_str("f(1); f(2); loop(x) if loop(x);");
+
+    let options = QueryOptions {
+        max_goals: Some(1000),
+        ..Default::default()
+    };
+    let mut query = oso.oso.query_with_options("loop(1)", &options).unwrap();
+    let err = query.next().unwrap().unwrap_err();
+    assert!(matches!(
+        err,
+        OsoError::Polar(polar_error::PolarError(polar_error::ErrorKind::Runtime(
+            polar_error::RuntimeError::GoalLimitExceeded { limit: 1000 }
+        )))
+    ));
+
+    // Queries under the limits run as usual.
+    let query = oso
+        .oso
+        .query_rule_with_options("f", (PolarValue::Variable("x".to_owned()),), &options)
+        .unwrap();
+    assert_eq!(query.count(), 2);
+
+    // A cancelled query fails on its next result.
+    let mut query = oso.oso.query("f(x)").unwrap();
+    assert!(query.next().unwrap().is_ok());
+    query.cancellation_handle().cancel();
+    let err = query.next().unwrap().unwrap_err();
+    assert!(matches!(
+        err,
+        OsoError::Polar(polar_error::PolarError(polar_error::ErrorKind::Runtime(
+            polar_error::RuntimeError::QueryCancelled
+        )))
+    ));
+}
+
 #[test]
 fn test_load_file_extension_check() {
     common::setup();
@@ -378,6 +477,62 @@ fn test_register_constant() -> oso::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_run_tests() {
+    common::setup();
+
+    let mut oso = OsoTest::new();
+    oso.load_str(
+        r#"allow(actor, "read", repo) if actor.name in repo.members;
+           test "members can read" {
+               alice = {name: "alice"};
+               repo = {members: ["alice"]};
+               assert allow(alice, "read", repo);
+               assert_not allow(alice, "delete", repo);
+           }
+           test "broken" {
+               bob = {name: "bob"};
+               assert allow(bob, "read", {members: ["bob"]});
+               assert allow(bob, "read", {members: []});
+               assert allow(bob, "read", new Repository());
+           }"#,
+    );
+
+    let outcomes = oso.oso.run_tests();
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0].name, "members can read");
+    assert!(outcomes[0].passed());
+
+    assert_eq!(outcomes[1].name, "broken");
+    let failures = &outcomes[1].failures;
+    assert_eq!(failures.len(), 2);
+    assert!(failures[0].expected);
+    assert!(failures[0].error.is_none());
+    assert!(failures[0]
+        .location
+        .starts_with(r#"allow(bob, "read", {members: []}) at line 11"#));
+    assert!(failures[1].error.is_some());
+}
+
+#[test]
+fn test_table_rule() {
+    common::setup();
+
+    let mut oso = OsoTest::new();
+    oso.oso.table_rule("member");
+    oso.load_str(
+        r#"parent("a", "b");
+           parent("b", "c");
+           parent("c", "a");
+           member(x, y) if member(x, z) and parent(z, y);
+           member(x, y) if parent(x, y);"#,
+    );
+
+    let mut groups = oso.qvar::<String>(r#"member("a", g)"#, "g");
+    groups.sort();
+    assert_eq!(groups, vec!["a", "b", "c"]);
+}
+
 #[ignore]
 #[test]
 fn test_host_method_string() {
@@ -852,6 +1007,47 @@ fn test_nan_inf() -> oso::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_timestamps_and_durations() -> oso::Result<()> {
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    common::setup();
+
+    let mut oso = test_oso();
+    let epoch_day = UNIX_EPOCH + Duration::from_secs(86_400);
+    oso.oso.register_constant(epoch_day, "epoch_day")?;
+    oso.oso
+        .register_constant(Duration::from_millis(1500), "timeout")?;
+
+    oso.qeval("epoch_day = @1970-01-02 and epoch_day matches Timestamp");
+    oso.qeval("timeout = 1500ms and timeout matches Duration");
+    oso.qeval("epoch_day.unix_secs() = 86400 and not timeout.is_negative()");
+    oso.qvar_one(
+        "x = @1970-01-02 + 1500ms",
+        "x",
+        epoch_day + Duration::from_millis(1500),
+    );
+    oso.qvar_one(
+        "x = @1970-01-02 - @1970-01-01",
+        "x",
+        Duration::from_secs(86_400),
+    );
+
+    // Negative durations have no `std::time::Duration` equivalent.
+    let result = oso.oso.query("x = -1s")?.next().unwrap()?;
+    assert!(result.get_typed::<Duration>("x").is_err());
+    assert!(result.get_typed::<oso::Duration>("x")?.is_negative());
+
+    oso.oso.set_clock(Arc::new(oso::FixedClock(
+        oso::Timestamp::try_from(epoch_day).unwrap(),
+    )));
+    oso.qvar_one("x = now()", "x", epoch_day);
+    oso.qvar_one("x = now() - 1d", "x", SystemTime::UNIX_EPOCH);
+
+    Ok(())
+}
+
 #[test]
 fn test_iterators() -> oso::Result<()> {
     common::setup();
@@ -4,7 +4,7 @@
 use maplit::hashmap;
 use thiserror::Error;
 
-use oso::{ClassBuilder, PolarClass};
+use oso::{ClassBuilder, OsoError, PolarClass};
 
 mod common;
 
@@ -140,6 +140,273 @@ fn test_type_mismatch_fails_unification() {
     assert!(rs[0].is_empty(), "expected empty result");
 }
 
+#[test]
+fn test_external_comparison() {
+    common::setup();
+
+    #[derive(PartialEq, PartialOrd, PolarClass, Clone)]
+    struct Version(i64, i64);
+    #[derive(PolarClass, Clone)]
+    struct Timestamp(i64);
+
+    let mut test = OsoTest::new();
+    test.oso
+        .register_class(
+            Version::get_polar_class_builder()
+                .set_constructor(Version)
+                .with_equality_check()
+                .with_ord()
+                .build(),
+        )
+        .unwrap();
+    test.oso
+        .register_class(
+            Timestamp::get_polar_class_builder()
+                .set_constructor(Timestamp)
+                .set_comparison(|a, b| a.0.partial_cmp(&b.0))
+                .build(),
+        )
+        .unwrap();
+
+    test.qeval("new Version(1, 2) < new Version(1, 10)");
+    test.qeval("new Version(1, 2) <= new Version(1, 2)");
+    test.qeval("new Version(2, 0) > new Version(1, 10)");
+    test.qeval("new Version(2, 0) >= new Version(2, 0)");
+    test.qeval("new Version(2, 0) != new Version(2, 1)");
+    test.qnull("new Version(2, 0) != new Version(2, 0)");
+    test.qnull("new Version(2, 0) < new Version(2, 0)");
+    test.qnull("new Version(2, 0) > new Version(2, 0)");
+    test.qeval("new Timestamp(1) < new Timestamp(2)");
+    test.qnull("new Timestamp(2) <= new Timestamp(1)");
+
+    // Instances of different types are unordered.
+    test.qnull("new Version(1, 0) < new Timestamp(2)");
+    test.qnull("new Version(1, 0) >= new Timestamp(2)");
+    test.qnull("new Version(1, 0) < 2");
+
+    test.load_str(
+        r#"allow(_, "download", version: Version) if
+             version >= new Version(1, 4) and version < new Version(2, 0);"#,
+    );
+    assert!(test
+        .oso
+        .is_allowed("user", "download", Version(1, 10))
+        .unwrap());
+    assert!(!test
+        .oso
+        .is_allowed("user", "download", Version(2, 0))
+        .unwrap());
+}
+
+#[test]
+fn test_class_inheritance() {
+    common::setup();
+
+    #[derive(PolarClass, Clone)]
+    struct Principal;
+    #[derive(PolarClass, Clone)]
+    struct User;
+    #[derive(PolarClass, Clone)]
+    struct Admin;
+    #[derive(PolarClass, Clone)]
+    struct Repo;
+
+    let mut test = OsoTest::new();
+    test.oso
+        .register_class(Principal::get_polar_class())
+        .unwrap();
+    test.oso
+        .register_class(
+            User::get_polar_class_builder()
+                .set_constructor(|| User)
+                .implements("Principal")
+                .build(),
+        )
+        .unwrap();
+    test.oso
+        .register_class(
+            Admin::get_polar_class_builder()
+                .set_constructor(|| Admin)
+                .extends::<User>()
+                .build(),
+        )
+        .unwrap();
+    test.oso.register_class(Repo::get_polar_class()).unwrap();
+
+    // Rules with more specific specializers come first.
+    test.load_str(
+        r#"kind(_: Principal, "principal");
+           kind(_: Admin, "admin");
+           kind(_: User, "user");
+
+           actor User {}
+           resource Repo { roles = ["owner"]; }
+           has_role(_: Admin, "owner", _: Repo);"#,
+    );
+    assert_eq!(
+        test.qvar::<String>("kind(new Admin(), kind)", "kind"),
+        vec!["admin", "user", "principal"]
+    );
+    assert_eq!(
+        test.qvar::<String>("kind(new User(), kind)", "kind"),
+        vec!["user", "principal"]
+    );
+    test.qeval("new Admin() matches Principal");
+    test.qnull("new User() matches Admin");
+    assert!(test
+        .oso
+        .query_rule("has_role", (Admin, "owner", Repo))
+        .unwrap()
+        .next()
+        .is_some());
+}
+
+#[test]
+fn test_class_inheritance_order() {
+    common::setup();
+
+    #[derive(PolarClass, Clone)]
+    struct A;
+    #[derive(PolarClass, Clone)]
+    struct B;
+    #[derive(PolarClass, Clone)]
+    struct C;
+    #[derive(PolarClass, Clone)]
+    struct D;
+
+    let mut test = OsoTest::new();
+    test.oso.register_class(A::get_polar_class()).unwrap();
+    test.oso
+        .register_class(B::get_polar_class_builder().extends::<A>().build())
+        .unwrap();
+    test.oso
+        .register_class(C::get_polar_class_builder().extends::<A>().build())
+        .unwrap();
+    test.oso
+        .register_class(
+            D::get_polar_class_builder()
+                .set_constructor(|| D)
+                .extends::<B>()
+                .extends::<C>()
+                .build(),
+        )
+        .unwrap();
+
+    // A diamond: `A` comes after both of its subtypes.
+    test.load_str(
+        r#"kind(_: A, "a");
+           kind(_: C, "c");
+           kind(_: B, "b");
+           kind(_: D, "d");"#,
+    );
+    assert_eq!(
+        test.qvar::<String>("kind(new D(), kind)", "kind"),
+        vec!["d", "b", "c", "a"]
+    );
+}
+
+#[test]
+fn test_inconsistent_class_inheritance() {
+    common::setup();
+
+    #[derive(PolarClass, Clone)]
+    struct A;
+    #[derive(PolarClass, Clone)]
+    struct B;
+    #[derive(PolarClass, Clone)]
+    struct C;
+
+    let mut test = OsoTest::new();
+    test.oso.register_class(A::get_polar_class()).unwrap();
+    test.oso
+        .register_class(B::get_polar_class_builder().extends::<A>().build())
+        .unwrap();
+    // `A` can't come both before & after `B`.
+    test.oso
+        .register_class(
+            C::get_polar_class_builder()
+                .extends::<A>()
+                .extends::<B>()
+                .build(),
+        )
+        .unwrap();
+
+    let err = test.oso.load_str("f(_: C);").unwrap_err();
+    assert!(
+        matches!(err, OsoError::InconsistentSupertypes { ref name } if name == "C"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_external_kwargs() {
+    common::setup();
+
+    #[derive(PolarClass, Clone)]
+    struct User {
+        #[polar(attribute)]
+        name: String,
+        #[polar(attribute)]
+        age: i64,
+    }
+
+    impl User {
+        fn greet(&self, greeting: String, punctuation: String) -> String {
+            format!("{}, {}{}", greeting, self.name, punctuation)
+        }
+    }
+
+    let mut test = OsoTest::new();
+    test.oso
+        .register_class(
+            User::get_polar_class_builder()
+                .set_constructor_with_kwargs(&["name", "age"], |name, age| User { name, age })
+                .add_method_with_kwargs("greet", &["greeting", "punctuation"], User::greet)
+                .build(),
+        )
+        .unwrap();
+
+    test.qvar_one("new User(age: 30, name: \"alice\").age = x", "x", 30);
+    test.qvar_one(
+        "new User(\"alice\", age: 30).name = x",
+        "x",
+        "alice".to_owned(),
+    );
+    test.qvar_one("new User(\"alice\", 30).name = x", "x", "alice".to_owned());
+    test.qvar_one(
+        "x = new User(\"alice\", 30).greet(punctuation: \"!\", greeting: \"hi\")",
+        "x",
+        "hi, alice!".to_owned(),
+    );
+    test.qvar_one(
+        "x = new User(\"alice\", 30).greet(\"hi\", punctuation: \".\")",
+        "x",
+        "hi, alice.".to_owned(),
+    );
+
+    let err = test.query_err("x = new User(\"alice\", 30).greet(\"hi\", greeting: \"hi\")");
+    assert!(
+        err.contains("User.greet is missing the argument punctuation."),
+        "{}",
+        err
+    );
+    let err = test.query_err(
+        "x = new User(\"alice\", 30).greet(greeting: \"hi\", punctuation: \"!\", tone: \"loud\")",
+    );
+    assert!(
+        err.contains("User.greet got an unexpected keyword argument tone."),
+        "{}",
+        err
+    );
+    let err = test.query_err("x = new User(\"alice\", name: \"bob\")");
+    assert!(
+        err.contains("User constructor is missing the argument age."),
+        "{}",
+        err
+    );
+}
+
 #[test]
 fn test_external() {
     common::setup();
@@ -788,3 +1055,39 @@ fn test_uuid_10() -> Result<(), Box<dyn std::error::Error>> {
     test.oso.query_rule("f", (x, y))?.next().unwrap()?;
     Ok(())
 }
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::{DateTime, Duration, Utc};
+    let mut test = OsoTest::new();
+    test.load_str("f(x: Timestamp, y: Duration) if x + y = @2024-01-01T01:30:00Z;");
+    let x = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")?.with_timezone(&Utc);
+    let y = Duration::minutes(90);
+    test.oso.query_rule("f", (x, y))?.next().unwrap()?;
+    test.qvar_one(
+        "x = @2024-01-01T00:00:00.5Z",
+        "x",
+        x + Duration::milliseconds(500),
+    );
+    test.qvar_one("x = -90m", "x", -y);
+    Ok(())
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn test_big_numbers() -> Result<(), Box<dyn std::error::Error>> {
+    use oso::{BigDecimal, BigInt};
+    let mut test = OsoTest::new();
+    test.load_str("f(x: Integer, y: Decimal) if x * y > 1000000000000000000000M;");
+    let x: BigInt = "1000000000000000000000".parse()?;
+    let y: BigDecimal = "1.5".parse()?;
+    test.oso
+        .query_rule("f", (x.clone(), y.clone()))?
+        .next()
+        .unwrap()?;
+    test.qvar_one("x = 1000000000000000000000 + 1", "x", x + 1);
+    test.qvar_one("x = 0.1M + 0.2M", "x", "0.3".parse::<BigDecimal>()?);
+    test.qvar_one("x = 5N", "x", 5);
+    Ok(())
+}
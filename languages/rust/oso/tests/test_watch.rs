@@ -0,0 +1,139 @@
+#![cfg(feature = "watch")]
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use oso::decision_cache::DecisionCache;
+use oso::watch::WatchEvent;
+use oso::{Oso, PolarClass};
+
+mod common;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn test_watch_reloads_changed_files() {
+    common::setup();
+
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.polar");
+    let b = dir.path().join("b.polar");
+    std::fs::write(&a, "f(1);").unwrap();
+    std::fs::write(&b, "f(2);").unwrap();
+
+    let mut oso = Oso::new();
+    oso.load_files(vec![&a, &b]).unwrap();
+    let (sender, events) = mpsc::channel::<WatchEvent>();
+    let watcher = oso.watch(move |event| sender.send(event).unwrap()).unwrap();
+    let results = |oso: &Oso| -> Vec<i64> {
+        oso.query("f(x)")
+            .unwrap()
+            .map(|result| result.unwrap().get_typed("x").unwrap())
+            .collect()
+    };
+
+    std::fs::write(&a, "f(3);").unwrap();
+    let event = events.recv_timeout(TIMEOUT).unwrap();
+    assert!(event.result.is_ok());
+    assert_eq!(event.changed, vec![a.canonicalize().unwrap()]);
+    assert_eq!(results(&oso), vec![3, 2]);
+
+    // A change with errors is reported and leaves the policy as it was.
+    std::fs::write(&b, "f(4)").unwrap();
+    let event = events.recv_timeout(TIMEOUT).unwrap();
+    assert!(event.result.is_err());
+    assert!(event.diagnostics.iter().any(|d| d.is_error()));
+    assert_eq!(results(&oso), vec![3, 2]);
+
+    // Changes aren't picked up after the watcher is dropped.
+    drop(watcher);
+    std::fs::write(&b, "f(5);").unwrap();
+    assert!(events.recv_timeout(Duration::from_millis(500)).is_err());
+    assert_eq!(results(&oso), vec![3, 2]);
+}
+
+#[test]
+fn test_watch_follows_loaded_files() {
+    common::setup();
+
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.polar");
+    let b = dir.path().join("b.polar");
+    std::fs::write(&a, "f(1);").unwrap();
+    std::fs::write(&b, "f(2);").unwrap();
+    // `c` is in a directory that isn't watched yet.
+    let other = tempfile::tempdir().unwrap();
+    let c = other.path().join("c.polar");
+    std::fs::write(&c, "f(3);").unwrap();
+
+    let mut oso = Oso::new();
+    oso.load_files(vec![&a, &b]).unwrap();
+    let (sender, events) = mpsc::channel::<WatchEvent>();
+    let _watcher = oso.watch(move |event| sender.send(event).unwrap()).unwrap();
+    let results = |oso: &Oso| -> Vec<i64> {
+        oso.query("f(x)")
+            .unwrap()
+            .map(|result| result.unwrap().get_typed("x").unwrap())
+            .collect()
+    };
+
+    // Files removed while watching aren't reloaded.
+    oso.remove_file(&b).unwrap();
+    oso.replace_file(&c).unwrap();
+    assert_eq!(results(&oso), vec![1, 3]);
+
+    std::fs::write(&a, "f(4);").unwrap();
+    let event = events.recv_timeout(TIMEOUT).unwrap();
+    assert!(event.result.is_ok());
+    assert_eq!(event.changed, vec![a.canonicalize().unwrap()]);
+    assert_eq!(results(&oso), vec![4, 3]);
+
+    // Files added while watching are reloaded.
+    std::fs::write(&c, "f(5);").unwrap();
+    let event = events.recv_timeout(TIMEOUT).unwrap();
+    assert!(event.result.is_ok());
+    assert_eq!(event.changed, vec![c.canonicalize().unwrap()]);
+    assert_eq!(results(&oso), vec![4, 5]);
+
+    // Changes to removed files are ignored.
+    std::fs::write(&b, "f(6);").unwrap();
+    assert!(events.recv_timeout(Duration::from_millis(500)).is_err());
+    assert_eq!(results(&oso), vec![4, 5]);
+}
+
+#[test]
+fn test_watch_uses_latest_oso() {
+    common::setup();
+
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.polar");
+    std::fs::write(&a, r#"allow(_, "read", 1);"#).unwrap();
+
+    let mut oso = Oso::new();
+    oso.load_files(vec![&a]).unwrap();
+    let (sender, events) = mpsc::channel::<WatchEvent>();
+    let _watcher = oso.watch(move |event| sender.send(event).unwrap()).unwrap();
+
+    // A cache set after watching is cleared by reloads.
+    oso.set_decision_cache(Some(DecisionCache::new()));
+    assert!(oso.is_allowed("alice", "read", 1).unwrap());
+    assert!(oso.is_allowed("alice", "read", 1).unwrap());
+    assert_eq!(oso.decision_cache().unwrap().stats().hits, 1);
+
+    // Classes registered after watching can be used by reloads.
+    #[derive(Clone, PolarClass)]
+    struct Foo {
+        #[polar(attribute)]
+        x: i64,
+    }
+    let foo_class = Foo::get_polar_class_builder()
+        .set_constructor(|| Foo { x: 1 })
+        .build();
+    oso.register_class(foo_class).unwrap();
+
+    std::fs::write(&a, r#"allow(_, "read", 2); ?= new Foo().x = 1;"#).unwrap();
+    let event = events.recv_timeout(TIMEOUT).unwrap();
+    assert!(event.result.is_ok(), "{:?}", event.result);
+    assert!(!oso.is_allowed("alice", "read", 1).unwrap());
+    assert!(oso.is_allowed("alice", "read", 2).unwrap());
+}
@@ -4,6 +4,7 @@
 use polar_core::error::PolarError;
 pub use polar_core::polar::Polar;
 pub use polar_core::query::Query;
+pub use polar_core::query_options::{CancellationHandle, QueryOptions};
 use polar_core::{error, terms};
 
 use std::ffi::{c_void, CStr, CString};
@@ -110,6 +111,17 @@ pub extern "C" fn polar_load(
     })
 }
 
+#[no_mangle]
+pub extern "C" fn polar_reload(
+    polar_ptr: *mut Polar,
+    sources: *const c_char,
+) -> *mut CResult<c_void> {
+    ffi_try!({
+        let polar = unsafe { ffi_ref!(polar_ptr) };
+        from_json(sources).and_then(|sources| polar.reload(sources))
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn polar_clear_rules(polar_ptr: *mut Polar) -> *mut CResult<c_void> {
     ffi_try!({
@@ -145,6 +157,20 @@ pub extern "C" fn polar_register_mro(
         from_json(mro).and_then(|mro| polar.register_mro(terms::Symbol::new(name.as_ref()), mro))
     })
 }
+
+#[no_mangle]
+pub extern "C" fn polar_table_rule(
+    polar_ptr: *mut Polar,
+    name: *const c_char,
+) -> *mut CResult<c_void> {
+    ffi_try!({
+        let polar = unsafe { ffi_ref!(polar_ptr) };
+        let name = unsafe { ffi_string!(name) };
+        polar.table_rule(terms::Symbol::new(name.as_ref()));
+        Ok(())
+    })
+}
+
 // @Note(steve): trace is treated as a bool. 0 for false, anything else for true.
 // If we get more than one flag on these ffi methods, consider renaming it flags and making it a bitflags field.
 // Then we won't have to update the ffi to add new optional things like logging or tracing or whatever.
@@ -184,6 +210,25 @@ pub extern "C" fn polar_new_query(
     })
 }
 
+#[no_mangle]
+pub extern "C" fn polar_new_query_with_options(
+    polar_ptr: *mut Polar,
+    query_str: *const c_char,
+    trace: u32,
+    options: *const c_char,
+) -> *mut CResult<Query> {
+    ffi_try!({
+        let polar = unsafe { ffi_ref!(polar_ptr) };
+        let s = unsafe { ffi_string!(query_str) };
+        let trace = trace != 0;
+        from_json(options).and_then(|options: QueryOptions| {
+            polar
+                .new_query_with_options(&s, trace, &options)
+                .map(|q| box_ptr!(q))
+        })
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn polar_next_polar_message(polar_ptr: *mut Polar) -> *mut CResult<c_char> {
     ffi_try!({
@@ -304,6 +349,23 @@ pub extern "C" fn polar_query_source_info(query_ptr: *mut Query) -> *mut CResult
     })
 }
 
+/// Returns a handle that cancels `query` from any thread. Free it with
+/// `cancellation_handle_free`.
+#[no_mangle]
+pub extern "C" fn polar_query_cancellation_handle(
+    query_ptr: *mut Query,
+) -> *mut CancellationHandle {
+    let query = unsafe { ffi_ref!(query_ptr) };
+    box_ptr!(query.cancellation_handle())
+}
+
+#[no_mangle]
+pub extern "C" fn cancellation_handle_cancel(handle: *mut CancellationHandle) -> i32 {
+    let handle = unsafe { ffi_ref!(handle) };
+    handle.cancel();
+    POLAR_SUCCESS
+}
+
 #[no_mangle]
 pub extern "C" fn polar_bind(
     query_ptr: *mut Query,
@@ -351,6 +413,14 @@ pub extern "C" fn query_free(query: *mut Query) -> i32 {
     POLAR_SUCCESS
 }
 
+/// Recovers the original boxed version of `handle` so that
+/// it can be properly freed
+#[no_mangle]
+pub extern "C" fn cancellation_handle_free(handle: *mut CancellationHandle) -> i32 {
+    std::mem::drop(unsafe { Box::from_raw(handle) });
+    POLAR_SUCCESS
+}
+
 /// Recovers the original boxed version of `result` so that
 /// it can be properly freed
 #[no_mangle]
@@ -160,6 +160,98 @@ pub fn indexed_rules(c: &mut Criterion) {
     group.finish();
 }
 
+/// Bench: create `n` facts of the form `has_role("user_i", "member", "repo_i")`
+/// and measure the time to find the user with a role on `repo_{n / 2}`.
+/// This measures indexing on arguments other than the first.
+pub fn indexed_rules_by_later_argument(c: &mut Criterion) {
+    fn make_runner(n: usize) -> Runner {
+        let mut runner =
+            runner_from_query(&format!(r#"has_role(user, "member", "repo_{}")"#, n / 2));
+        let mut policy = String::new();
+        for i in 0..n {
+            write!(policy, r#"has_role("user_{0}", "member", "repo_{0}");"#, i).unwrap();
+        }
+        runner.load_str(&policy).unwrap();
+        runner.expected_result(maplit::hashmap!(
+            sym!("user") => term!(format!("user_{}", n / 2).as_str())
+        ));
+        runner
+    }
+
+    let n_array = [100, 1000, 10_000];
+
+    let mut group = c.benchmark_group("indexed_by_later_argument");
+    for n in &n_array {
+        group.bench_function(BenchmarkId::from_parameter(format!("{}", n)), |b| {
+            b.iter_batched_ref(
+                || make_runner(*n),
+                |runner| {
+                    runner.run();
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+/// Bench: register `n` classes `C_i` and create a rule `g(_: C_i)` for each,
+/// then measure the time to call `g` with an instance of `C_{n / 2}`.
+/// This measures indexing on specializer class tags.
+pub fn indexed_rules_by_class(c: &mut Criterion) {
+    fn instance(instance_id: u64, class_id: u64) -> Term {
+        Term::new_from_ffi(Value::ExternalInstance(ExternalInstance {
+            instance_id,
+            constructor: None,
+            repr: None,
+            class_repr: None,
+            class_id: Some(class_id),
+        }))
+    }
+
+    fn make_runner(n: u64) -> Runner {
+        let polar = Polar::new();
+        let mut policy = String::new();
+        for i in 1..=n {
+            let class = sym!(format!("C_{}", i).as_str());
+            polar
+                .register_constant(class.clone(), instance(i, i))
+                .unwrap();
+            polar.register_mro(class, vec![i]).unwrap();
+            write!(policy, "g(_: C_{});", i).unwrap();
+        }
+        polar.load_str(&policy).unwrap();
+
+        let query = Term::new_from_ffi(Value::Call(Call {
+            name: sym!("g"),
+            args: vec![instance(n + 1, n / 2)],
+            kwargs: None,
+        }));
+        let query = polar.new_query_from_term(query, false);
+        let mut runner = Runner::new(polar, query);
+        runner.expected_result(Bindings::new());
+        runner
+    }
+
+    let n_array = [100, 1000];
+
+    let mut group = c.benchmark_group("indexed_by_class");
+    for n in &n_array {
+        group.bench_function(BenchmarkId::from_parameter(format!("{}", n)), |b| {
+            b.iter_batched_ref(
+                || make_runner(*n),
+                |runner| {
+                    runner.run();
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
 /// Bench: create `TARGET` rules of the form `f(i) if f(i-1)`
 /// and measure the time to compute `f(TARGET)`
 /// This basically measures the performance of the rule sorting
@@ -202,6 +294,8 @@ criterion_group!(
     fib,
     prime,
     indexed_rules,
+    indexed_rules_by_later_argument,
+    indexed_rules_by_class,
     not,
     load_policy,
 );
@@ -0,0 +1,142 @@
+//! Built-in predicates that aggregate over the solutions of a goal.
+//!
+//! `count(x, goal, n)`, `collect(x, goal, list)`, `sum(x, goal, total)`, `min(x, goal, m)` and
+//! `max(x, goal, m)` find every solution of `goal` in a sub-VM, and unify their last argument
+//! with the number, list, sum, minimum or maximum of the values of `x` in those solutions.
+//! Bindings made while solving `goal` are not kept. A rule with the same name takes precedence
+//! over the built-in predicate, and may be called with a value instead of a goal.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::counter::Counter;
+use crate::error::{PolarError, PolarResult};
+use crate::events::QueryEvent;
+use crate::runnable::Runnable;
+use crate::terms::{Call, Operation, Operator, Symbol, Term, Value};
+use crate::vm::{Goal, PolarVirtualMachine};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Collect,
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    /// The aggregate that a call to `name` with `arity` positional arguments is a call to, if any.
+    pub(crate) fn from_name(name: &Symbol, arity: usize) -> Option<Self> {
+        if arity != 3 {
+            return None;
+        }
+        match name.0.as_str() {
+            "count" => Some(Self::Count),
+            "collect" => Some(Self::Collect),
+            "sum" => Some(Self::Sum),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+
+    /// The aggregate that `call` is a call to, if any.
+    pub(crate) fn from_call(call: &Call) -> Option<Self> {
+        if call.kwargs.is_some() {
+            return None;
+        }
+        Self::from_name(&call.name, call.args.len())
+    }
+}
+
+/// Whether `goal`, the second argument of a call to an aggregate, is a value, e.g., `1` or `x.y`,
+/// rather than a query. Only a rule with the aggregate's name can be called with a value.
+pub(crate) fn is_value(goal: &Term) -> bool {
+    use Operator::*;
+    match goal.value() {
+        Value::Expression(Operation { operator, .. }) => {
+            matches!(operator, New | Dot | Mul | Div | Mod | Rem | Add | Sub)
+        }
+        Value::Call(_) | Value::Variable(_) | Value::RestVariable(_) | Value::Boolean(_) => false,
+        _ => true,
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Count => "count",
+            Self::Collect => "collect",
+            Self::Sum => "sum",
+            Self::Min => "min",
+            Self::Max => "max",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A `Runnable` that collects the values of `template` in each solution of a goal.
+///
+/// The values are left in `values` for the parent VM to aggregate.
+#[derive(Clone)]
+pub struct Aggregator {
+    vm: PolarVirtualMachine,
+    template: Term,
+    /// The aggregate call, for errors.
+    term: Term,
+    values: Rc<RefCell<Vec<Term>>>,
+}
+
+impl Aggregator {
+    pub(crate) fn new(
+        vm: &PolarVirtualMachine,
+        template: Term,
+        goal: Term,
+        term: Term,
+        values: Rc<RefCell<Vec<Term>>>,
+    ) -> Self {
+        Self {
+            vm: vm.clone_with_goals(vec![Goal::Query { term: goal }]),
+            template,
+            term,
+            values,
+        }
+    }
+}
+
+impl Runnable for Aggregator {
+    fn run(&mut self, _: Option<&mut Counter>) -> PolarResult<QueryEvent> {
+        loop {
+            match self.vm.run(None)? {
+                QueryEvent::Result { .. } => {
+                    let value = self.vm.aggregate_value(&self.template, &self.term)?;
+                    self.values.borrow_mut().push(value);
+                }
+                QueryEvent::Done { .. } => return Ok(QueryEvent::Done { result: true }),
+                event => return Ok(event),
+            }
+        }
+    }
+
+    fn external_question_result(&mut self, call_id: u64, answer: bool) -> PolarResult<()> {
+        self.vm.external_question_result(call_id, answer)
+    }
+
+    fn external_call_result(&mut self, call_id: u64, term: Option<Term>) -> PolarResult<()> {
+        self.vm.external_call_result(call_id, term)
+    }
+
+    fn debug_command(&mut self, command: &str) -> PolarResult<()> {
+        self.vm.debug_command(command)
+    }
+
+    fn clone_runnable(&self) -> Box<dyn Runnable> {
+        Box::new(self.clone())
+    }
+
+    fn handle_error(&mut self, error: PolarError) -> PolarResult<QueryEvent> {
+        self.vm.handle_error(error)
+    }
+}
@@ -338,6 +338,14 @@ impl BindingManager {
         self.variable_state_at_point(variable, &self.bsp())
     }
 
+    /// Whether `variable` has constraints other than being unified with other variables.
+    pub fn is_constrained(&self, variable: &Symbol) -> bool {
+        matches!(
+            self._variable_state(variable),
+            BindingManagerVariableState::Partial(_)
+        )
+    }
+
     pub fn variable_state_at_point(&self, variable: &Symbol, bsp: &Bsp) -> VariableState {
         let index = bsp.bindings_index;
         let mut next = variable;
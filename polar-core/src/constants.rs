@@ -1,7 +1,7 @@
 use crate::terms::{Symbol, Term};
 use std::collections::HashMap;
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub(crate) struct Constants {
     // Symbol -> Term (populated by *all* constants)
     pub symbol_to_term: HashMap<Symbol, Term>,
@@ -13,6 +13,8 @@ use crate::{
 
 use serde::{Deserialize, Serialize};
 
+pub mod memory;
+
 type Id = u64;
 type VarId = Id;
 type TypeName = String;
@@ -913,6 +915,8 @@ impl Vars {
                     Value::Boolean(_) => "Bool",
                     Value::String(_) => "String",
                     Value::Number(_) => "Number",
+                    Value::Timestamp(_) => "Timestamp",
+                    Value::Duration(_) => "Duration",
                     Value::List(_) => "List",
                     Value::Dictionary(_) => "Dictionary",
                     Value::ExternalInstance(_) => "ExternalInstance",
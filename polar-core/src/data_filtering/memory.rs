@@ -0,0 +1,612 @@
+//! A reference evaluator for data filters over in-memory collections.
+//!
+//! Both [`Filter`] and the older [`FilterPlan`] can be evaluated against a map from type names to
+//! the records of that type. This is meant as a testing oracle for policies and host adapters,
+//! and as a backend for datasets small enough to scan.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::{
+    Constraint, ConstraintKind, ConstraintValue, FetchRequest, FilterPlan, Ref, ResultSet, Type,
+    TypeName, Types, VarId,
+};
+use crate::{
+    error::{df_field_missing, unsupported, PolarResult, RuntimeError},
+    filter::{Comparison, Condition, Datum, Filter, Projection, Relation},
+    regexes::Regexes,
+    terms::{Symbol, Term, Value},
+};
+
+/// A record that data filters can be evaluated against.
+pub trait Record {
+    /// The value of `field` on this record, if it has one.
+    fn field(&self, name: &str) -> Option<Value>;
+
+    /// The record itself, for comparisons that don't name a field (e.g. `x = resource`).
+    fn value(&self) -> Value;
+}
+
+/// Dictionaries are records with one field per key.
+impl Record for Value {
+    fn field(&self, name: &str) -> Option<Value> {
+        match self {
+            Value::Dictionary(dict) => dict
+                .fields
+                .get(&Symbol::new(name))
+                .map(|term| term.value().clone()),
+            _ => None,
+        }
+    }
+
+    fn value(&self) -> Value {
+        self.clone()
+    }
+}
+
+/// Records of each type, keyed by type name. A missing type has no records.
+pub type Data<R> = HashMap<TypeName, Vec<R>>;
+
+fn records<'a, R>(data: &'a Data<R>, typ: &str) -> &'a [R] {
+    data.get(typ).map_or(&[], Vec::as_slice)
+}
+
+fn field<R: Record>(record: &R, typ: &str, name: &str) -> PolarResult<Value> {
+    match record.field(name) {
+        Some(value) => Ok(value),
+        None => df_field_missing(typ, name),
+    }
+}
+
+fn contains(list: &Value, item: &Value) -> PolarResult<bool> {
+    match list {
+        Value::List(terms) => Ok(terms.iter().any(|term| term.value() == item)),
+        _ => unsupported(
+            format!("cannot check membership in {}", list),
+            Term::new_temporary(list.clone()),
+        ),
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> PolarResult<Ordering> {
+    let ord = match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Timestamp(l), Value::Timestamp(r)) => Some(l.cmp(r)),
+        (Value::Duration(l), Value::Duration(r)) => Some(l.cmp(r)),
+        _ => None,
+    };
+    match ord {
+        Some(ord) => Ok(ord),
+        None => unsupported(
+            format!("cannot compare {} and {}", left, right),
+            Term::new_temporary(left.clone()),
+        ),
+    }
+}
+
+fn matches_regex(string: &Value, pattern: &Value, regexes: &Regexes) -> PolarResult<bool> {
+    match (string, pattern) {
+        (Value::String(string), Value::String(pattern)) => match regexes.get(pattern) {
+            Ok(regex) => Ok(regex.is_match(string)),
+            Err(err) => Err(RuntimeError::InvalidRegex {
+                msg: err.to_string(),
+                term: Term::new_temporary(Value::String(pattern.clone())),
+            }
+            .into()),
+        },
+        _ => unsupported(
+            format!("cannot match {} against {}", string, pattern),
+            Term::new_temporary(string.clone()),
+        ),
+    }
+}
+
+/// A relation resolved to the fields it joins on.
+struct Join<'a> {
+    from: &'a str,
+    my_field: &'a str,
+    to: &'a str,
+    other_field: &'a str,
+}
+
+/// The record bound to each type while following relations. A type is bound to `None` if the
+/// record it relates to has no match, as in a `LEFT JOIN`.
+type Env<'a, R> = HashMap<&'a str, Option<&'a R>>;
+
+impl Filter {
+    /// Return the records of type `root` in `data` that pass the filter.
+    ///
+    /// `types` supplies the fields joined on by each relation.
+    pub fn evaluate<'a, R: Record>(
+        &'a self,
+        types: &'a Types,
+        data: &'a Data<R>,
+    ) -> PolarResult<Vec<&'a R>> {
+        let joins = self
+            .relations
+            .iter()
+            .map(
+                |Relation(from, name, to)| match types.get(from).and_then(|fs| fs.get(name)) {
+                    Some(Type::Relation {
+                        my_field,
+                        other_field,
+                        ..
+                    }) => Ok(Join {
+                        from,
+                        my_field,
+                        to,
+                        other_field,
+                    }),
+                    _ => df_field_missing(from, name),
+                },
+            )
+            .collect::<PolarResult<Vec<_>>>()?;
+
+        // Compile the literal patterns once rather than for each record.
+        let mut regexes = Regexes::default();
+        for Condition(_, op, right) in self.conditions.iter().flatten() {
+            if let (
+                Comparison::MatchesRegex | Comparison::NotMatchesRegex,
+                Datum::Immediate(Value::String(pattern)),
+            ) = (op, right)
+            {
+                regexes.add(pattern);
+            }
+        }
+
+        let mut passed = vec![];
+        for record in records(data, &self.root) {
+            let mut env = Env::new();
+            env.insert(self.root.as_str(), Some(record));
+            if self.matches(&joins, data, &regexes, &mut env)? {
+                passed.push(record);
+            }
+        }
+        Ok(passed)
+    }
+
+    /// Bind the remaining `joins` in every possible way and check the conditions.
+    fn matches<'a, R: Record>(
+        &self,
+        joins: &[Join<'a>],
+        data: &'a Data<R>,
+        regexes: &Regexes,
+        env: &mut Env<'a, R>,
+    ) -> PolarResult<bool> {
+        let (join, rest) = match joins.split_first() {
+            None => return self.holds(regexes, env),
+            Some(split) => split,
+        };
+
+        let key = match env.get(join.from).copied().flatten() {
+            Some(record) => Some(field(record, join.from, join.my_field)?),
+            None => None,
+        };
+        let mut related = false;
+        if let Some(key) = key {
+            for other in records(data, join.to) {
+                if field(other, join.to, join.other_field)? == key {
+                    related = true;
+                    env.insert(join.to, Some(other));
+                    if self.matches(rest, data, regexes, env)? {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        if related {
+            Ok(false)
+        } else {
+            env.insert(join.to, None);
+            self.matches(rest, data, regexes, env)
+        }
+    }
+
+    fn holds<R: Record>(&self, regexes: &Regexes, env: &Env<R>) -> PolarResult<bool> {
+        for conjuncts in &self.conditions {
+            let mut all = true;
+            for condition in conjuncts {
+                if !condition.holds(regexes, env)? {
+                    all = false;
+                    break;
+                }
+            }
+            if all {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl Condition {
+    /// Conditions on a type that isn't bound (see [`Env`]) never hold. `regexes` has the
+    /// compiled patterns of the filter's conditions.
+    fn holds<R: Record>(&self, regexes: &Regexes, env: &Env<R>) -> PolarResult<bool> {
+        use Comparison::*;
+        let Condition(left, op, right) = self;
+        let (left, right) = match (left.resolve(env)?, right.resolve(env)?) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return Ok(false),
+        };
+        Ok(match op {
+            Eq => left == right,
+            Neq => left != right,
+            In => contains(&right, &left)?,
+            Nin => !contains(&right, &left)?,
+            Lt => compare(&left, &right)? == Ordering::Less,
+            Leq => compare(&left, &right)? != Ordering::Greater,
+            Gt => compare(&left, &right)? == Ordering::Greater,
+            Geq => compare(&left, &right)? != Ordering::Less,
+            MatchesRegex => matches_regex(&left, &right, regexes)?,
+            NotMatchesRegex => !matches_regex(&left, &right, regexes)?,
+        })
+    }
+}
+
+impl Datum {
+    fn resolve<R: Record>(&self, env: &Env<R>) -> PolarResult<Option<Value>> {
+        match self {
+            Datum::Immediate(value) => Ok(Some(value.clone())),
+            Datum::Field(Projection(typ, name)) => match env.get(typ.as_str()).copied().flatten() {
+                None => Ok(None),
+                Some(record) => match name {
+                    None => Ok(Some(record.value())),
+                    Some(name) => field(record, typ, name).map(Some),
+                },
+            },
+        }
+    }
+}
+
+impl FilterPlan {
+    /// Return the records in `data` matched by any result set in the plan, without duplicates.
+    pub fn evaluate<'a, R: Record>(&self, data: &'a Data<R>) -> PolarResult<Vec<&'a R>> {
+        let mut passed: Vec<&R> = vec![];
+        for result_set in &self.result_sets {
+            for record in result_set.evaluate(data)? {
+                if !passed.iter().any(|r| std::ptr::eq(*r, record)) {
+                    passed.push(record);
+                }
+            }
+        }
+        Ok(passed)
+    }
+}
+
+/// The records fetched for each request of a result set so far, with their type.
+type Fetched<'a, 'b, R> = HashMap<VarId, (&'b str, Vec<&'a R>)>;
+
+impl ResultSet {
+    /// Resolve each fetch request in `resolve_order`, and return the records fetched for
+    /// `result_id`.
+    fn evaluate<'a, R: Record>(&self, data: &'a Data<R>) -> PolarResult<Vec<&'a R>> {
+        let mut fetched: Fetched<R> = HashMap::new();
+        for id in &self.resolve_order {
+            if let Some(request) = self.requests.get(id) {
+                let records = request.evaluate(data, &fetched)?;
+                fetched.insert(*id, (&request.class_tag, records));
+            }
+        }
+        Ok(fetched
+            .remove(&self.result_id)
+            .map(|(_, records)| records)
+            .unwrap_or_default())
+    }
+}
+
+impl FetchRequest {
+    fn evaluate<'a, R: Record>(
+        &self,
+        data: &'a Data<R>,
+        fetched: &Fetched<R>,
+    ) -> PolarResult<Vec<&'a R>> {
+        let mut records = vec![];
+        'records: for record in self::records(data, &self.class_tag) {
+            for constraint in &self.constraints {
+                if !constraint.check(record, &self.class_tag, fetched)? {
+                    continue 'records;
+                }
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+impl Constraint {
+    fn check<R: Record>(&self, record: &R, typ: &str, fetched: &Fetched<R>) -> PolarResult<bool> {
+        use ConstraintKind::*;
+        let value = match &self.field {
+            None => record.value(),
+            Some(name) => field(record, typ, name)?,
+        };
+
+        let other = match &self.value {
+            ConstraintValue::Term(term) => term.value().clone(),
+            ConstraintValue::Field(name) => field(record, typ, name)?,
+            // A reference stands for the list of values of an earlier result, so equality
+            // with it means membership.
+            ConstraintValue::Ref(Ref {
+                field: ref_field,
+                result_id,
+            }) => {
+                let (ref_typ, records) = match fetched.get(result_id) {
+                    Some(fetched) => fetched,
+                    None => return Ok(matches!(self.kind, Neq | Nin)),
+                };
+                let values = records
+                    .iter()
+                    .map(|other| match ref_field {
+                        None => Ok(other.value()),
+                        Some(name) => field(*other, ref_typ, name),
+                    })
+                    .collect::<PolarResult<Vec<_>>>()?;
+                return match self.kind {
+                    Eq | In => Ok(values.contains(&value)),
+                    Neq | Nin => Ok(!values.contains(&value)),
+                    Contains => {
+                        for other in &values {
+                            if contains(&value, other)? {
+                                return Ok(true);
+                            }
+                        }
+                        Ok(false)
+                    }
+                };
+            }
+        };
+        Ok(match self.kind {
+            Eq => value == other,
+            Neq => value != other,
+            In => contains(&other, &value)?,
+            Nin => !contains(&other, &value)?,
+            Contains => contains(&value, &other)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        data_filtering::build_filter_plan,
+        error::{ErrorKind, PolarError, RuntimeError},
+        events::ResultEvent,
+        filter::singleton,
+        terms::*,
+    };
+
+    fn s(x: &str) -> String {
+        x.to_string()
+    }
+
+    fn record(fields: Vec<(&str, Value)>) -> Value {
+        Value::Dictionary(Dictionary {
+            fields: fields
+                .into_iter()
+                .map(|(k, v)| (sym!(k), Term::new_temporary(v)))
+                .collect(),
+        })
+    }
+
+    fn types() -> Types {
+        hashmap! {
+            s("Repo") => hashmap! {
+                s("id") => Type::Base { class_tag: s("Integer") },
+                s("org") => Type::Relation {
+                    kind: s("one"),
+                    other_class_tag: s("Org"),
+                    my_field: s("org_id"),
+                    other_field: s("id"),
+                },
+            },
+            s("Org") => hashmap! {
+                s("name") => Type::Base { class_tag: s("String") },
+                s("repos") => Type::Relation {
+                    kind: s("many"),
+                    other_class_tag: s("Repo"),
+                    my_field: s("id"),
+                    other_field: s("org_id"),
+                },
+            },
+        }
+    }
+
+    fn data() -> Data<Value> {
+        let org = |id: i64, name: &str| record(vec![("id", value!(id)), ("name", value!(name))]);
+        let repo = |id: i64, org_id: i64, stars: i64| {
+            record(vec![
+                ("id", value!(id)),
+                ("org_id", value!(org_id)),
+                ("stars", value!(stars)),
+                ("tags", value!(["rust", "polar"])),
+            ])
+        };
+        hashmap! {
+            s("Org") => vec![org(1, "apple"), org(2, "osohq")],
+            s("Repo") => vec![repo(1, 1, 10), repo(2, 2, 20), repo(3, 2, 30), repo(4, 3, 40)],
+        }
+    }
+
+    fn ids(records: Vec<&Value>) -> Vec<i64> {
+        let mut ids = records
+            .into_iter()
+            .map(|r| match r.field("id") {
+                Some(Value::Number(crate::numerics::Numeric::Integer(i))) => i,
+                _ => panic!("record without an id: {}", r),
+            })
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn field(typ: &str, name: &str) -> Datum {
+        Datum::Field(Projection(s(typ), Some(s(name))))
+    }
+
+    #[test]
+    fn test_evaluate_filter_conditions() -> PolarResult<()> {
+        let (types, data) = (types(), data());
+        let filter = |conditions| Filter {
+            root: s("Repo"),
+            relations: vec![],
+            conditions,
+        };
+
+        let f = filter(vec![hashset! {
+            Condition(field("Repo", "stars"), Comparison::Geq, Datum::Immediate(value!(20))),
+            Condition(field("Repo", "id"), Comparison::Neq, Datum::Immediate(value!(3))),
+        }]);
+        assert_eq!(ids(f.evaluate(&types, &data)?), vec![2, 4]);
+
+        let f = filter(vec![
+            singleton(Condition(
+                field("Repo", "id"),
+                Comparison::In,
+                Datum::Immediate(value!([1, 3])),
+            )),
+            singleton(Condition(
+                Datum::Immediate(value!("rust")),
+                Comparison::Nin,
+                field("Repo", "tags"),
+            )),
+        ]);
+        assert_eq!(ids(f.evaluate(&types, &data)?), vec![1, 3]);
+
+        let f = filter(vec![singleton(Condition(
+            Datum::Immediate(value!(true)),
+            Comparison::Eq,
+            Datum::Immediate(value!(false)),
+        ))]);
+        assert!(f.evaluate(&types, &data)?.is_empty());
+
+        let f = filter(vec![singleton(Condition(
+            field("Repo", "tags"),
+            Comparison::Lt,
+            Datum::Immediate(value!(1)),
+        ))]);
+        assert!(matches!(
+            f.evaluate(&types, &data),
+            Err(PolarError(ErrorKind::Runtime(
+                RuntimeError::Unsupported { .. }
+            )))
+        ));
+
+        let regex = |op, pattern: &str| Filter {
+            root: s("Org"),
+            relations: vec![],
+            conditions: vec![singleton(Condition(
+                field("Org", "name"),
+                op,
+                Datum::Immediate(value!(pattern)),
+            ))],
+        };
+        let f = regex(Comparison::MatchesRegex, "^o");
+        assert_eq!(ids(f.evaluate(&types, &data)?), vec![2]);
+        let f = regex(Comparison::NotMatchesRegex, "^o");
+        assert_eq!(ids(f.evaluate(&types, &data)?), vec![1]);
+        let f = regex(Comparison::MatchesRegex, "(");
+        assert!(matches!(
+            f.evaluate(&types, &data),
+            Err(PolarError(ErrorKind::Runtime(
+                RuntimeError::InvalidRegex { .. }
+            )))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate_filter_relations() -> PolarResult<()> {
+        let (types, data) = (types(), data());
+
+        let f = Filter {
+            root: s("Repo"),
+            relations: vec![Relation(s("Repo"), s("org"), s("Org"))],
+            conditions: vec![
+                singleton(Condition(
+                    field("Org", "name"),
+                    Comparison::Eq,
+                    Datum::Immediate(value!("osohq")),
+                )),
+                singleton(Condition(
+                    field("Repo", "stars"),
+                    Comparison::Gt,
+                    Datum::Immediate(value!(35)),
+                )),
+            ],
+        };
+        // Repo 4 has no org, but still passes the second disjunct.
+        assert_eq!(ids(f.evaluate(&types, &data)?), vec![2, 3, 4]);
+
+        let f = Filter {
+            root: s("Org"),
+            relations: vec![Relation(s("Org"), s("repos"), s("Repo"))],
+            conditions: vec![singleton(Condition(
+                field("Repo", "stars"),
+                Comparison::Eq,
+                Datum::Immediate(value!(30)),
+            ))],
+        };
+        assert_eq!(ids(f.evaluate(&types, &data)?), vec![2]);
+
+        let f = Filter {
+            root: s("Repo"),
+            relations: vec![Relation(s("Repo"), s("owner"), s("User"))],
+            conditions: vec![],
+        };
+        assert!(matches!(
+            f.evaluate(&types, &data),
+            Err(PolarError(ErrorKind::Runtime(
+                RuntimeError::DataFilteringFieldMissing { .. }
+            )))
+        ));
+        Ok(())
+    }
+
+    /// Both kinds of filter built from the same partial results select the same records.
+    #[test]
+    fn test_evaluate_partial_results() -> PolarResult<()> {
+        let (types, data) = (types(), data());
+        let partial = |expr| {
+            vec![ResultEvent::new(hashmap! {
+                sym!("resource") => term!(op!(And,
+                    term!(op!(Isa, var!("_this"), term!(pattern!(instance!("Repo"))))),
+                    expr
+                ))
+            })]
+        };
+        let cases = vec![
+            (
+                term!(op!(
+                    Unify,
+                    term!(op!(
+                        Dot,
+                        term!(op!(Dot, var!("_this"), str!("org"))),
+                        str!("name")
+                    )),
+                    str!("osohq")
+                )),
+                vec![2, 3],
+            ),
+            (
+                term!(op!(
+                    Unify,
+                    term!(op!(Dot, var!("_this"), str!("id"))),
+                    term!(4)
+                )),
+                vec![4],
+            ),
+        ];
+
+        for (expr, expected) in cases {
+            let results = partial(expr);
+            let filter = Filter::build(types.clone(), results.clone(), "resource", "Repo")?;
+            assert_eq!(ids(filter.evaluate(&types, &data)?), expected);
+
+            let plan = build_filter_plan(types.clone(), results, "resource", "Repo")?;
+            assert_eq!(ids(plan.evaluate(&data)?), expected);
+        }
+        Ok(())
+    }
+}
@@ -79,6 +79,8 @@ impl PolarError {
                 | ExtraToken { token, loc }
                 | IntegerOverflow { token, loc }
                 | InvalidFloat { token, loc }
+                | InvalidTimestamp { token, loc }
+                | DurationOverflow { token, loc }
                 | ReservedWord { token, loc }
                 | UnrecognizedToken { token, loc } => {
                     Some(Context::new(e.source.clone(), *loc, loc + token.len()))
@@ -102,14 +104,18 @@ impl PolarError {
                 ArithmeticError { term }
                 | TypeError { term, .. }
                 | UnhandledPartial { term, .. }
-                | Unsupported { term, .. } => term.parsed_context().cloned(),
+                | Unsupported { term, .. }
+                | InvalidRegex { term, .. } => term.parsed_context().cloned(),
 
                 // These errors never have context.
                 StackOverflow { .. }
                 | QueryTimeout { .. }
+                | GoalLimitExceeded { .. }
+                | QueryCancelled
                 | IncompatibleBindings { .. }
                 | DataFilteringFieldMissing { .. }
                 | DataFilteringUnsupportedOp { .. }
+                | DataFilteringSql { .. }
                 | InvalidRegistration { .. }
                 | QueryForUndefinedRule { .. }
                 | MultipleLoadError => None,
@@ -120,6 +126,7 @@ impl PolarError {
                 ResourceBlock { term, .. }
                 | SingletonVariable { term, .. }
                 | UndefinedRuleCall { term }
+                | InvalidRegexPattern { term, .. }
                 | DuplicateResourceBlockDeclaration {
                     declaration: term, ..
                 }
@@ -263,6 +270,14 @@ pub enum ParseErrorKind {
         token: String,
         loc: usize,
     },
+    InvalidTimestamp {
+        token: String,
+        loc: usize,
+    },
+    DurationOverflow {
+        token: String,
+        loc: usize,
+    },
     WrongValueType {
         loc: usize,
         term: Term,
@@ -311,6 +326,14 @@ impl fmt::Display for ParseErrorKind {
                 "{} was parsed as a float, but is invalid",
                 token.escape_debug()
             ),
+            Self::InvalidTimestamp { token, .. } => write!(
+                f,
+                "{} is not a valid RFC 3339 timestamp",
+                token.escape_debug()
+            ),
+            Self::DurationOverflow { token, .. } => {
+                write!(f, "'{}' caused a duration overflow", token.escape_debug())
+            }
             Self::WrongValueType { term, expected, .. } => {
                 write!(f, "Wrong value type: {}. Expected a {}", term, expected)
             }
@@ -344,6 +367,20 @@ pub enum RuntimeError {
     QueryTimeout {
         elapsed: u64,
         timeout: u64,
+        /// Whether the timeout was set with `QueryOptions::timeout_ms`, rather than with the
+        /// `POLAR_TIMEOUT_MS` environment variable.
+        #[serde(skip)]
+        from_options: bool,
+    },
+    GoalLimitExceeded {
+        limit: u64,
+    },
+    QueryCancelled,
+    /// A pattern matched with `matches_regex` isn't a valid regular expression.
+    InvalidRegex {
+        msg: String,
+        /// Term where the error arose, tracked for lexical context.
+        term: Term,
     },
     Application {
         msg: String,
@@ -366,6 +403,10 @@ pub enum RuntimeError {
     DataFilteringUnsupportedOp {
         operation: Operation,
     },
+    /// A data filtering query could not be translated to SQL.
+    DataFilteringSql {
+        msg: String,
+    },
     // TODO(gj): consider moving to ValidationError.
     InvalidRegistration {
         sym: Symbol,
@@ -399,7 +440,23 @@ impl fmt::Display for RuntimeError {
             Self::StackOverflow { msg } => {
                 write!(f, "{}", msg)
             }
-            Self::QueryTimeout { elapsed, timeout } => write!(f, "Query timeout: Query running for {}ms, which exceeds the timeout of {}ms. To disable timeouts, set the POLAR_TIMEOUT_MS environment variable to 0.", elapsed, timeout),
+            Self::QueryTimeout {
+                elapsed,
+                timeout,
+                from_options,
+            } => {
+                write!(f, "Query timeout: Query running for {}ms, which exceeds the timeout of {}ms. ", elapsed, timeout)?;
+                if *from_options {
+                    write!(f, "The timeout was set with `QueryOptions::timeout_ms`; set it to 0 to disable timeouts.")
+                } else {
+                    write!(f, "To disable timeouts, set the POLAR_TIMEOUT_MS environment variable to 0.")
+                }
+            }
+            Self::GoalLimitExceeded { limit } => {
+                write!(f, "Query exceeded the limit of {} goals.", limit)
+            }
+            Self::QueryCancelled => write!(f, "Query cancelled."),
+            Self::InvalidRegex { msg, .. } => write!(f, "Invalid regex: {}", msg),
             Self::Application {
                 msg, stack_trace, ..
             } => {
@@ -466,6 +523,9 @@ The expression is: {expr}
                 );
                 write!(f, "{}", msg)
             }
+            Self::DataFilteringSql { msg } => {
+                write!(f, "Cannot generate SQL for data filtering query: {}", msg)
+            }
             Self::InvalidRegistration { sym, msg } => {
                 write!(f, "Invalid attempt to register '{}': {}", sym, msg)
             }
@@ -543,6 +603,13 @@ pub enum ValidationError {
         /// Term<Call> where the error arose, tracked for lexical context.
         term: Term,
     },
+    /// The policy matches against a string literal that isn't a valid regular expression. This
+    /// is the validation analogue of `RuntimeError::InvalidRegex`.
+    InvalidRegexPattern {
+        /// Term<String> where the error arose, tracked for lexical context.
+        term: Term,
+        msg: String,
+    },
     ResourceBlock {
         /// Term where the error arose, tracked for lexical context.
         term: Term,
@@ -589,6 +656,7 @@ impl fmt::Display for ValidationError {
             Self::UndefinedRuleCall { term } => {
                 write!(f, "Call to undefined rule: {}", term)
             }
+            Self::InvalidRegexPattern { msg, .. } => write!(f, "Invalid regex: {}", msg),
             Self::MissingRequiredRule { rule_type } => {
                 write!(f, "Missing implementation for required rule {}", rule_type)
             }
@@ -643,6 +711,14 @@ pub(crate) fn df_unsupported_op<T>(operation: Operation) -> PolarResult<T> {
     Err(RuntimeError::DataFilteringUnsupportedOp { operation }.into())
 }
 
+pub(crate) fn df_sql<T, U>(msg: T) -> PolarResult<U>
+where
+    T: AsRef<str>,
+{
+    let msg = msg.as_ref().into();
+    Err(RuntimeError::DataFilteringSql { msg }.into())
+}
+
 pub(crate) fn df_field_missing<T, U, V>(var_type: T, field: U) -> PolarResult<V>
 where
     T: AsRef<str>,
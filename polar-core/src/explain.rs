@@ -0,0 +1,366 @@
+//! Explanations of query results: why a query succeeded, or why it failed.
+//!
+//! A query created with [`Polar::new_explained_query`](crate::polar::Polar::new_explained_query)
+//! records how far the VM got through each rule it tried. Once the query has produced a result
+//! or finished, [`Query::explanation`](crate::query::Query::explanation) returns either a proof
+//! tree for the first result, or, for a query that failed, the candidate rules that were tried
+//! and the first condition that failed in each.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::loc_to_pos;
+use crate::rules::*;
+use crate::sources::Context;
+use crate::terms::*;
+use crate::traces::*;
+use crate::vm::PolarVirtualMachine;
+
+/// A position in a Polar source.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub filename: Option<String>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number.
+    pub column: usize,
+}
+
+impl Location {
+    fn from_context(context: Option<&Context>) -> Option<Self> {
+        context.map(|context| {
+            let (row, column) = loc_to_pos(&context.source.src, context.left);
+            Self {
+                filename: context.source.filename.clone(),
+                line: row + 1,
+                column: column + 1,
+            }
+        })
+    }
+}
+
+/// Why a query succeeded or failed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Explanation {
+    /// The query succeeded; `proof` is the tree of rules and conditions behind its first result.
+    Success { query: String, proof: Proof },
+    /// The query failed after trying each of the `candidates` rules.
+    Failure {
+        query: String,
+        candidates: Vec<Candidate>,
+    },
+}
+
+impl Explanation {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success { .. })
+    }
+}
+
+/// A step in a proof and the steps that established it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Proof {
+    pub step: Step,
+    pub children: Vec<Proof>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Step {
+    /// A rule, identified by its head, was applied.
+    Rule {
+        rule: String,
+        location: Option<Location>,
+    },
+    /// A condition was queried.
+    Query {
+        term: String,
+        location: Option<Location>,
+    },
+}
+
+/// A rule that was a candidate for a call, and how far its evaluation got.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    /// The head of the rule.
+    pub rule: String,
+    pub location: Option<Location>,
+    pub outcome: Outcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Outcome {
+    /// The rule was never tried, e.g. because an earlier rule produced the result.
+    NotEvaluated,
+    /// The rule succeeded at least once.
+    Succeeded,
+    /// The argument at `index` did not match the rule's parameter.
+    ParameterMismatch { index: usize, parameter: String },
+    /// The body condition at `index` failed. If the condition is a call, `candidates` are the
+    /// rules it tried.
+    ConditionFailed {
+        index: usize,
+        condition: String,
+        location: Option<Location>,
+        candidates: Vec<Candidate>,
+    },
+}
+
+/// The head of `rule` as written.
+fn rule_head(rule: &Rule) -> String {
+    match rule.parsed_context() {
+        Some(context) => {
+            let chars = context.source.src.chars();
+            chars.take(context.right).skip(context.left).collect()
+        }
+        None => rule.head_as_string(),
+    }
+}
+
+/// `param` as written, e.g. `repo: Repo` rather than its desugared form.
+fn parameter_source(param: &Parameter) -> String {
+    let end = param.specializer.as_ref().unwrap_or(&param.parameter);
+    match (param.parameter.parsed_context(), end.parsed_context()) {
+        (Some(start), Some(end)) if Arc::ptr_eq(&start.source, &end.source) => {
+            let chars = start.source.src.chars();
+            chars.take(end.right).skip(start.left).collect()
+        }
+        _ => param.to_string(),
+    }
+}
+
+/// How far evaluation of a rule got. Later progress compares greater.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Progress {
+    Parameter(usize),
+    Condition(usize),
+    Succeeded,
+}
+
+/// Identifies a rule by the address of its shared definition.
+type RuleKey = usize;
+
+fn rule_key(rule: &Arc<Rule>) -> RuleKey {
+    Arc::as_ptr(rule) as RuleKey
+}
+
+/// The rules and body conditions leading to a call, from the root of the query.
+type Site = Vec<(RuleKey, usize)>;
+
+/// Recover the call site of the current goal from the trace tree.
+///
+/// Each level of the trace stack ends with the node currently being evaluated at that depth: a
+/// rule is followed by its body (a conjunction), which is followed by the level holding the
+/// body's conditions.
+fn call_site<'a, I>(levels: I) -> Site
+where
+    I: IntoIterator<Item = &'a [Rc<Trace>]>,
+{
+    enum State<'a> {
+        Start,
+        Rule(&'a Arc<Rule>),
+        Body(&'a Arc<Rule>, &'a TermList),
+    }
+
+    let mut site = vec![];
+    let mut state = State::Start;
+    for level in levels {
+        state = match (state, level.last().map(|trace| &trace.node)) {
+            (State::Body(rule, conditions), _) => {
+                // Disjuncts are traced as siblings of their disjunction, so find the last
+                // node that is one of the body's conditions.
+                let index = level.iter().rev().find_map(|trace| match &trace.node {
+                    Node::Term(term) => conditions.iter().position(|c| c == term),
+                    Node::Rule(_) => None,
+                });
+                if let Some(index) = index {
+                    site.push((rule_key(rule), index));
+                }
+                State::Start
+            }
+            (_, Some(Node::Rule(rule))) => State::Rule(rule),
+            (State::Rule(rule), Some(Node::Term(term))) => match term.value() {
+                Value::Expression(Operation {
+                    operator: Operator::And,
+                    args,
+                }) => State::Body(rule, args),
+                _ => State::Start,
+            },
+            _ => State::Start,
+        }
+    }
+    site
+}
+
+/// Records the progress of an explained query through the rules it tries.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Explainer {
+    progress: HashMap<(Site, RuleKey), Progress>,
+    proof: Option<Rc<Trace>>,
+}
+
+impl Explainer {
+    fn record(&mut self, site: Site, rule: RuleKey, progress: Progress) {
+        let recorded = self.progress.entry((site, rule)).or_insert(progress);
+        *recorded = progress.max(*recorded);
+    }
+
+    /// A term was just queried; if it is a body condition, record that its rule got this far.
+    pub(crate) fn query<'a, I>(&mut self, levels: I)
+    where
+        I: IntoIterator<Item = &'a [Rc<Trace>]>,
+    {
+        let mut site = call_site(levels);
+        if let Some((rule, index)) = site.pop() {
+            self.record(site, rule, Progress::Condition(index));
+        }
+    }
+
+    /// The argument at `index` is about to be matched against `rule`'s parameter.
+    pub(crate) fn parameter<'a, I>(&mut self, levels: I, rule: &Arc<Rule>, index: usize)
+    where
+        I: IntoIterator<Item = &'a [Rc<Trace>]>,
+    {
+        self.record(
+            call_site(levels),
+            rule_key(rule),
+            Progress::Parameter(index),
+        );
+    }
+
+    /// The body of `rule` succeeded.
+    pub(crate) fn rule_succeeded<'a, I>(&mut self, levels: I, rule: &Arc<Rule>)
+    where
+        I: IntoIterator<Item = &'a [Rc<Trace>]>,
+    {
+        self.record(call_site(levels), rule_key(rule), Progress::Succeeded);
+    }
+
+    /// The query produced a result; keep the proof of the first one.
+    pub(crate) fn result(&mut self, trace: Option<&Rc<Trace>>) {
+        if self.proof.is_none() {
+            self.proof = trace.cloned();
+        }
+    }
+
+    pub(crate) fn explanation(&self, vm: &PolarVirtualMachine, query: &Term) -> Explanation {
+        let query_string = vm.term_source(query, false);
+        match &self.proof {
+            Some(trace) => {
+                let mut proofs = Self::proofs(vm, trace);
+                let proof = if proofs.len() == 1 {
+                    proofs.pop().unwrap()
+                } else {
+                    Proof {
+                        step: Step::Query {
+                            term: query_string.clone(),
+                            location: None,
+                        },
+                        children: proofs,
+                    }
+                };
+                Explanation::Success {
+                    query: query_string,
+                    proof,
+                }
+            }
+            None => {
+                let candidates = match query.value() {
+                    Value::Call(call) => self.candidates(vm, &vec![], call),
+                    _ => vec![],
+                };
+                Explanation::Failure {
+                    query: query_string,
+                    candidates,
+                }
+            }
+        }
+    }
+
+    /// Convert a trace to proofs, flattening conjunctions into their conjuncts.
+    fn proofs(vm: &PolarVirtualMachine, trace: &Trace) -> Vec<Proof> {
+        let children = trace
+            .children
+            .iter()
+            .flat_map(|child| Self::proofs(vm, child))
+            .collect();
+        let step = match &trace.node {
+            Node::Term(term)
+                if matches!(
+                    term.value(),
+                    Value::Expression(Operation {
+                        operator: Operator::And,
+                        ..
+                    })
+                ) =>
+            {
+                return children
+            }
+            Node::Term(term) => Step::Query {
+                term: vm.term_source(term, false),
+                location: Location::from_context(term.parsed_context()),
+            },
+            Node::Rule(rule) => Step::Rule {
+                rule: rule_head(rule),
+                location: Location::from_context(rule.parsed_context()),
+            },
+        };
+        vec![Proof { step, children }]
+    }
+
+    /// The rules that were candidates for `call` at `site`, in source order.
+    fn candidates(&self, vm: &PolarVirtualMachine, site: &Site, call: &Call) -> Vec<Candidate> {
+        let rules = match vm.kb.read().unwrap().get_generic_rule(&call.name) {
+            Some(generic_rule) => generic_rule.ordered_rules(),
+            None => return vec![],
+        };
+        rules
+            .into_iter()
+            .filter(|rule| rule.params.len() == call.args.len())
+            .map(|rule| {
+                let progress = self.progress.get(&(site.clone(), rule_key(&rule)));
+                let outcome = match progress {
+                    None => Outcome::NotEvaluated,
+                    Some(Progress::Succeeded) => Outcome::Succeeded,
+                    Some(Progress::Parameter(index)) => Outcome::ParameterMismatch {
+                        index: *index,
+                        parameter: parameter_source(&rule.params[*index]),
+                    },
+                    Some(Progress::Condition(index)) => {
+                        let condition = match rule.body.value() {
+                            Value::Expression(Operation {
+                                operator: Operator::And,
+                                args,
+                            }) => &args[*index],
+                            _ => &rule.body,
+                        };
+                        let candidates = match condition.value() {
+                            Value::Call(call) => {
+                                let mut site = site.clone();
+                                site.push((rule_key(&rule), *index));
+                                self.candidates(vm, &site, call)
+                            }
+                            _ => vec![],
+                        };
+                        Outcome::ConditionFailed {
+                            index: *index,
+                            condition: vm.term_source(condition, false),
+                            location: Location::from_context(condition.parsed_context()),
+                            candidates,
+                        }
+                    }
+                };
+                Candidate {
+                    rule: rule_head(&rule),
+                    location: Location::from_context(rule.parsed_context()),
+                    outcome,
+                }
+            })
+            .collect()
+    }
+}
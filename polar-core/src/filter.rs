@@ -13,6 +13,8 @@ use crate::{
 
 use serde::Serialize;
 
+pub mod sql;
+
 type TypeName = String;
 type FieldName = String;
 type VarName = String;
@@ -33,9 +35,12 @@ type Set<A> = HashSet<A>;
 /// the record passes through the filter.
 #[derive(Clone, Eq, Debug, Serialize, PartialEq)]
 pub struct Filter {
-    root: TypeName,                  // the host already has this, so we could leave it off
-    relations: Vec<Relation>,        // this & root determine the "joins" (or whatever)
-    conditions: Vec<Set<Condition>>, // disjunctive normal form
+    // the host already has this, so we could leave it off
+    pub root: TypeName,
+    // this & root determine the "joins" (or whatever)
+    pub relations: Vec<Relation>,
+    // disjunctive normal form
+    pub conditions: Vec<Set<Condition>>,
 }
 
 /// A named logical extension of a data set. Corresponds to a "join" in relational
@@ -47,11 +52,11 @@ pub struct Filter {
 /// from the `Foo` type to the `Bar` type, accessed using the `bar` field
 /// on `Foo`.
 #[derive(PartialEq, Eq, Debug, Serialize, Clone, Hash)]
-pub struct Relation(TypeName, FieldName, TypeName);
+pub struct Relation(pub TypeName, pub FieldName, pub TypeName);
 
 /// A constraint that must hold for a record in the data source.
 #[derive(PartialEq, Eq, Debug, Serialize, Clone, Hash)]
-pub struct Condition(Datum, Comparison, Datum);
+pub struct Condition(pub Datum, pub Comparison, pub Datum);
 
 /// The left or right side of a Condition.
 #[derive(PartialEq, Eq, Debug, Serialize, Clone, Hash)]
@@ -71,11 +76,14 @@ pub enum Comparison {
     Leq,
     Gt,
     Geq,
+    /// The left side is a string matched by the regex on the right.
+    MatchesRegex,
+    NotMatchesRegex,
 }
 
 /// An abstract "field reference" on a record from a named data source.
 #[derive(PartialEq, Eq, Debug, Serialize, Clone, Hash)]
-pub struct Projection(TypeName, Option<FieldName>);
+pub struct Projection(pub TypeName, pub Option<FieldName>);
 
 type TypeInfo = Map<TypeName, Map<FieldName, Type>>;
 type VarTypes = Map<PathVar, TypeName>;
@@ -325,12 +333,19 @@ impl FilterInfo {
     /// Handle a unary operation from the simplifier
     fn add_constraint_1(&mut self, op: Operation) -> PolarResult<()> {
         use Operator::*;
-        // The only case this currently handles is `not in`.
+        // The only cases this currently handles are `not in` and `not matches_regex`.
         match op.operator {
             Not => match op.args[0].as_expression() {
-                Ok(Operation { operator: In, args }) if args.len() == 2 => {
+                Ok(Operation { operator, args })
+                    if args.len() == 2 && matches!(operator, In | MatchesRegex) =>
+                {
                     let (left, right) = (self.term2datum(&args[0])?, self.term2datum(&args[1])?);
-                    self.add_condition(left, Comparison::Nin, right);
+                    let op = if *operator == In {
+                        Comparison::Nin
+                    } else {
+                        Comparison::NotMatchesRegex
+                    };
+                    self.add_condition(left, op, right);
                     Ok(())
                 }
                 _ => df_unsupported_op(op),
@@ -355,6 +370,7 @@ impl FilterInfo {
             Leq => Comparison::Leq,
             Gt => Comparison::Gt,
             Geq => Comparison::Geq,
+            MatchesRegex => Comparison::MatchesRegex,
             _ => return df_unsupported_op(op),
         };
         self.add_condition(left, op, right);
@@ -550,6 +566,8 @@ impl Display for Comparison {
                 Gt => ">",
                 Leq => "<=",
                 Geq => ">=",
+                MatchesRegex => "~",
+                NotMatchesRegex => "!~",
             }
         )
     }
@@ -768,6 +786,37 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_matches_regex() -> PolarResult<()> {
+        let s = String::from;
+        let types = hashmap! {
+            s("Resource") => hashmap!{
+                s("name") => Type::Base {
+                    class_tag: s("String")
+                }
+            }
+        };
+        let name = || term!(op!(Dot, var!("_this"), str!("name")));
+        let ors = vec![ResultEvent::new(hashmap! {
+            sym!("resource") => term!(op!(And,
+                term!(op!(Isa, var!("_this"), term!(pattern!(instance!("Resource"))))),
+                term!(op!(MatchesRegex, name(), str!("^a"))),
+                term!(op!(Not, term!(op!(MatchesRegex, name(), str!("z$")))))
+            ))
+        })];
+
+        let Filter { conditions, .. } = Filter::build(types, ors, "resource", "Resource")?;
+        let name = || Datum::Field(Projection(s("Resource"), Some(s("name"))));
+        assert_eq!(
+            conditions,
+            vec![hashset! {
+                Condition(name(), Comparison::MatchesRegex, Datum::Immediate(value!("^a"))),
+                Condition(name(), Comparison::NotMatchesRegex, Datum::Immediate(value!("z$"))),
+            }]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_vec_of_ands() {
         let ex = or_(
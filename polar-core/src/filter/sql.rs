@@ -0,0 +1,650 @@
+//! Compile a [`Filter`] into a parameterised SQL query.
+//!
+//! Every type name in a filter is mapped to a [`Table`], and every relation to a [`Join`]
+//! between two of those tables. Each table is aliased by its type name in the generated query,
+//! which is unambiguous because a type occurs at most once in a filter.
+//!
+//! ```text
+//! SELECT DISTINCT "Repo".* FROM "repos" AS "Repo"
+//! LEFT JOIN "orgs" AS "Org" ON "Repo"."org_id" = "Org"."id"
+//! WHERE ("Org"."name" = ?)
+//! ```
+//!
+//! Immediate values are never interpolated into the query text; they are returned in
+//! [`SqlQuery::params`] in the order of their placeholders, for the host to bind. Timestamps, big
+//! integers and decimals are bound as they are, for the host to convert to its database's types.
+
+use serde::Serialize;
+
+use super::{Comparison, Condition, Datum, FieldName, Filter, Map, Projection, TypeName};
+use crate::{
+    data_filtering::{Type, Types},
+    error::{df_field_missing, df_sql, PolarResult},
+    terms::{has_rest_var, Value},
+};
+
+/// The SQL flavour to generate.
+///
+/// `matches_regex` patterns are passed through to the database, which evaluates them with its own
+/// regular expression syntax rather than the `regex` crate's, so patterns outside the syntax the
+/// two share (e.g., `\b`, a backspace in PostgreSQL) can behave differently than in the VM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// Positional `?` placeholders; no array columns. `matches_regex` is compiled to `REGEXP`,
+    /// which requires the host to register a `regexp()` function.
+    Sqlite,
+    /// Numbered `$1` placeholders; `IN` over a field is compiled to `= ANY(...)`, and
+    /// `matches_regex` to `~`.
+    Postgres,
+}
+
+/// The table backing a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    pub name: String,
+    /// The column that uniquely identifies a row, used for projections without a field.
+    pub id_column: String,
+    /// Columns whose name differs from the field name. Unmapped fields use the field name.
+    pub columns: Map<FieldName, String>,
+}
+
+/// The fields joining the two sides of a relation, e.g. `Repo.org_id = Org.id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Join {
+    pub my_field: FieldName,
+    pub other_field: FieldName,
+}
+
+/// Maps the types and relations of a [`Filter`] onto tables and columns.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    pub tables: Map<TypeName, Table>,
+    /// Keyed by the type the relation is declared on and the relation's field name.
+    pub joins: Map<(TypeName, FieldName), Join>,
+}
+
+/// A query produced by [`Filter::to_sql`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SqlQuery {
+    pub sql: String,
+    /// Values for the query's placeholders, in order.
+    pub params: Vec<Value>,
+}
+
+impl Table {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            id_column: "id".to_string(),
+            columns: Map::new(),
+        }
+    }
+
+    pub fn with_id_column(mut self, column: &str) -> Self {
+        self.id_column = column.to_string();
+        self
+    }
+
+    pub fn with_column(mut self, field: &str, column: &str) -> Self {
+        self.columns.insert(field.to_string(), column.to_string());
+        self
+    }
+
+    fn column<'a>(&'a self, field: Option<&'a str>) -> &'a str {
+        match field {
+            None => &self.id_column,
+            Some(field) => self.columns.get(field).map_or(field, String::as_str),
+        }
+    }
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A schema with a join for every relation declared in `types`. Tables must still be added
+    /// with [`Schema::with_table`].
+    pub fn from_types(types: &Types) -> Self {
+        let joins = types
+            .iter()
+            .flat_map(|(typ, fields)| {
+                fields.iter().filter_map(move |(field, def)| match def {
+                    Type::Relation {
+                        my_field,
+                        other_field,
+                        ..
+                    } => Some((
+                        (typ.clone(), field.clone()),
+                        Join {
+                            my_field: my_field.clone(),
+                            other_field: other_field.clone(),
+                        },
+                    )),
+                    _ => None,
+                })
+            })
+            .collect();
+        Self {
+            joins,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_table(mut self, type_name: &str, table: Table) -> Self {
+        self.tables.insert(type_name.to_string(), table);
+        self
+    }
+
+    pub fn with_join(
+        mut self,
+        type_name: &str,
+        field_name: &str,
+        my_field: &str,
+        other_field: &str,
+    ) -> Self {
+        self.joins.insert(
+            (type_name.to_string(), field_name.to_string()),
+            Join {
+                my_field: my_field.to_string(),
+                other_field: other_field.to_string(),
+            },
+        );
+        self
+    }
+
+    fn table(&self, type_name: &str) -> PolarResult<&Table> {
+        match self.tables.get(type_name) {
+            Some(table) => Ok(table),
+            None => df_sql(format!("no table for type `{}`", type_name)),
+        }
+    }
+}
+
+impl Dialect {
+    fn quote(self, ident: &str) -> String {
+        format!("\"{}\"", ident.replace('"', "\"\""))
+    }
+
+    fn placeholder(self, n: usize) -> String {
+        match self {
+            Self::Sqlite => "?".to_string(),
+            Self::Postgres => format!("${}", n),
+        }
+    }
+}
+
+/// Accumulates the parameters of a query while its conditions are rendered.
+struct Builder<'a> {
+    schema: &'a Schema,
+    dialect: Dialect,
+    params: Vec<Value>,
+}
+
+impl<'a> Builder<'a> {
+    fn column(&self, Projection(typ, field): &Projection) -> PolarResult<String> {
+        let column = self.schema.table(typ)?.column(field.as_deref());
+        Ok(format!(
+            "{}.{}",
+            self.dialect.quote(typ),
+            self.dialect.quote(column)
+        ))
+    }
+
+    fn param(&mut self, value: &Value) -> PolarResult<String> {
+        match value {
+            Value::Number(_) | Value::String(_) | Value::Boolean(_) | Value::Timestamp(_) => {
+                self.params.push(value.clone());
+                Ok(self.dialect.placeholder(self.params.len()))
+            }
+            _ => df_sql(format!("unsupported value `{}`", value)),
+        }
+    }
+
+    fn datum(&mut self, datum: &Datum) -> PolarResult<String> {
+        match datum {
+            Datum::Field(proj) => self.column(proj),
+            Datum::Immediate(value) => self.param(value),
+        }
+    }
+
+    fn condition(&mut self, condition: &Condition) -> PolarResult<String> {
+        use {Comparison::*, Datum::*};
+        let Condition(left, op, right) = condition;
+        match (left, op, right) {
+            // Comparisons between immediates (e.g. the `true = false` of an empty filter) are
+            // decided here: Postgres can't infer the type of a parameter compared to another.
+            (Immediate(l), Eq, Immediate(r)) => Ok(constant(l == r)),
+            (Immediate(l), Neq, Immediate(r)) => Ok(constant(l != r)),
+
+            (_, In | Nin, Immediate(Value::List(list))) => {
+                if has_rest_var(list) {
+                    return df_sql(format!("unsupported value `{}`", right));
+                }
+                if list.is_empty() {
+                    return Ok(constant(*op == Nin));
+                }
+                let left = self.datum(left)?;
+                let items = list
+                    .iter()
+                    .map(|term| self.param(term.value()))
+                    .collect::<PolarResult<Vec<_>>>()?;
+                let op = if *op == In { "IN" } else { "NOT IN" };
+                Ok(format!("{} {} ({})", left, op, items.join(", ")))
+            }
+
+            (_, In | Nin, Field(_)) if self.dialect == Dialect::Postgres => {
+                let any = format!("{} = ANY({})", self.datum(left)?, self.datum(right)?);
+                Ok(if *op == In {
+                    any
+                } else {
+                    format!("NOT ({})", any)
+                })
+            }
+
+            (_, In | Nin, _) => df_sql(format!("unsupported condition `{}`", condition)),
+
+            (_, MatchesRegex | NotMatchesRegex, _) => {
+                let (left, right) = (self.datum(left)?, self.datum(right)?);
+                Ok(match (self.dialect, op) {
+                    (Dialect::Postgres, MatchesRegex) => format!("{} ~ {}", left, right),
+                    (Dialect::Postgres, _) => format!("{} !~ {}", left, right),
+                    (Dialect::Sqlite, MatchesRegex) => format!("{} REGEXP {}", left, right),
+                    (Dialect::Sqlite, _) => format!("NOT ({} REGEXP {})", left, right),
+                })
+            }
+
+            _ => Ok(format!(
+                "{} {} {}",
+                self.datum(left)?,
+                op,
+                self.datum(right)?
+            )),
+        }
+    }
+}
+
+fn constant(b: bool) -> String {
+    if b { "1 = 1" } else { "1 = 0" }.to_string()
+}
+
+impl Filter {
+    /// Compile the filter to a `SELECT` of the rows of `root` that pass it.
+    ///
+    /// Relations become `LEFT JOIN`s so that a disjunct which does not mention a relation is not
+    /// restricted by it, and rows are deduplicated with `DISTINCT`.
+    pub fn to_sql(&self, schema: &Schema, dialect: Dialect) -> PolarResult<SqlQuery> {
+        let quote = |ident: &str| dialect.quote(ident);
+        let root = schema.table(&self.root)?;
+        let mut sql = format!(
+            "SELECT DISTINCT {}.* FROM {} AS {}",
+            quote(&self.root),
+            quote(&root.name),
+            quote(&self.root)
+        );
+
+        for super::Relation(from, field, to) in &self.relations {
+            let join = match schema.joins.get(&(from.clone(), field.clone())) {
+                Some(join) => join,
+                None => return df_field_missing(from, field),
+            };
+            let (from_table, to_table) = (schema.table(from)?, schema.table(to)?);
+            sql.push_str(&format!(
+                " LEFT JOIN {} AS {} ON {}.{} = {}.{}",
+                quote(&to_table.name),
+                quote(to),
+                quote(from),
+                quote(from_table.column(Some(&join.my_field))),
+                quote(to),
+                quote(to_table.column(Some(&join.other_field))),
+            ));
+        }
+
+        let mut builder = Builder {
+            schema,
+            dialect,
+            params: vec![],
+        };
+        let disjuncts = self
+            .conditions
+            .iter()
+            .map(|conjuncts| {
+                // Sort conditions so that the generated query is deterministic.
+                let mut conjuncts = conjuncts.iter().collect::<Vec<_>>();
+                conjuncts.sort_by_cached_key(|c| c.to_string());
+                let conjuncts = conjuncts
+                    .into_iter()
+                    .map(|c| builder.condition(c))
+                    .collect::<PolarResult<Vec<_>>>()?;
+                Ok(if conjuncts.is_empty() {
+                    constant(true)
+                } else {
+                    conjuncts.join(" AND ")
+                })
+            })
+            .collect::<PolarResult<Vec<_>>>()?;
+
+        let condition = if disjuncts.is_empty() {
+            constant(false)
+        } else {
+            disjuncts
+                .iter()
+                .map(|d| format!("({})", d))
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        };
+        sql.push_str(" WHERE ");
+        sql.push_str(&condition);
+
+        Ok(SqlQuery {
+            sql,
+            params: builder.params,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::{ErrorKind, PolarError, RuntimeError};
+    use crate::filter::{singleton, Relation, Set};
+    use crate::numerics::Numeric;
+    use crate::terms::Term;
+    use rusqlite::{params_from_iter, types::Value as SqlValue, Connection};
+
+    fn s(x: &str) -> String {
+        x.to_string()
+    }
+
+    fn field(typ: &str, field: &str) -> Datum {
+        Datum::Field(Projection(s(typ), Some(s(field))))
+    }
+
+    fn schema() -> Schema {
+        Schema::new()
+            .with_table("Org", Table::new("orgs").with_column("name", "org_name"))
+            .with_table("Repo", Table::new("repos"))
+            .with_table("Issue", Table::new("issues").with_id_column("issue_id"))
+            .with_join("Repo", "org", "org_id", "id")
+            .with_join("Issue", "repo", "repo_id", "id")
+    }
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE orgs (id INTEGER PRIMARY KEY, org_name TEXT);
+            CREATE TABLE repos (id INTEGER PRIMARY KEY, org_id INTEGER, is_public BOOLEAN);
+            CREATE TABLE issues (issue_id INTEGER PRIMARY KEY, repo_id INTEGER, title TEXT);
+            INSERT INTO orgs VALUES (1, 'apple'), (2, 'osohq');
+            INSERT INTO repos VALUES (1, 1, false), (2, 2, false), (3, 2, true), (4, NULL, true);
+            INSERT INTO issues VALUES (1, 1, 'bug'), (2, 2, 'bug'), (3, 3, 'feature');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    /// Run `filter` against the test database, returning the ids of the matching rows.
+    fn run(filter: &Filter) -> PolarResult<Vec<i64>> {
+        let SqlQuery { sql, params } = filter.to_sql(&schema(), Dialect::Sqlite)?;
+        let params = params.into_iter().map(|value| match value {
+            Value::Number(Numeric::Integer(i)) => SqlValue::Integer(i),
+            Value::Number(Numeric::Float(f)) => SqlValue::Real(f),
+            Value::String(s) => SqlValue::Text(s),
+            Value::Boolean(b) => SqlValue::Integer(b as i64),
+            _ => unreachable!(),
+        });
+        let conn = db();
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let mut ids = stmt
+            .query_map(params_from_iter(params), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<i64>, _>>()
+            .unwrap();
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    #[test]
+    fn test_sql_conditions() -> PolarResult<()> {
+        let filter = Filter {
+            root: s("Repo"),
+            relations: vec![],
+            conditions: vec![singleton(Condition(
+                field("Repo", "is_public"),
+                Comparison::Eq,
+                Datum::Immediate(Value::Boolean(true)),
+            ))],
+        };
+        assert_eq!(
+            filter.to_sql(&schema(), Dialect::Sqlite)?,
+            SqlQuery {
+                sql: s(
+                    r#"SELECT DISTINCT "Repo".* FROM "repos" AS "Repo" WHERE ("Repo"."is_public" = ?)"#
+                ),
+                params: vec![Value::Boolean(true)],
+            }
+        );
+        assert_eq!(run(&filter)?, vec![3, 4]);
+
+        let filter = Filter {
+            root: s("Repo"),
+            relations: vec![],
+            conditions: vec![
+                hashset! {
+                    Condition(field("Repo", "id"), Comparison::Gt, Datum::Immediate(value!(1))),
+                    Condition(field("Repo", "id"), Comparison::Neq, Datum::Immediate(value!(3))),
+                },
+                singleton(Condition(
+                    Datum::Immediate(value!(1)),
+                    Comparison::Eq,
+                    Datum::Field(Projection(s("Repo"), None)),
+                )),
+            ],
+        };
+        assert_eq!(run(&filter)?, vec![1, 2, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sql_relations() -> PolarResult<()> {
+        let filter = Filter {
+            root: s("Issue"),
+            relations: vec![
+                Relation(s("Issue"), s("repo"), s("Repo")),
+                Relation(s("Repo"), s("org"), s("Org")),
+            ],
+            conditions: vec![
+                singleton(Condition(
+                    field("Org", "name"),
+                    Comparison::Eq,
+                    Datum::Immediate(value!("osohq")),
+                )),
+                singleton(Condition(
+                    field("Issue", "title"),
+                    Comparison::Eq,
+                    Datum::Immediate(value!("bug")),
+                )),
+            ],
+        };
+        assert_eq!(
+            filter.to_sql(&schema(), Dialect::Postgres)?.sql,
+            [
+                r#"SELECT DISTINCT "Issue".* FROM "issues" AS "Issue""#,
+                r#"LEFT JOIN "repos" AS "Repo" ON "Issue"."repo_id" = "Repo"."id""#,
+                r#"LEFT JOIN "orgs" AS "Org" ON "Repo"."org_id" = "Org"."id""#,
+                r#"WHERE ("Org"."org_name" = $1) OR ("Issue"."title" = $2)"#,
+            ]
+            .join(" ")
+        );
+        assert_eq!(run(&filter)?, vec![1, 2, 3]);
+
+        let filter = Filter {
+            root: s("Repo"),
+            relations: vec![Relation(s("Repo"), s("org"), s("Org"))],
+            conditions: vec![singleton(Condition(
+                field("Org", "name"),
+                Comparison::Neq,
+                Datum::Immediate(value!("apple")),
+            ))],
+        };
+        assert_eq!(run(&filter)?, vec![2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sql_in() -> PolarResult<()> {
+        let in_list = |op, items: Vec<Term>| Filter {
+            root: s("Repo"),
+            relations: vec![],
+            conditions: vec![singleton(Condition(
+                field("Repo", "id"),
+                op,
+                Datum::Immediate(Value::List(items)),
+            ))],
+        };
+        let filter = in_list(Comparison::In, vec![term!(1), term!(3), term!(5)]);
+        assert_eq!(
+            filter.to_sql(&schema(), Dialect::Postgres)?,
+            SqlQuery {
+                sql: s(
+                    r#"SELECT DISTINCT "Repo".* FROM "repos" AS "Repo" WHERE ("Repo"."id" IN ($1, $2, $3))"#
+                ),
+                params: vec![value!(1), value!(3), value!(5)],
+            }
+        );
+        assert_eq!(run(&filter)?, vec![1, 3]);
+        assert_eq!(
+            run(&in_list(Comparison::Nin, vec![term!(1), term!(3)]))?,
+            vec![2, 4]
+        );
+        assert_eq!(run(&in_list(Comparison::In, vec![]))?, Vec::<i64>::new());
+        assert_eq!(run(&in_list(Comparison::Nin, vec![]))?, vec![1, 2, 3, 4]);
+
+        // Membership in a field is only supported for array columns in Postgres.
+        let filter = Filter {
+            root: s("Repo"),
+            relations: vec![],
+            conditions: vec![singleton(Condition(
+                Datum::Immediate(value!(1)),
+                Comparison::Nin,
+                field("Repo", "tags"),
+            ))],
+        };
+        assert_eq!(
+            filter.to_sql(&schema(), Dialect::Postgres)?.sql,
+            r#"SELECT DISTINCT "Repo".* FROM "repos" AS "Repo" WHERE (NOT ($1 = ANY("Repo"."tags")))"#
+        );
+        assert!(matches!(
+            filter.to_sql(&schema(), Dialect::Sqlite),
+            Err(PolarError(ErrorKind::Runtime(
+                RuntimeError::DataFilteringSql { .. }
+            )))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sql_matches_regex() -> PolarResult<()> {
+        let matches = |op| Filter {
+            root: s("Org"),
+            relations: vec![],
+            conditions: vec![singleton(Condition(
+                field("Org", "name"),
+                op,
+                Datum::Immediate(value!("^o")),
+            ))],
+        };
+        let select = r#"SELECT DISTINCT "Org".* FROM "orgs" AS "Org" WHERE "#;
+        let sql = |op, dialect| -> PolarResult<String> {
+            Ok(matches(op)
+                .to_sql(&schema(), dialect)?
+                .sql
+                .replace(select, ""))
+        };
+        assert_eq!(
+            sql(Comparison::MatchesRegex, Dialect::Postgres)?,
+            r#"("Org"."org_name" ~ $1)"#
+        );
+        assert_eq!(
+            sql(Comparison::NotMatchesRegex, Dialect::Postgres)?,
+            r#"("Org"."org_name" !~ $1)"#
+        );
+        assert_eq!(
+            sql(Comparison::MatchesRegex, Dialect::Sqlite)?,
+            r#"("Org"."org_name" REGEXP ?)"#
+        );
+        assert_eq!(
+            sql(Comparison::NotMatchesRegex, Dialect::Sqlite)?,
+            r#"(NOT ("Org"."org_name" REGEXP ?))"#
+        );
+        assert_eq!(
+            matches(Comparison::MatchesRegex)
+                .to_sql(&schema(), Dialect::Sqlite)?
+                .params,
+            vec![value!("^o")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sql_empty_filter() -> PolarResult<()> {
+        let filter = Filter::empty("Repo");
+        let query = filter.to_sql(&schema(), Dialect::Postgres)?;
+        assert_eq!(
+            query.sql,
+            r#"SELECT DISTINCT "Repo".* FROM "repos" AS "Repo" WHERE (1 = 0)"#
+        );
+        assert!(query.params.is_empty());
+        assert_eq!(run(&filter)?, Vec::<i64>::new());
+
+        let filter = Filter {
+            root: s("Repo"),
+            relations: vec![],
+            conditions: vec![Set::new()],
+        };
+        assert_eq!(run(&filter)?, vec![1, 2, 3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sql_errors() {
+        let filter = |root: &str, relations| Filter {
+            root: s(root),
+            relations,
+            conditions: vec![],
+        };
+        assert!(matches!(
+            filter("Unknown", vec![]).to_sql(&schema(), Dialect::Sqlite),
+            Err(PolarError(ErrorKind::Runtime(RuntimeError::DataFilteringSql { msg })))
+                if msg == "no table for type `Unknown`"
+        ));
+        assert!(matches!(
+            filter("Org", vec![Relation(s("Org"), s("repos"), s("Repo"))])
+                .to_sql(&schema(), Dialect::Sqlite),
+            Err(PolarError(ErrorKind::Runtime(
+                RuntimeError::DataFilteringFieldMissing { .. }
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_schema_from_types() {
+        let types = hashmap! {
+            s("Repo") => hashmap! {
+                s("id") => Type::Base { class_tag: s("Integer") },
+                s("org") => Type::Relation {
+                    kind: s("one"),
+                    other_class_tag: s("Org"),
+                    my_field: s("org_id"),
+                    other_field: s("id"),
+                },
+            },
+        };
+        assert_eq!(
+            Schema::from_types(&types),
+            Schema::new().with_join("Repo", "org", "org_id", "id")
+        );
+    }
+}
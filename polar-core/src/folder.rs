@@ -35,6 +35,12 @@ pub trait Folder: Sized {
     fn fold_boolean(&mut self, b: bool) -> bool {
         fold_boolean(b, self)
     }
+    fn fold_timestamp(&mut self, t: Timestamp) -> Timestamp {
+        fold_timestamp(t, self)
+    }
+    fn fold_duration(&mut self, d: Duration) -> Duration {
+        fold_duration(d, self)
+    }
     fn fold_instance_id(&mut self, i: u64) -> u64 {
         fold_instance_id(i, self)
     }
@@ -114,6 +120,8 @@ pub fn fold_value<T: Folder>(v: Value, fld: &mut T) -> Value {
         Value::Number(n) => Value::Number(fld.fold_number(n)),
         Value::String(s) => Value::String(fld.fold_string(s)),
         Value::Boolean(b) => Value::Boolean(fld.fold_boolean(b)),
+        Value::Timestamp(t) => Value::Timestamp(fld.fold_timestamp(t)),
+        Value::Duration(d) => Value::Duration(fld.fold_duration(d)),
         Value::ExternalInstance(e) => Value::ExternalInstance(fld.fold_external_instance(e)),
         Value::Dictionary(d) => Value::Dictionary(fld.fold_dictionary(d)),
         Value::Pattern(p) => Value::Pattern(fld.fold_pattern(p)),
@@ -137,6 +145,14 @@ pub fn fold_boolean<T: Folder>(b: bool, _fld: &mut T) -> bool {
     b
 }
 
+pub fn fold_timestamp<T: Folder>(t: Timestamp, _fld: &mut T) -> Timestamp {
+    t
+}
+
+pub fn fold_duration<T: Folder>(d: Duration, _fld: &mut T) -> Duration {
+    d
+}
+
 pub fn fold_instance_id<T: Folder>(id: u64, _fld: &mut T) -> u64 {
     id
 }
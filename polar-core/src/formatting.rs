@@ -133,6 +133,7 @@ fn precedence(o: &Operator) -> i32 {
         Operator::Leq => 5,
         Operator::Neq => 5,
         Operator::Gt => 5,
+        Operator::MatchesRegex => 5,
         Operator::Lt => 5,
         Operator::Unify => 4,
         Operator::Assign => 4,
@@ -336,6 +337,10 @@ mod display {
             match self {
                 Self::Integer(i) => write!(f, "{}", i),
                 Self::Float(float) => write!(f, "{}", float),
+                #[cfg(feature = "bignum")]
+                Self::BigInteger(i) => write!(f, "{}", i),
+                #[cfg(feature = "bignum")]
+                Self::Decimal(d) => write!(f, "{}M", d.to_plain_string()),
             }
         }
     }
@@ -443,6 +448,7 @@ mod to_polar {
                 Neq => "!=",
                 Gt => ">",
                 Lt => "<",
+                MatchesRegex => "matches_regex",
                 Or => "or",
                 And => "and",
                 New => "new",
@@ -511,8 +517,8 @@ mod to_polar {
                     to_polar_parens(self.operator, &self.args[0])
                 ),
                 // Binary operators
-                Mul | Div | Mod | Rem | Add | Sub | Eq | Geq | Leq | Neq | Gt | Lt | Unify
-                | Isa | In | Assign => match self.args.len() {
+                Mul | Div | Mod | Rem | Add | Sub | Eq | Geq | Leq | Neq | Gt | Lt
+                | MatchesRegex | Unify | Isa | In | Assign => match self.args.len() {
                     2 => format!(
                         "{} {} {}",
                         to_polar_parens(self.operator, &self.args[0]),
@@ -644,6 +650,8 @@ mod to_polar {
                         "false".to_string()
                     }
                 }
+                Value::Timestamp(t) => format!("@{}", t),
+                Value::Duration(d) => d.to_string(),
                 Value::Dictionary(i) => i.to_polar(),
                 Value::Pattern(i) => i.to_polar(),
                 Value::ExternalInstance(i) => i.to_polar(),
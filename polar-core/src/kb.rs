@@ -7,10 +7,14 @@ use super::constants::Constants;
 use super::counter::Counter;
 use super::diagnostic::Diagnostic;
 use super::error::{invalid_state, PolarError, PolarResult, RuntimeError, ValidationError};
+use super::policy_test::PolicyTest;
+use super::regexes::Regexes;
 use super::resource_block::{ResourceBlocks, ACTOR_UNION_NAME, RESOURCE_UNION_NAME};
 use super::rules::*;
+use super::sources::Source;
+use super::temporal::{calls_now, Clock, SystemClock};
 use super::terms::*;
-use super::validations::check_undefined_rule_calls;
+use super::validations::{check_regex_patterns, check_undefined_rule_calls};
 
 enum RuleParamMatch {
     True,
@@ -34,14 +38,29 @@ pub struct KnowledgeBase {
 
     /// Map from contents to filename for files loaded into the KB.
     loaded_content: HashMap<String, String>,
+    /// Sources loaded into the KB, in order.
+    pub(crate) loaded_sources: Vec<Source>,
 
     rules: HashMap<Symbol, GenericRule>,
     rule_types: RuleTypes,
+    /// Names of rules whose answers are tabled during a query.
+    tabled_rules: HashSet<Symbol>,
+    /// Compiled string literal patterns of `matches_regex` operations in rules.
+    pub(crate) regexes: Regexes,
+    /// The clock read by `now`, if not the system clock.
+    clock: Option<Arc<dyn Clock>>,
+    /// Whether a rule calls `now`, so that query results may change over time.
+    calls_now: bool,
+    /// Incremented whenever the rules, constants, MROs or clock change, since query results may
+    /// change with them.
+    generation: u64,
     /// For symbols returned from gensym.
     gensym_counter: Counter,
     /// For call IDs, instance IDs, symbols, etc.
     id_counter: Counter,
     pub inline_queries: Vec<Term>,
+    /// Policy tests, in the order they were loaded.
+    pub tests: Vec<PolicyTest>,
 
     /// Resource block bookkeeping.
     pub resource_blocks: ResourceBlocks,
@@ -60,6 +79,32 @@ impl KnowledgeBase {
         self.id_counter.next()
     }
 
+    /// Read the time for `now` from `clock` instead of the system clock.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = Some(clock);
+        self.generation += 1;
+    }
+
+    /// Whether a rule calls `now`, so that the results of queries may change over time even
+    /// if the policy doesn't.
+    pub fn calls_now(&self) -> bool {
+        self.calls_now
+    }
+
+    /// Changes whenever the rules, constants, MROs or clock change, so that query results
+    /// cached at one generation can be discarded at the next.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The current time according to the KB's clock.
+    pub fn now(&self) -> Timestamp {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemClock.now(),
+        }
+    }
+
     pub fn id_counter(&self) -> Counter {
         self.id_counter.clone()
     }
@@ -89,6 +134,9 @@ impl KnowledgeBase {
             .rules
             .entry(rule.name.clone())
             .or_insert_with(|| GenericRule::new(rule.name.clone(), vec![]));
+        self.regexes.add_literals(&rule.body);
+        self.calls_now |= calls_now(&rule.body);
+        self.generation += 1;
         generic_rule.add_rule(Arc::new(rule));
     }
 
@@ -106,6 +154,7 @@ impl KnowledgeBase {
         }
 
         diagnostics.append(&mut check_undefined_rule_calls(self));
+        diagnostics.append(&mut check_regex_patterns(self));
 
         diagnostics
     }
@@ -449,6 +498,12 @@ impl KnowledgeBase {
                                 Value::String(_) => instance!(sym!("String")),
                                 Value::Number(Numeric::Integer(_)) => instance!(sym!("Integer")),
                                 Value::Number(Numeric::Float(_)) => instance!(sym!("Float")),
+                                #[cfg(feature = "bignum")]
+                                Value::Number(Numeric::BigInteger(_)) => {
+                                    instance!(sym!("Integer"))
+                                }
+                                #[cfg(feature = "bignum")]
+                                Value::Number(Numeric::Decimal(_)) => instance!(sym!("Decimal")),
                                 Value::Boolean(_) => instance!(sym!("Boolean")),
                                 Value::List(_) => instance!(sym!("List")),
                                 Value::Dictionary(rule_fields) => {
@@ -554,11 +609,16 @@ impl KnowledgeBase {
         &self.rules
     }
 
-    #[cfg(test)]
     pub fn get_rule_types(&self, name: &Symbol) -> Option<&Vec<Rule>> {
         self.rule_types.get(name)
     }
 
+    /// Names of rules with rule types, including built-in rule types and those created for
+    /// resource blocks.
+    pub fn get_rule_type_names(&self) -> impl Iterator<Item = &Symbol> {
+        self.rule_types.names()
+    }
+
     pub fn get_generic_rule(&self, name: &Symbol) -> Option<&GenericRule> {
         self.rules.get(name)
     }
@@ -599,6 +659,7 @@ impl KnowledgeBase {
         } else {
             self.constants.insert(name, value)
         }
+        self.generation += 1;
         Ok(())
     }
 
@@ -640,15 +701,68 @@ impl KnowledgeBase {
             return invalid_state(format!("Cannot add MRO for unregistered class {}", name));
         }
         self.mro.insert(name, mro);
+        self.generation += 1;
         Ok(())
     }
 
+    /// Table the answers to calls to the rule `name`: each variant of a call is evaluated once per
+    /// query, and recursive calls read the answers found so far, so left-recursive rules and
+    /// rules over cyclic relationships terminate.
+    pub fn table_rule(&mut self, name: Symbol) {
+        self.tabled_rules.insert(name);
+        self.generation += 1;
+    }
+
+    pub fn is_tabled(&self, name: &Symbol) -> bool {
+        self.tabled_rules.contains(name)
+    }
+
     pub fn clear_rules(&mut self) {
         self.rules.clear();
         self.rule_types.reset();
         self.inline_queries.clear();
+        self.tests.clear();
         self.loaded_content.clear();
+        self.loaded_sources.clear();
         self.resource_blocks.clear();
+        self.regexes.clear();
+        self.calls_now = false;
+        self.generation += 1;
+    }
+
+    /// A KB without rules that has the constants, MROs, and tabled rules of this one and shares
+    /// its ID counters. A policy can be loaded into it and then swapped in with
+    /// [`KnowledgeBase::replace_rules`].
+    pub fn without_rules(&self) -> Self {
+        Self {
+            constants: self.constants.clone(),
+            mro: self.mro.clone(),
+            tabled_rules: self.tabled_rules.clone(),
+            gensym_counter: self.gensym_counter.clone(),
+            id_counter: self.id_counter.clone(),
+            clock: self.clock.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Replace the loaded policy with the one loaded into `other`, keeping the constants and
+    /// MROs of this KB.
+    pub fn replace_rules(&mut self, other: KnowledgeBase) {
+        self.rules = other.rules;
+        self.rule_types = other.rule_types;
+        self.inline_queries = other.inline_queries;
+        self.tests = other.tests;
+        self.loaded_content = other.loaded_content;
+        self.loaded_sources = other.loaded_sources;
+        self.resource_blocks = other.resource_blocks;
+        self.regexes = other.regexes;
+        self.calls_now = other.calls_now;
+        self.generation += 1;
+    }
+
+    /// The sources loaded into the KB, in order.
+    pub fn loaded_sources(&self) -> &[Source] {
+        &self.loaded_sources
     }
 
     // TODO(gj): Remove this fn & `FileLoading` error variant. These checks don't spark joy.
@@ -835,6 +949,27 @@ impl KnowledgeBase {
         Ok(())
     }
 
+    /// The tags of every class that `instance` is an instance of, if it is an external instance
+    /// with a registered class. Matches the in-core class check of the VM's `isa`, which
+    /// consults the MRO registered for the instance's class.
+    pub fn instance_classes(&self, instance: &Term) -> Option<HashSet<Symbol>> {
+        let class_id = match instance.value() {
+            Value::ExternalInstance(ExternalInstance {
+                class_id: Some(class_id),
+                ..
+            }) => class_id,
+            _ => return None,
+        };
+        let class = self.get_symbol_for_class_id(class_id)?;
+        let mro = self.mro.get(class).map_or(&[][..], |mro| &mro[..]);
+        Some(
+            mro.iter()
+                .filter_map(|id| self.get_symbol_for_class_id(id))
+                .cloned()
+                .collect(),
+        )
+    }
+
     pub fn is_union(&self, maybe_union: &Term) -> bool {
         (maybe_union.is_actor_union()) || (maybe_union.is_resource_union())
     }
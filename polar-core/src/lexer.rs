@@ -5,10 +5,52 @@ use std::{
     str::{CharIndices, FromStr},
 };
 
-use super::{error::ParseErrorKind, terms::Symbol};
+use super::{
+    error::ParseErrorKind,
+    terms::{Duration, Numeric, Symbol, Timestamp},
+};
 
 pub type SrcPos = (usize, usize);
 
+/// Define `KEYWORDS` and `keyword()` from the same list so the two can't drift apart.
+macro_rules! keywords {
+    ($($word:literal => $token:expr,)*) => {
+        /// Words that lex as keywords or literals instead of symbols.
+        pub const KEYWORDS: &[&str] = &[$($word),*];
+
+        /// The token for `word` if it's a keyword or literal.
+        fn keyword(word: &str) -> Option<Token> {
+            match word {
+                $($word => Some($token),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+keywords! {
+    "true" => Token::Boolean(true),
+    "false" => Token::Boolean(false),
+    "inf" => Token::Float(f64::INFINITY),
+    "nan" => Token::Float(f64::NAN),
+    "new" => Token::New,
+    "in" => Token::In,
+    "cut" => Token::Cut,
+    "debug" => Token::Debug,
+    "print" => Token::Print,
+    "isa" => Token::Isa,
+    "forall" => Token::ForAll,
+    "if" => Token::If,
+    "and" => Token::And,
+    "or" => Token::Or,
+    "not" => Token::Not,
+    "matches" => Token::Matches,
+    "matches_regex" => Token::MatchesRegex,
+    "type" => Token::Type,
+    "mod" => Token::Mod,
+    "rem" => Token::Rem,
+}
+
 // Take a location in a string and return the row and column.
 pub fn loc_to_pos(src: &str, loc: usize) -> SrcPos {
     let mut row = 0;
@@ -49,49 +91,54 @@ pub type Spanned<Tok, Loc, Error> = Result<(Loc, Tok, Loc), Error>;
 pub enum Token {
     Integer(i64),
     Float(f64),
+    #[cfg_attr(not(feature = "bignum"), allow(dead_code))]
+    Number(Numeric), // A big integer or decimal.
+    Timestamp(Timestamp),
+    Duration(Duration),
     String(String),
     Boolean(bool),
     Symbol(Symbol),
-    Colon,     // :
-    Comma,     // ,
-    LB,        // [
-    RB,        // ]
-    LP,        // (
-    RP,        // )
-    LCB,       // {
-    RCB,       // }
-    Dot,       // .
-    New,       // new
-    Bang,      // !
-    Mul,       // *
-    Div,       // /
-    Mod,       // mod
-    Rem,       // rem
-    Add,       // +
-    Sub,       // -
-    Eq,        // ==
-    Neq,       // !=
-    Leq,       // <=
-    Geq,       // >=
-    Lt,        // <
-    Gt,        // >
-    Unify,     // =
-    Assign,    // :=
-    Pipe,      // |
-    SemiColon, // ;
-    Query,     // ?=
-    In,        // in
-    Cut,       // cut
-    Debug,     // debug()
-    Print,     // print()
-    Isa,       // isa
-    ForAll,    // forall
-    If,        // if
-    And,       // and
-    Or,        // or
-    Not,       // not
-    Matches,   // matches
-    Type,      // type
+    Colon,        // :
+    Comma,        // ,
+    LB,           // [
+    RB,           // ]
+    LP,           // (
+    RP,           // )
+    LCB,          // {
+    RCB,          // }
+    Dot,          // .
+    New,          // new
+    Bang,         // !
+    Mul,          // *
+    Div,          // /
+    Mod,          // mod
+    Rem,          // rem
+    Add,          // +
+    Sub,          // -
+    Eq,           // ==
+    Neq,          // !=
+    Leq,          // <=
+    Geq,          // >=
+    Lt,           // <
+    Gt,           // >
+    Unify,        // =
+    Assign,       // :=
+    Pipe,         // |
+    SemiColon,    // ;
+    Query,        // ?=
+    In,           // in
+    Cut,          // cut
+    Debug,        // debug()
+    Print,        // print()
+    Isa,          // isa
+    ForAll,       // forall
+    If,           // if
+    And,          // and
+    Or,           // or
+    Not,          // not
+    Matches,      // matches
+    MatchesRegex, // matches_regex
+    Type,         // type
 }
 
 impl ToString for Token {
@@ -99,49 +146,53 @@ impl ToString for Token {
         match self {
             Token::Integer(i) => i.to_string(),
             Token::Float(f) => f.to_string(),
+            Token::Number(n) => n.to_string(),
+            Token::Timestamp(t) => format!("@{}", t),
+            Token::Duration(d) => d.to_string(),
             Token::String(s) => s.clone(),
             Token::Boolean(b) => b.to_string(),
             Token::Symbol(sym) => sym.0.clone(),
-            Token::Colon => ":".to_owned(),         // :
-            Token::Comma => ",".to_owned(),         // ,
-            Token::LB => "[".to_owned(),            // [
-            Token::RB => "]".to_owned(),            // ]
-            Token::LP => "(".to_owned(),            // (
-            Token::RP => ")".to_owned(),            // )
-            Token::LCB => "{".to_owned(),           // {
-            Token::RCB => "}".to_owned(),           // }
-            Token::Dot => ".".to_owned(),           // .
-            Token::New => "new".to_owned(),         // new
-            Token::Bang => "!".to_owned(),          // !
-            Token::Mul => "*".to_owned(),           // *
-            Token::Div => "/".to_owned(),           // /
-            Token::Mod => "mod".to_owned(),         // mod
-            Token::Rem => "rem".to_owned(),         // rem
-            Token::Add => "+".to_owned(),           // +
-            Token::Sub => "-".to_owned(),           // -
-            Token::Eq => "==".to_owned(),           // ==
-            Token::Neq => "!=".to_owned(),          // !=
-            Token::Leq => "<=".to_owned(),          // <=
-            Token::Geq => ">=".to_owned(),          // >=
-            Token::Lt => "<".to_owned(),            // <
-            Token::Gt => ">".to_owned(),            // >
-            Token::Unify => "=".to_owned(),         // =
-            Token::Assign => ":=".to_owned(),       // :=
-            Token::Pipe => "|".to_owned(),          // |
-            Token::SemiColon => ";".to_owned(),     // ;
-            Token::Query => "?=".to_owned(),        // ?=
-            Token::In => "in".to_owned(),           // in
-            Token::Cut => "cut".to_owned(),         // cut
-            Token::Debug => "debug".to_owned(),     // debug
-            Token::Print => "print".to_owned(),     // print
-            Token::Isa => "isa".to_owned(),         // isa
-            Token::ForAll => "forall".to_owned(),   // forall
-            Token::If => "if".to_owned(),           // if
-            Token::And => "and".to_owned(),         // and
-            Token::Or => "or".to_owned(),           // or
-            Token::Not => "not".to_owned(),         // not
-            Token::Matches => "matches".to_owned(), // matches
-            Token::Type => "type".to_owned(),       // type
+            Token::Colon => ":".to_owned(),                    // :
+            Token::Comma => ",".to_owned(),                    // ,
+            Token::LB => "[".to_owned(),                       // [
+            Token::RB => "]".to_owned(),                       // ]
+            Token::LP => "(".to_owned(),                       // (
+            Token::RP => ")".to_owned(),                       // )
+            Token::LCB => "{".to_owned(),                      // {
+            Token::RCB => "}".to_owned(),                      // }
+            Token::Dot => ".".to_owned(),                      // .
+            Token::New => "new".to_owned(),                    // new
+            Token::Bang => "!".to_owned(),                     // !
+            Token::Mul => "*".to_owned(),                      // *
+            Token::Div => "/".to_owned(),                      // /
+            Token::Mod => "mod".to_owned(),                    // mod
+            Token::Rem => "rem".to_owned(),                    // rem
+            Token::Add => "+".to_owned(),                      // +
+            Token::Sub => "-".to_owned(),                      // -
+            Token::Eq => "==".to_owned(),                      // ==
+            Token::Neq => "!=".to_owned(),                     // !=
+            Token::Leq => "<=".to_owned(),                     // <=
+            Token::Geq => ">=".to_owned(),                     // >=
+            Token::Lt => "<".to_owned(),                       // <
+            Token::Gt => ">".to_owned(),                       // >
+            Token::Unify => "=".to_owned(),                    // =
+            Token::Assign => ":=".to_owned(),                  // :=
+            Token::Pipe => "|".to_owned(),                     // |
+            Token::SemiColon => ";".to_owned(),                // ;
+            Token::Query => "?=".to_owned(),                   // ?=
+            Token::In => "in".to_owned(),                      // in
+            Token::Cut => "cut".to_owned(),                    // cut
+            Token::Debug => "debug".to_owned(),                // debug
+            Token::Print => "print".to_owned(),                // print
+            Token::Isa => "isa".to_owned(),                    // isa
+            Token::ForAll => "forall".to_owned(),              // forall
+            Token::If => "if".to_owned(),                      // if
+            Token::And => "and".to_owned(),                    // and
+            Token::Or => "or".to_owned(),                      // or
+            Token::Not => "not".to_owned(),                    // not
+            Token::Matches => "matches".to_owned(),            // matches
+            Token::MatchesRegex => "matches_regex".to_owned(), // matches_regex
+            Token::Type => "type".to_owned(),                  // type
         }
     }
 }
@@ -221,28 +272,7 @@ impl<'input> Lexer<'input> {
             }
         }
 
-        let token = match self.buf.as_ref() {
-            "true" => Token::Boolean(true),
-            "false" => Token::Boolean(false),
-            "inf" => Token::Float(f64::INFINITY),
-            "nan" => Token::Float(f64::NAN),
-            "new" => Token::New,
-            "in" => Token::In,
-            "cut" => Token::Cut,
-            "debug" => Token::Debug,
-            "print" => Token::Print,
-            "isa" => Token::Isa,
-            "forall" => Token::ForAll,
-            "if" => Token::If,
-            "and" => Token::And,
-            "or" => Token::Or,
-            "not" => Token::Not,
-            "matches" => Token::Matches,
-            "type" => Token::Type,
-            "mod" => Token::Mod,
-            "rem" => Token::Rem,
-            _ => Token::Symbol(Symbol::new(&self.buf)),
-        };
+        let token = keyword(&self.buf).unwrap_or_else(|| Token::Symbol(Symbol::new(&self.buf)));
         Some(Ok((start, token, last + 1)))
     }
 
@@ -373,6 +403,47 @@ impl<'input> Lexer<'input> {
             }
         }
 
+        if !parse_as_float {
+            if let Some(unit) = self.scan_suffix(Duration::is_unit) {
+                let end = last + unit.len() + 1;
+                return match i64::from_str(&self.buf)
+                    .ok()
+                    .and_then(|n| Duration::from_unit(n, &unit))
+                {
+                    Some(duration) => Some(Ok((start, Token::Duration(duration), end))),
+                    None => Some(Err(ParseErrorKind::DurationOverflow {
+                        token: format!("{}{}", self.buf, unit),
+                        loc: start,
+                    })),
+                };
+            }
+        }
+
+        #[cfg(feature = "bignum")]
+        if let Some(suffix) = self.scan_suffix(|s| s == "M" || (s == "N" && !parse_as_float)) {
+            use crate::numerics::{BigDecimal, BigInt};
+            let number = if suffix == "M" {
+                BigDecimal::from_str(&self.buf).ok().map(Numeric::from)
+            } else {
+                BigInt::from_str(&self.buf).ok().map(Numeric::from)
+            };
+            return match number {
+                Some(n) => Some(Ok((start, Token::Number(n), last + 2))),
+                None => Some(Err(ParseErrorKind::InvalidFloat {
+                    token: format!("{}{}", self.buf, suffix),
+                    loc: start,
+                })),
+            };
+        }
+
+        // Integers too big for an `i64` are big integers.
+        #[cfg(feature = "bignum")]
+        if !parse_as_float && i64::from_str(&self.buf).is_err() {
+            if let Ok(n) = crate::numerics::BigInt::from_str(&self.buf) {
+                return Some(Ok((start, Token::Number(Numeric::from(n)), last + 1)));
+            }
+        }
+
         if parse_as_float {
             if let Ok(f) = f64::from_str(&self.buf) {
                 Some(Ok((start, Token::Float(f), last + 1)))
@@ -392,6 +463,51 @@ impl<'input> Lexer<'input> {
         }
     }
 
+    /// Scan the suffix of a number literal, like the unit of the duration `30d`, if the number just
+    /// scanned is followed by one. Nothing is consumed if it isn't.
+    fn scan_suffix(&mut self, is_suffix: impl Fn(&str) -> bool) -> Option<String> {
+        let (mut c, mut chars) = (self.c, self.chars.clone());
+        let mut unit = String::new();
+        while let Some((_, char)) = c {
+            if char.is_alphanumeric() || char == '_' {
+                unit.push(char);
+                c = chars.next();
+            } else {
+                break;
+            }
+        }
+        if is_suffix(&unit) {
+            self.c = c;
+            self.chars = chars;
+            Some(unit)
+        } else {
+            None
+        }
+    }
+
+    /// Scan a timestamp literal like `@2024-01-01T00:00:00Z`.
+    fn scan_timestamp(&mut self, i: usize) -> Option<Spanned<Token, usize, ParseErrorKind>> {
+        let start = i;
+        let mut last = i;
+        self.buf.clear();
+        self.c = self.chars.next();
+        while let Some((i, char)) = self.c {
+            if char.is_ascii_alphanumeric() || matches!(char, '-' | ':' | '.' | '+') {
+                self.push_char(char);
+                last = i;
+            } else {
+                break;
+            }
+        }
+        match Timestamp::parse(&self.buf) {
+            Some(timestamp) => Some(Ok((start, Token::Timestamp(timestamp), last + 1))),
+            None => Some(Err(ParseErrorKind::InvalidTimestamp {
+                token: format!("@{}", self.buf),
+                loc: start,
+            })),
+        }
+    }
+
     /// Scan a one character operator to token.
     #[inline]
     #[allow(clippy::unnecessary_wraps)]
@@ -468,6 +584,7 @@ impl<'input> Iterator for Lexer<'input> {
                 }
                 '"' => self.scan_string(i),
                 '0'..='9' => self.scan_number(i, char),
+                '@' => self.scan_timestamp(i),
                 ':' => self.scan_1c_or_2c_op(i, Token::Colon, '=', Token::Assign),
                 '=' => self.scan_1c_or_2c_op(i, Token::Unify, '=', Token::Eq),
                 '<' => self.scan_1c_or_2c_op(i, Token::Lt, '=', Token::Leq),
@@ -510,6 +627,22 @@ mod tests {
         assert_eq!(loc_to_pos(src, 18), (2, 5));
     }
 
+    #[test]
+    fn test_keywords() {
+        for keyword in KEYWORDS {
+            let mut lexer = Lexer::new(keyword);
+            let token = lexer.next();
+            assert!(
+                matches!(token, Some(Ok((_, ref t, _))) if !matches!(t, Token::Symbol(_))),
+                "{} lexed as {:?}",
+                keyword,
+                token
+            );
+        }
+        let mut lexer = Lexer::new("resource");
+        assert!(matches!(lexer.next(), Some(Ok((_, Token::Symbol(_), _)))));
+    }
+
     #[test]
     fn lex_infinite_loop_bugs() {
         let f = " 123";
@@ -693,4 +826,28 @@ mod tests {
         let mut lexer = Lexer::new(f);
         assert!(matches!(lexer.next(), Some(Ok((_, Token::Float(f), _))) if f == 1.1e-1));
     }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big_numbers() {
+        let mut lexer = Lexer::new("18446744073709551616 19.99M 5N 5Max");
+        assert!(matches!(
+            lexer.next(),
+            Some(Ok((0, Token::Number(Numeric::BigInteger(_)), 20)))
+        ));
+        assert!(matches!(
+            lexer.next(),
+            Some(Ok((21, Token::Number(Numeric::Decimal(_)), 27)))
+        ));
+        assert!(matches!(
+            lexer.next(),
+            Some(Ok((28, Token::Number(Numeric::Integer(5)), 30)))
+        ));
+        // Suffixes are only consumed on their own.
+        assert!(matches!(
+            lexer.next(),
+            Some(Ok((31, Token::Integer(5), 32)))
+        ));
+        assert!(matches!(lexer.next(), Some(Ok((32, Token::Symbol(_), 35)))));
+    }
 }
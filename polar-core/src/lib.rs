@@ -7,6 +7,7 @@ extern crate maplit;
 #[macro_use]
 pub mod macros;
 
+mod aggregate;
 mod bindings;
 mod constants;
 mod counter;
@@ -15,6 +16,7 @@ mod debugger;
 pub mod diagnostic;
 pub mod error;
 pub mod events;
+pub mod explain;
 pub mod filter;
 mod folder;
 mod formatting;
@@ -27,17 +29,22 @@ mod numerics;
 pub mod parser;
 mod partial;
 pub mod polar;
+pub mod policy_test;
 pub mod query;
+pub mod query_options;
+mod regexes;
 pub mod resource_block;
 mod rewrites;
 pub mod rules;
 mod runnable;
 pub mod sources;
+mod tabling;
+pub mod temporal;
 pub mod terms;
 pub mod traces;
 mod validations;
-mod visitor;
+pub mod visitor;
 mod vm;
 pub mod warning;
 
-pub use lexer::loc_to_pos;
+pub use lexer::{loc_to_pos, KEYWORDS};
@@ -7,7 +7,15 @@ use std::mem::discriminant;
 use std::num::FpCategory;
 use std::ops::{Add, Div, Mul, Rem, Sub};
 
-#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[cfg(feature = "bignum")]
+pub use bigdecimal::BigDecimal;
+#[cfg(feature = "bignum")]
+pub use num_bigint::BigInt;
+
+/// Not `Copy`, since the big numbers of the `bignum` feature aren't, and `non_exhaustive`, since
+/// they're only variants with the feature, so that enabling it doesn't break other crates.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub enum Numeric {
     Integer(i64),
 
@@ -16,6 +24,23 @@ pub enum Numeric {
         deserialize_with = "deserialize_float"
     )]
     Float(f64),
+
+    /// An integer outside the range of an `i64`. Integers that overflow are promoted to
+    /// big integers, and big integers that fit are demoted again.
+    #[cfg(feature = "bignum")]
+    #[serde(
+        serialize_with = "bignum::serialize_string",
+        deserialize_with = "bignum::deserialize_string"
+    )]
+    BigInteger(BigInt),
+
+    /// An exact decimal number, like the literal `19.99M`.
+    #[cfg(feature = "bignum")]
+    #[serde(
+        serialize_with = "bignum::serialize_string",
+        deserialize_with = "bignum::deserialize_string"
+    )]
+    Decimal(BigDecimal),
 }
 
 /// Since JSON does not support ±∞ or NaN (RFC 8259 §6),
@@ -86,15 +111,42 @@ where
     deserializer.deserialize_any(FloatVisitor)
 }
 
+/// An arithmetic operation, for [`wide`].
+#[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "bignum"), allow(dead_code))]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Rem,
+}
+
+/// Apply `op` to numbers that aren't both `i64`s and `f64`s, or to `i64`s whose result overflows
+/// one. Without the `bignum` feature, that's always an overflow.
+#[cfg(not(feature = "bignum"))]
+fn wide(_op: Op, _a: Numeric, _b: Numeric) -> Option<Numeric> {
+    None
+}
+
+#[cfg(feature = "bignum")]
+use bignum::wide;
+
 impl Add for Numeric {
     type Output = Option<Self>;
 
     fn add(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Numeric::Integer(a), Numeric::Integer(b)) => a.checked_add(b).map(Numeric::Integer),
+            (Numeric::Integer(a), Numeric::Integer(b)) => match a.checked_add(b) {
+                Some(c) => Some(Numeric::Integer(c)),
+                None => wide(Op::Add, Numeric::Integer(a), Numeric::Integer(b)),
+            },
             (Numeric::Integer(a), Numeric::Float(b)) => Some(Numeric::Float(a as f64 + b)),
             (Numeric::Float(a), Numeric::Integer(b)) => Some(Numeric::Float(a + b as f64)),
             (Numeric::Float(a), Numeric::Float(b)) => Some(Numeric::Float(a + b)),
+            #[cfg(feature = "bignum")]
+            (a, b) => wide(Op::Add, a, b),
         }
     }
 }
@@ -104,10 +156,15 @@ impl Sub for Numeric {
 
     fn sub(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Numeric::Integer(a), Numeric::Integer(b)) => a.checked_sub(b).map(Numeric::Integer),
+            (Numeric::Integer(a), Numeric::Integer(b)) => match a.checked_sub(b) {
+                Some(c) => Some(Numeric::Integer(c)),
+                None => wide(Op::Sub, Numeric::Integer(a), Numeric::Integer(b)),
+            },
             (Numeric::Integer(a), Numeric::Float(b)) => Some(Numeric::Float(a as f64 - b)),
             (Numeric::Float(a), Numeric::Integer(b)) => Some(Numeric::Float(a - b as f64)),
             (Numeric::Float(a), Numeric::Float(b)) => Some(Numeric::Float(a - b)),
+            #[cfg(feature = "bignum")]
+            (a, b) => wide(Op::Sub, a, b),
         }
     }
 }
@@ -120,11 +177,17 @@ impl Numeric {
 
         match (self, modulus) {
             (Numeric::Integer(a), Numeric::Integer(b)) => {
-                a.checked_rem(b).map(|c| (c + b) % b).map(Numeric::Integer)
+                match a.checked_rem(b).and_then(|c| c.checked_add(b)) {
+                    Some(c) => Some(Numeric::Integer(c % b)),
+                    None if b == 0 => None,
+                    None => wide(Op::Mod, Numeric::Integer(a), Numeric::Integer(b)),
+                }
             }
             (Numeric::Integer(a), Numeric::Float(b)) => Some(Numeric::Float(modulo(a as f64, b))),
             (Numeric::Float(a), Numeric::Integer(b)) => Some(Numeric::Float(modulo(a, b as f64))),
             (Numeric::Float(a), Numeric::Float(b)) => Some(Numeric::Float(modulo(a, b))),
+            #[cfg(feature = "bignum")]
+            (a, b) => wide(Op::Mod, a, b),
         }
     }
 }
@@ -134,10 +197,16 @@ impl Rem for Numeric {
 
     fn rem(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Numeric::Integer(a), Numeric::Integer(b)) => a.checked_rem(b).map(Numeric::Integer),
+            (Numeric::Integer(a), Numeric::Integer(b)) => match a.checked_rem(b) {
+                Some(c) => Some(Numeric::Integer(c)),
+                None if b == 0 => None,
+                None => wide(Op::Rem, Numeric::Integer(a), Numeric::Integer(b)),
+            },
             (Numeric::Integer(a), Numeric::Float(b)) => Some(Numeric::Float((a as f64) % b)),
             (Numeric::Float(a), Numeric::Integer(b)) => Some(Numeric::Float(a % (b as f64))),
             (Numeric::Float(a), Numeric::Float(b)) => Some(Numeric::Float(a % b)),
+            #[cfg(feature = "bignum")]
+            (a, b) => wide(Op::Rem, a, b),
         }
     }
 }
@@ -147,10 +216,15 @@ impl Mul for Numeric {
 
     fn mul(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Numeric::Integer(a), Numeric::Integer(b)) => a.checked_mul(b).map(Numeric::Integer),
+            (Numeric::Integer(a), Numeric::Integer(b)) => match a.checked_mul(b) {
+                Some(c) => Some(Numeric::Integer(c)),
+                None => wide(Op::Mul, Numeric::Integer(a), Numeric::Integer(b)),
+            },
             (Numeric::Integer(a), Numeric::Float(b)) => Some(Numeric::Float(a as f64 * b)),
             (Numeric::Float(a), Numeric::Integer(b)) => Some(Numeric::Float(a * b as f64)),
             (Numeric::Float(a), Numeric::Float(b)) => Some(Numeric::Float(a * b)),
+            #[cfg(feature = "bignum")]
+            (a, b) => wide(Op::Mul, a, b),
         }
     }
 }
@@ -164,6 +238,8 @@ impl Div for Numeric {
             (Numeric::Integer(a), Numeric::Float(b)) => Some(Numeric::Float(a as f64 / b)),
             (Numeric::Float(a), Numeric::Integer(b)) => Some(Numeric::Float(a / b as f64)),
             (Numeric::Float(a), Numeric::Float(b)) => Some(Numeric::Float(a / b)),
+            #[cfg(feature = "bignum")]
+            (a, b) => wide(Op::Div, a, b),
         }
     }
 }
@@ -224,6 +300,9 @@ impl Hash for Numeric {
                     }
                 }
             },
+            // Hash big numbers the same as numerically equal integers and floats.
+            #[cfg(feature = "bignum")]
+            Numeric::BigInteger(_) | Numeric::Decimal(_) => return bignum::hash(self, state),
         }
         .hash(state)
     }
@@ -251,11 +330,13 @@ impl PartialOrd for Numeric {
                 i.partial_cmp(&(f as i64))
             }
         };
-        match (*self, *other) {
-            (Self::Integer(left), Self::Integer(right)) => left.partial_cmp(&right),
-            (Self::Integer(i), Self::Float(f)) => partial_cmp(i, f),
-            (Self::Float(f), Self::Integer(i)) => partial_cmp(i, f).map(Ordering::reverse),
-            (Self::Float(left), Self::Float(right)) => left.partial_cmp(&right),
+        match (self, other) {
+            (Self::Integer(left), Self::Integer(right)) => left.partial_cmp(right),
+            (Self::Integer(i), Self::Float(f)) => partial_cmp(*i, *f),
+            (Self::Float(f), Self::Integer(i)) => partial_cmp(*i, *f).map(Ordering::reverse),
+            (Self::Float(left), Self::Float(right)) => left.partial_cmp(right),
+            #[cfg(feature = "bignum")]
+            (left, right) => bignum::partial_cmp(left, right),
         }
     }
 }
@@ -270,6 +351,152 @@ impl From<f64> for Numeric {
         Self::Float(other)
     }
 }
+#[cfg(feature = "bignum")]
+impl From<BigInt> for Numeric {
+    fn from(other: BigInt) -> Self {
+        bignum::demote(other)
+    }
+}
+#[cfg(feature = "bignum")]
+impl From<BigDecimal> for Numeric {
+    fn from(other: BigDecimal) -> Self {
+        Self::Decimal(other)
+    }
+}
+
+/// Arithmetic, comparison, hashing and serialization of big integers and decimals.
+#[cfg(feature = "bignum")]
+mod bignum {
+    use super::*;
+
+    use bigdecimal::{ToPrimitive, Zero};
+    use std::str::FromStr;
+
+    /// Serialize big numbers as strings, so that hosts parsing JSON don't lose precision.
+    pub fn serialize_string<T: fmt::Display, S: Serializer>(
+        n: &T,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        s.collect_str(n)
+    }
+
+    pub fn deserialize_string<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        T::from_str(&s).map_err(de::Error::custom)
+    }
+
+    /// An integer as an `i64` if it fits in one.
+    pub fn demote(i: BigInt) -> Numeric {
+        match i64::try_from(&i) {
+            Ok(i) => Numeric::Integer(i),
+            Err(_) => Numeric::BigInteger(i),
+        }
+    }
+
+    fn to_f64(n: &Numeric) -> f64 {
+        match n {
+            Numeric::Integer(i) => *i as f64,
+            Numeric::Float(f) => *f,
+            Numeric::BigInteger(i) => i.to_f64().unwrap_or(f64::NAN),
+            Numeric::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// The exact value of a finite number.
+    fn to_decimal(n: &Numeric) -> Option<BigDecimal> {
+        match n {
+            Numeric::Integer(i) => Some(BigDecimal::from(*i)),
+            Numeric::Float(f) => BigDecimal::try_from(*f).ok(),
+            Numeric::BigInteger(i) => Some(BigDecimal::from(i.clone())),
+            Numeric::Decimal(d) => Some(d.clone()),
+        }
+    }
+
+    /// Floats are contagious, as they are with integers; otherwise decimals are; otherwise the
+    /// operands are integers, and so is the result.
+    pub fn wide(op: Op, a: Numeric, b: Numeric) -> Option<Numeric> {
+        match (a, b) {
+            (a @ Numeric::Float(_), b) | (a, b @ Numeric::Float(_)) => {
+                let (a, b) = (Numeric::Float(to_f64(&a)), Numeric::Float(to_f64(&b)));
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Mod => a.modulo(b),
+                    Op::Rem => a % b,
+                }
+            }
+            (a @ Numeric::Decimal(_), b) | (a, b @ Numeric::Decimal(_)) => {
+                let (a, b) = (to_decimal(&a)?, to_decimal(&b)?);
+                let c = match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    _ if b.is_zero() => return None,
+                    Op::Div => a / b,
+                    Op::Mod => ((a % &b) + &b) % b,
+                    Op::Rem => a % b,
+                };
+                Some(Numeric::Decimal(c))
+            }
+            (a, b) => {
+                let integer = |n| match n {
+                    Numeric::Integer(i) => BigInt::from(i),
+                    Numeric::BigInteger(i) => i,
+                    _ => unreachable!("floats and decimals are handled above"),
+                };
+                let (a, b) = (integer(a), integer(b));
+                let c = match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    // Dividing integers gives a float.
+                    Op::Div => {
+                        let (a, b) = (demote(a), demote(b));
+                        return Some(Numeric::Float(to_f64(&a) / to_f64(&b)));
+                    }
+                    _ if b.is_zero() => return None,
+                    Op::Mod => ((a % &b) + &b) % b,
+                    Op::Rem => a % b,
+                };
+                Some(demote(c))
+            }
+        }
+    }
+
+    /// Compare numbers exactly, as decimals.
+    pub fn partial_cmp(a: &Numeric, b: &Numeric) -> Option<Ordering> {
+        match (to_decimal(a), to_decimal(b)) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            // One of the numbers is an infinite or NaN float.
+            _ => to_f64(a).partial_cmp(&to_f64(b)),
+        }
+    }
+
+    pub fn hash<H: Hasher>(n: &Numeric, state: &mut H) {
+        let d = to_decimal(n).expect("big numbers are finite");
+        if d.is_integer() {
+            let i = d.with_scale(0).into_bigint_and_exponent().0;
+            if let Ok(i) = i64::try_from(&i) {
+                return Numeric::Integer(i).hash(state);
+            }
+        }
+        // Hash the number like a float if it is one, otherwise by its normalized digits.
+        let f = to_f64(n);
+        if matches!(BigDecimal::try_from(f), Ok(f) if f == d) {
+            Numeric::Float(f).hash(state)
+        } else {
+            discriminant(&Numeric::Decimal(BigDecimal::zero())).hash(state);
+            d.normalized().hash(state)
+        }
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -535,4 +762,59 @@ mod tests {
             _ => panic!("expected a float"),
         });
     }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn big_numbers() {
+        let big = |s: &str| Numeric::from(s.parse::<BigInt>().unwrap());
+        let decimal = |s: &str| Numeric::from(s.parse::<BigDecimal>().unwrap());
+
+        // Overflowing integers are promoted, and demoted again when they fit.
+        let max = Numeric::Integer(i64::MAX);
+        assert_eq!(max.clone() + 1.into(), Some(big("9223372036854775808")));
+        assert!(matches!(
+            (max.clone() + 1.into()).unwrap() - 1.into(),
+            Some(Numeric::Integer(i64::MAX))
+        ));
+        assert!(matches!(
+            Numeric::Integer(i64::MIN).modulo((-1).into()),
+            Some(Numeric::Integer(0))
+        ));
+        assert_eq!(Numeric::Integer(i64::MIN) % 0.into(), None);
+
+        // Mixed comparison is exact.
+        assert!(big("9223372036854775808") > max);
+        assert!(big("9223372036854775808") == Numeric::Float(2_f64.powi(63)));
+        assert!(big("9223372036854775809") > Numeric::Float(2_f64.powi(63)));
+        assert!(big("9223372036854775809") < Numeric::Float(f64::INFINITY));
+        assert!(big("9223372036854775809") != Numeric::Float(f64::NAN));
+        assert!(decimal("0.1") != Numeric::Float(0.1));
+        assert!(decimal("0.5") == Numeric::Float(0.5));
+        assert!(decimal("2.00") == Numeric::Integer(2));
+
+        // Equal numbers hash equally.
+        assert_eq!(hash(&decimal("2.00")), hash(&Numeric::Integer(2)));
+        assert_eq!(hash(&decimal("0.5")), hash(&Numeric::Float(0.5)));
+        assert_eq!(hash(&decimal("1.50")), hash(&decimal("1.5")));
+        assert_ne!(hash(&decimal("0.1")), hash(&Numeric::Float(0.1)));
+        assert_eq!(
+            hash(&big("9223372036854775808")),
+            hash(&Numeric::Float(2_f64.powi(63)))
+        );
+        assert_eq!(
+            hash(&decimal("9223372036854775809")),
+            hash(&big("9223372036854775809"))
+        );
+
+        // Big numbers are serialized as strings, so they round trip precisely.
+        let n = big("-123456789012345678901234567890");
+        let json = to_json(&n).unwrap();
+        assert_eq!(json, r#"{"BigInteger":"-123456789012345678901234567890"}"#);
+        assert_eq!(from_json::<Numeric>(&json).unwrap(), n);
+        let n = decimal("0.100000000000000000000000000001");
+        let json = to_json(&n).unwrap();
+        assert_eq!(json, r#"{"Decimal":"0.100000000000000000000000000001"}"#);
+        assert_eq!(from_json::<Numeric>(&json).unwrap(), n);
+        assert!(from_json::<Numeric>(r#"{"Decimal":"1.5.0"}"#).is_err());
+    }
 }
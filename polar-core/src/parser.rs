@@ -3,8 +3,10 @@ use std::sync::Arc;
 use lalrpop_util::{lalrpop_mod, ParseError};
 
 use super::{
+    aggregate::Aggregate,
     error::{self, PolarResult},
     lexer::{self, Lexer, Token},
+    policy_test::PolicyTest,
     resource_block::Production,
     rules::*,
     sources::Source,
@@ -18,6 +20,34 @@ pub enum ValueOrLogical {
     Either(Term),
 }
 
+/// Check that the arguments of a call are values, except for the goal of a call to an aggregate
+/// `name`, which may also be a logical expression. Whether the goal is a query is checked when
+/// the built-in aggregate is called, since a rule with its name takes precedence. Calls with
+/// kwargs have no `name`.
+fn call_args(
+    name: Option<&Symbol>,
+    args: Vec<(usize, ValueOrLogical)>,
+) -> Result<TermList, error::ParseErrorKind> {
+    let goal = name
+        .and_then(|name| Aggregate::from_name(name, args.len()))
+        .map(|_| 1);
+    args.into_iter()
+        .enumerate()
+        .map(|(i, (loc, arg))| match arg {
+            ValueOrLogical::Logical(term) if Some(i) != goal => {
+                Err(error::ParseErrorKind::WrongValueType {
+                    loc,
+                    term,
+                    expected: "value".to_string(),
+                })
+            }
+            ValueOrLogical::Value(term)
+            | ValueOrLogical::Logical(term)
+            | ValueOrLogical::Either(term) => Ok(term),
+        })
+        .collect()
+}
+
 lalrpop_mod!(
     #[allow(clippy::all, dead_code, unused_imports, unused_mut)]
     polar
@@ -33,6 +63,7 @@ pub enum Line {
         resource: Term,
         productions: Vec<Production>,
     },
+    Test(PolicyTest),
 }
 
 fn lalrpop_error_to_polar_error(
@@ -59,7 +59,7 @@ pub fn invert_operation(Operation { operator, args }: Operation) -> Operation {
         Not => args[0].as_expression().expect("negated expression").clone(),
 
         // preserve the not
-        Isa | In => Operation {
+        Isa | In | MatchesRegex => Operation {
             operator: Not,
             args: vec![term!(Operation { operator, args })],
         },
@@ -162,7 +162,9 @@ impl Operation {
                             | (Value::Number(_), Value::Boolean(_))
                             | (Value::Boolean(_), Value::Number(_))
                             | (Value::Boolean(_), Value::Boolean(_))
-                            | (Value::String(_), Value::String(_)) => {
+                            | (Value::String(_), Value::String(_))
+                            | (Value::Timestamp(_), Value::Timestamp(_))
+                            | (Value::Duration(_), Value::Duration(_)) => {
                                 if compare(o.operator, &left, &right, None).unwrap() {
                                     TRUE
                                 } else {
@@ -1759,6 +1761,28 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_that_aggregate_with_partial_errors() -> TestResult {
+        let p = Polar::new();
+        p.load_str("f(x) if count(y, y in x, n) and n > 1;")?;
+        p.register_constant(sym!("x"), op!(And).into())?;
+        let mut q = p.new_query_from_term(term!(call!("f", [sym!("x")])), false);
+        let error = q.next_event().unwrap_err();
+        assert!(matches!(
+            error.0,
+            ErrorKind::Runtime(RuntimeError::Unsupported { .. }),
+        ));
+
+        // Values with constraints can't be aggregated either.
+        let mut q = p.new_query("collect(y, y > 1, l)", false)?;
+        let error = q.next_event().unwrap_err();
+        assert!(matches!(
+            error.0,
+            ErrorKind::Runtime(RuntimeError::Unsupported { .. }),
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_cut_with_partial() -> TestResult {
         let p = Polar::new();
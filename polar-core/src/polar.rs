@@ -1,4 +1,4 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
 
 use super::data_filtering::{build_filter_plan, FilterPlan, PartialResults, Types};
 use super::diagnostic::Diagnostic;
@@ -7,10 +7,13 @@ use super::filter::Filter;
 use super::kb::*;
 use super::messages::*;
 use super::parser;
+use super::policy_test::{Assertion, PolicyTest};
 use super::query::Query;
+use super::query_options::QueryOptions;
 use super::resource_block::resource_block_from_productions;
 use super::rewrites::*;
 use super::sources::*;
+use super::temporal::Clock;
 use super::terms::*;
 use super::validations::{
     check_ambiguous_precedence, check_no_allow_rule, check_resource_blocks_missing_has_permission,
@@ -21,6 +24,9 @@ pub struct Polar {
     pub kb: Arc<RwLock<KnowledgeBase>>,
     messages: MessageQueue,
     ignore_no_allow_warning: bool,
+    /// Held while the policy is loaded, reloaded or cleared, so that a reload based on the
+    /// loaded sources doesn't undo a concurrent change to them.
+    reload_lock: Mutex<()>,
 }
 
 impl Default for Polar {
@@ -41,16 +47,25 @@ impl Polar {
             kb: Arc::new(RwLock::new(KnowledgeBase::new())),
             messages: MessageQueue::new(),
             ignore_no_allow_warning,
+            reload_lock: Mutex::new(()),
         }
     }
 
     /// Load `sources` into the KB, returning compile-time diagnostics accumulated during the load.
     pub fn diagnostic_load(&self, sources: Vec<Source>) -> Vec<Diagnostic> {
+        let _reload = self.reload_lock.lock().unwrap();
+        let mut kb = self.kb.write().unwrap();
+        self.load_into(&mut kb, sources)
+    }
+
+    /// Load `sources` into `kb`, returning compile-time diagnostics accumulated during the load.
+    fn load_into(&self, kb: &mut KnowledgeBase, sources: Vec<Source>) -> Vec<Diagnostic> {
         // Separate function so that errors returned with `?` are captured.
         fn load_source(source: Source, kb: &mut KnowledgeBase) -> PolarResult<Vec<Diagnostic>> {
             if let Some(ref filename) = source.filename {
                 kb.add_source(filename, &source.src)?;
             }
+            kb.loaded_sources.push(source.clone());
             // TODO(gj): we still bomb out at the first ParseError.
             let mut lines = parser::parse_lines(source)?;
             lines.reverse();
@@ -66,6 +81,7 @@ impl Polar {
                     parser::Line::Query(term) => {
                         kb.inline_queries.push(term);
                     }
+                    parser::Line::Test(test) => kb.tests.push(test),
                     parser::Line::RuleType(rule_type) => {
                         // make sure rule_type doesn't have anything that needs to be rewritten in the head
                         let rule_type = rewrite_rule(rule_type, kb);
@@ -104,11 +120,10 @@ impl Polar {
             Ok(diagnostics)
         }
 
-        let mut kb = self.kb.write().unwrap();
         let mut diagnostics = vec![];
 
         for source in sources {
-            match load_source(source, &mut kb) {
+            match load_source(source, kb) {
                 Ok(mut ds) => diagnostics.append(&mut ds),
                 Err(e) => diagnostics.push(Diagnostic::Error(e)),
             }
@@ -146,13 +161,13 @@ impl Polar {
 
         // Perform validation checks against the whole policy
         if !self.ignore_no_allow_warning {
-            if let Some(w) = check_no_allow_rule(&kb) {
+            if let Some(w) = check_no_allow_rule(kb) {
                 diagnostics.push(w)
             }
         }
 
         // Check for has_permission calls alongside resource block definitions
-        if let Some(w) = check_resource_blocks_missing_has_permission(&kb) {
+        if let Some(w) = check_resource_blocks_missing_has_permission(kb) {
             diagnostics.push(Diagnostic::Warning(w.into()))
         };
 
@@ -161,14 +176,80 @@ impl Polar {
 
     /// Load `Source`s into the KB.
     pub fn load(&self, sources: Vec<Source>) -> PolarResult<()> {
-        if let Ok(kb) = self.kb.read() {
-            if kb.has_rules() {
-                return Err(RuntimeError::MultipleLoadError.into());
-            }
+        let _reload = self.reload_lock.lock().unwrap();
+        let mut kb = self.kb.write().unwrap();
+        if kb.has_rules() {
+            return Err(RuntimeError::MultipleLoadError.into());
+        }
+
+        let diagnostics = self.load_into(&mut kb, sources);
+        if let Err(e) = self.check_diagnostics(diagnostics) {
+            // If we've encountered any errors, clear the KB.
+            kb.clear_rules();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Replace the loaded policy with `sources`, atomically: they are loaded and validated in a
+    /// separate KB, which is swapped in only if there were no errors. Until then, queries use
+    /// the previous policy, and on error it stays loaded.
+    pub fn reload(&self, sources: Vec<Source>) -> PolarResult<()> {
+        let mut staged = self.stage();
+        staged.reload(sources)?;
+        staged.commit();
+        Ok(())
+    }
+
+    /// Like [`Polar::reload`], but returns the compile-time diagnostics accumulated during the
+    /// load. The new policy is swapped in only if none of them are errors.
+    pub fn diagnostic_reload(&self, sources: Vec<Source>) -> Vec<Diagnostic> {
+        let mut staged = self.stage();
+        let diagnostics = staged.diagnostic_reload(sources);
+        if !diagnostics.iter().any(Diagnostic::is_error) {
+            staged.commit();
+        }
+        diagnostics
+    }
+
+    /// Replace the loaded source with the same filename as `source`, or add `source` if there
+    /// is none, reloading the policy with [`Polar::reload`].
+    pub fn replace_source(&self, source: Source) -> PolarResult<()> {
+        let mut staged = self.stage();
+        staged.replace_source(source)?;
+        staged.commit();
+        Ok(())
+    }
+
+    /// Remove the loaded source named `filename`, reloading the policy with [`Polar::reload`].
+    pub fn remove_source(&self, filename: &str) -> PolarResult<()> {
+        let mut staged = self.stage();
+        staged.remove_source(filename)?;
+        staged.commit();
+        Ok(())
+    }
+
+    /// Start reloading the policy. Until the returned [`StagedPolicy`] is committed or dropped,
+    /// other loads and reloads wait, but queries use the loaded policy.
+    pub fn stage(&self) -> StagedPolicy<'_> {
+        let reload = self.reload_lock.lock().unwrap();
+        let kb = self.kb.read().unwrap().without_rules();
+        StagedPolicy {
+            polar: self,
+            kb: Arc::new(RwLock::new(kb)),
+            _reload: reload,
         }
+    }
 
+    /// The sources of the loaded policy, in the order they were loaded.
+    pub fn loaded_sources(&self) -> Vec<Source> {
+        self.kb.read().unwrap().loaded_sources().to_vec()
+    }
+
+    /// Queue the warnings in `diagnostics` and return the first error, if any.
+    fn check_diagnostics(&self, diagnostics: Vec<Diagnostic>) -> PolarResult<()> {
         let (mut errors, mut warnings) = (vec![], vec![]);
-        for diagnostic in self.diagnostic_load(sources) {
+        for diagnostic in diagnostics {
             match diagnostic {
                 Diagnostic::Error(e) => errors.push(e),
                 Diagnostic::Warning(w) => warnings.push(w),
@@ -178,12 +259,10 @@ impl Polar {
         self.messages
             .extend(warnings.into_iter().map(Message::warning));
 
-        if let Some(e) = errors.into_iter().next() {
-            // If we've encountered any errors, clear the KB.
-            self.clear_rules();
-            return Err(e);
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
         }
-        Ok(())
     }
 
     // Used in integration tests
@@ -193,6 +272,7 @@ impl Polar {
 
     /// Clear rules from the knowledge base
     pub fn clear_rules(&self) {
+        let _reload = self.reload_lock.lock().unwrap();
         let mut kb = self.kb.write().unwrap();
         kb.clear_rules();
     }
@@ -202,28 +282,100 @@ impl Polar {
         term.map(|t| self.new_query_from_term(t, trace))
     }
 
-    pub fn new_query(&self, src: &str, trace: bool) -> PolarResult<Query> {
-        parser::parse_query(src).map(|term| self.new_query_from_term(term, trace))
-    }
-
-    pub fn new_query_from_term(&self, mut term: Term, trace: bool) -> Query {
+    /// A query for `term` against the policy in `kb`.
+    fn query_kb(
+        &self,
+        kb: &Arc<RwLock<KnowledgeBase>>,
+        mut term: Term,
+        trace: bool,
+        options: &QueryOptions,
+    ) -> Query {
         use crate::vm::{Goal, PolarVirtualMachine};
         {
-            let kb = self.kb.read().unwrap();
+            let kb = kb.read().unwrap();
             term = rewrite_term(term, &kb);
         }
         let query = Goal::Query { term: term.clone() };
-        let vm =
-            PolarVirtualMachine::new(self.kb.clone(), trace, vec![query], self.messages.clone());
+        let mut vm =
+            PolarVirtualMachine::new(kb.clone(), trace, vec![query], self.messages.clone());
+        vm.set_options(options);
         Query::new(vm, term)
     }
 
+    /// The policy tests that have been loaded. See [`crate::policy_test`].
+    pub fn tests(&self) -> Vec<PolicyTest> {
+        self.kb.read().unwrap().tests.clone()
+    }
+
+    /// A query for `assertion` of `test`, which passes if it has results and `assertion.expected`
+    /// is set, or has none and it isn't.
+    pub fn new_test_query(&self, test: &PolicyTest, assertion: &Assertion) -> Query {
+        self.new_query_from_term(test.query(assertion), false)
+    }
+
+    pub fn new_query(&self, src: &str, trace: bool) -> PolarResult<Query> {
+        parser::parse_query(src).map(|term| self.new_query_from_term(term, trace))
+    }
+
+    pub fn new_query_from_term(&self, term: Term, trace: bool) -> Query {
+        self.new_query_from_term_with_options(term, trace, &QueryOptions::default())
+    }
+
+    /// Like [`Polar::new_query`], but with the limits set in `options`.
+    pub fn new_query_with_options(
+        &self,
+        src: &str,
+        trace: bool,
+        options: &QueryOptions,
+    ) -> PolarResult<Query> {
+        parser::parse_query(src)
+            .map(|term| self.new_query_from_term_with_options(term, trace, options))
+    }
+
+    pub fn new_query_from_term_with_options(
+        &self,
+        term: Term,
+        trace: bool,
+        options: &QueryOptions,
+    ) -> Query {
+        self.query_kb(&self.kb, term, trace, options)
+    }
+
+    /// Like [`Polar::new_query`], but records an explanation of the query's result.
+    /// See [`Query::explanation`].
+    pub fn new_explained_query(&self, src: &str) -> PolarResult<Query> {
+        parser::parse_query(src).map(|term| self.new_explained_query_from_term(term))
+    }
+
+    pub fn new_explained_query_from_term(&self, term: Term) -> Query {
+        let mut query = self.new_query_from_term(term, true);
+        query.explain();
+        query
+    }
+
     // @TODO: Direct load_rules endpoint.
 
     pub fn get_external_id(&self) -> u64 {
         self.kb.read().unwrap().new_id()
     }
 
+    /// Read the time for `now` from `clock` instead of the system clock, e.g. a
+    /// [`FixedClock`](crate::temporal::FixedClock) in tests.
+    pub fn set_clock(&self, clock: Arc<dyn Clock>) {
+        self.kb.write().unwrap().set_clock(clock)
+    }
+
+    /// Whether a rule of the loaded policy calls `now`. See [`KnowledgeBase::calls_now`].
+    pub fn calls_now(&self) -> bool {
+        self.kb.read().unwrap().calls_now()
+    }
+
+    /// Changes whenever the loaded policy, registered constants, MROs or clock change. See
+    /// [`KnowledgeBase::generation`].
+    pub fn generation(&self) -> u64 {
+        self.kb.read().unwrap().generation()
+    }
+
     pub fn register_constant(&self, name: Symbol, value: Term) -> PolarResult<()> {
         self.kb.write().unwrap().register_constant(name, value)
     }
@@ -238,6 +390,12 @@ impl Polar {
         self.kb.write().unwrap().add_mro(name, mro)
     }
 
+    /// Table the answers to calls to the rule `name` during each query. See
+    /// [`KnowledgeBase::table_rule`].
+    pub fn table_rule(&self, name: Symbol) {
+        self.kb.write().unwrap().table_rule(name)
+    }
+
     pub fn next_message(&self) -> Option<Message> {
         self.messages.next()
     }
@@ -270,6 +428,73 @@ impl Polar {
     }
 }
 
+/// A policy being reloaded, loaded into a separate KB with the constants, MROs and tabled rules
+/// of the loaded one. See [`Polar::stage`].
+pub struct StagedPolicy<'polar> {
+    polar: &'polar Polar,
+    kb: Arc<RwLock<KnowledgeBase>>,
+    _reload: MutexGuard<'polar, ()>,
+}
+
+impl StagedPolicy<'_> {
+    /// The sources of the loaded policy, which can't change until the staged one is committed
+    /// or dropped.
+    pub fn loaded_sources(&self) -> Vec<Source> {
+        self.polar.loaded_sources()
+    }
+
+    /// Load `sources`, returning the compile-time diagnostics accumulated during the load.
+    pub fn diagnostic_reload(&mut self, sources: Vec<Source>) -> Vec<Diagnostic> {
+        let mut kb = self.polar.kb.read().unwrap().without_rules();
+        let diagnostics = self.polar.load_into(&mut kb, sources);
+        self.kb = Arc::new(RwLock::new(kb));
+        diagnostics
+    }
+
+    /// Load `sources`, queueing warnings and returning the first error, if any.
+    pub fn reload(&mut self, sources: Vec<Source>) -> PolarResult<()> {
+        let diagnostics = self.diagnostic_reload(sources);
+        self.polar.check_diagnostics(diagnostics)
+    }
+
+    /// Load the sources of the loaded policy with `source` in place of the one with the same
+    /// filename, or after them if there is none.
+    pub fn replace_source(&mut self, source: Source) -> PolarResult<()> {
+        let mut sources = self.polar.loaded_sources();
+        match sources
+            .iter_mut()
+            .find(|loaded| source.filename.is_some() && loaded.filename == source.filename)
+        {
+            Some(loaded) => *loaded = source,
+            None => sources.push(source),
+        }
+        self.reload(sources)
+    }
+
+    /// Load the sources of the loaded policy except the one named `filename`.
+    pub fn remove_source(&mut self, filename: &str) -> PolarResult<()> {
+        let mut sources = self.polar.loaded_sources();
+        sources.retain(|loaded| loaded.filename.as_deref() != Some(filename));
+        self.reload(sources)
+    }
+
+    /// Like [`Polar::next_inline_query`], but against the staged policy.
+    pub fn next_inline_query(&self, trace: bool) -> Option<Query> {
+        let term = { self.kb.write().unwrap().inline_queries.pop() };
+        term.map(|term| {
+            self.polar
+                .query_kb(&self.kb, term, trace, &QueryOptions::default())
+        })
+    }
+
+    /// Swap the staged policy in for the loaded one.
+    pub fn commit(self) {
+        let mut staged = self.kb.write().unwrap();
+        let kb = std::mem::take(&mut *staged);
+        self.polar.kb.write().unwrap().replace_rules(kb);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
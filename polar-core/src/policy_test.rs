@@ -0,0 +1,140 @@
+//! Unit tests for policies, written in Polar alongside the rules they test:
+//!
+//! ```polar
+//! test "members can read but not delete" {
+//!     alice = {name: "alice"};
+//!     repo = Repository{name: "oso", members: ["alice"]};
+//!     assert allow(alice, "read", repo);
+//!     assert_not allow(alice, "delete", repo);
+//! }
+//! ```
+//!
+//! Each assertion is queried on its own, with every fixture of its test bound first. A fixture
+//! whose value is an instance literal is constructed by the host, as if by
+//! `new Repository(name: "oso", members: ["alice"])`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::terms::{Operation, Operator, Term, Value};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Assertion {
+    pub term: Term,
+    /// Whether the assertion expects `term` to have results (`assert`) or not (`assert_not`).
+    pub expected: bool,
+}
+
+/// An item in the body of a test, as parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestItem {
+    Fixture { variable: Term, value: Term },
+    Assertion(Assertion),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyTest {
+    /// The name of the test, a string term that points at the test in its source.
+    pub name: Term,
+    /// Variables and the values they are bound to before each assertion.
+    pub fixtures: Vec<(Term, Term)>,
+    pub assertions: Vec<Assertion>,
+}
+
+impl PolicyTest {
+    pub fn new(name: Term, items: Vec<TestItem>) -> Self {
+        let mut fixtures = vec![];
+        let mut assertions = vec![];
+        for item in items {
+            match item {
+                TestItem::Fixture { variable, value } => fixtures.push((variable, value)),
+                TestItem::Assertion(assertion) => assertions.push(assertion),
+            }
+        }
+        Self {
+            name,
+            fixtures,
+            assertions,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_string().unwrap_or_default()
+    }
+
+    /// The query for `assertion`: its term, after unifying each fixture variable with its value.
+    /// Keeps the source location of the assertion.
+    pub fn query(&self, assertion: &Assertion) -> Term {
+        let mut args = self
+            .fixtures
+            .iter()
+            .map(|(variable, value)| {
+                variable.clone_with_value(Value::Expression(Operation {
+                    operator: Operator::Unify,
+                    args: vec![variable.clone(), value.clone()],
+                }))
+            })
+            .collect::<Vec<_>>();
+        args.push(assertion.term.clone());
+        assertion
+            .term
+            .clone_with_value(Value::Expression(Operation {
+                operator: Operator::And,
+                args,
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::ParseErrorKind;
+    use crate::parser::{parse_lines, Line};
+    use crate::sources::Source;
+
+    #[track_caller]
+    fn parse_test(src: &str) -> PolicyTest {
+        match parse_lines(Source::new(src)).unwrap().pop() {
+            Some(Line::Test(test)) => test,
+            line => panic!("expected a test, got {:?}", line),
+        }
+    }
+
+    #[test]
+    fn test_policy_test_query() {
+        let test = parse_test(
+            r#"test "members can read" {
+                 alice = {name: "alice"};
+                 repo = Repository{name: "oso"};
+                 assert allow(alice, "read", repo);
+                 assert_not allow(alice, "delete", repo);
+               }"#,
+        );
+        assert_eq!(test.name(), "members can read");
+        assert_eq!(test.assertions.len(), 2);
+        assert!(test.assertions[0].expected);
+        assert!(!test.assertions[1].expected);
+        assert_eq!(
+            test.query(&test.assertions[1]).to_string(),
+            r#"alice = {name: "alice"} and repo = new Repository(name: "oso") and allow(alice, "delete", repo)"#
+        );
+    }
+
+    #[test]
+    fn test_policy_test_keywords() {
+        // The keywords aren't reserved.
+        let lines = parse_lines(Source::new("test(assert) if assert_not(assert);")).unwrap();
+        assert!(matches!(lines[..], [Line::Rule(_)]));
+
+        let error = parse_lines(Source::new(r#"tset "typo" {}"#)).unwrap_err();
+        assert!(matches!(
+            error.unwrap_parse(),
+            ParseErrorKind::UnrecognizedToken { token, loc: 0 } if token == "tset"
+        ));
+        let error = parse_lines(Source::new(r#"test "typo" { asert f(); }"#)).unwrap_err();
+        assert!(matches!(
+            error.unwrap_parse(),
+            ParseErrorKind::UnrecognizedToken { token, loc: 14 } if token == "asert"
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 use super::error::PolarResult;
 use super::events::*;
+use super::explain::Explanation;
 use super::messages::*;
+use super::query_options::CancellationHandle;
 use super::runnable::Runnable;
 use super::terms::*;
 use super::vm::*;
@@ -22,6 +24,15 @@ impl Query {
         }
     }
 
+    pub(crate) fn explain(&mut self) {
+        self.vm.explain();
+    }
+
+    /// A handle to cancel the query with, from any thread.
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        self.vm.cancellation_handle()
+    }
+
     #[cfg(target_arch = "wasm32")]
     pub fn set_logging_options(&mut self, rust_log: Option<String>, polar_log: Option<String>) {
         self.vm.set_logging_options(rust_log, polar_log);
@@ -35,33 +46,27 @@ impl Query {
     /// 4. When Runnable B emits a Done event, pop Runnable B off the stack and return its result as
     ///    an answer to Runnable A.
     pub fn next_event(&mut self) -> PolarResult<QueryEvent> {
-        let mut counter = self.vm.id_counter();
-        let qe = match self.top_runnable().run(Some(&mut counter)) {
-            Ok(e) => e,
-            Err(e) => self.top_runnable().handle_error(e)?,
-        };
-        self.recv_event(qe)
-    }
-
-    fn recv_event(&mut self, qe: QueryEvent) -> PolarResult<QueryEvent> {
-        match qe {
-            QueryEvent::None => self.next_event(),
-            QueryEvent::Run { runnable, call_id } => {
-                self.push_runnable(runnable, call_id);
-                self.next_event()
-            }
-            QueryEvent::Done { result } => {
-                if let Some((_, result_call_id)) = self.pop_runnable() {
-                    self.top_runnable()
-                        .external_question_result(result_call_id, result)?;
-                    self.next_event()
-                } else {
-                    // VM is done.
-                    assert!(self.runnable_stack.is_empty());
-                    Ok(QueryEvent::Done { result })
+        loop {
+            let mut counter = self.vm.id_counter();
+            let qe = match self.top_runnable().run(Some(&mut counter)) {
+                Ok(e) => e,
+                Err(e) => self.top_runnable().handle_error(e)?,
+            };
+            match qe {
+                QueryEvent::None => (),
+                QueryEvent::Run { runnable, call_id } => self.push_runnable(runnable, call_id),
+                QueryEvent::Done { result } => {
+                    if let Some((_, result_call_id)) = self.pop_runnable() {
+                        self.top_runnable()
+                            .external_question_result(result_call_id, result)?;
+                    } else {
+                        // VM is done.
+                        assert!(self.runnable_stack.is_empty());
+                        return Ok(QueryEvent::Done { result });
+                    }
                 }
+                ev => return Ok(ev),
             }
-            ev => Ok(ev),
         }
     }
 
@@ -108,6 +113,15 @@ impl Query {
     pub fn bind(&mut self, name: Symbol, value: Term) -> PolarResult<()> {
         self.vm.bind(&name, value)
     }
+
+    /// Explain the first result of the query, or why it has none.
+    ///
+    /// Returns `None` unless the query was created with
+    /// [`Polar::new_explained_query`](crate::polar::Polar::new_explained_query). Call this after
+    /// the query has produced a result or is done; before that, it explains a failure.
+    pub fn explanation(&self) -> Option<Explanation> {
+        self.vm.explanation(&self.term)
+    }
 }
 
 // Query as an iterator returns `None` after the first time `Done` is seen
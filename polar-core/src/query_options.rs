@@ -0,0 +1,37 @@
+//! Limits on a query, and cancelling a query while it runs.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// Limits on a query. A limit that isn't set has its default: the timeout set by the
+/// `POLAR_TIMEOUT_MS` environment variable or 30 seconds, no limit on the number of goals, and
+/// 10,000 choice points and goals on the goal stack.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryOptions {
+    /// Timeout in milliseconds. `0` disables the timeout.
+    pub timeout_ms: Option<u64>,
+    /// Maximum number of goals the query may execute.
+    pub max_goals: Option<u64>,
+    /// Maximum number of choice points, i.e., alternatives waiting to be tried.
+    pub max_choices: Option<usize>,
+    /// Maximum number of goals on the goal stack.
+    pub max_stack_depth: Option<usize>,
+}
+
+/// Cancels a query, from any thread. Once cancelled, the query's next event is a
+/// [`RuntimeError::QueryCancelled`](crate::error::RuntimeError::QueryCancelled) error.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationHandle(Arc<AtomicBool>);
+
+impl CancellationHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,78 @@
+//! Regular expressions for the `matches_regex` operator.
+//!
+//! Patterns use the syntax of the [`regex`] crate, and match anywhere in a string unless they are
+//! anchored. The string literal patterns of a policy are compiled once, when its rules are added
+//! to the knowledge base, and those of a data filter once each time it's evaluated in memory;
+//! other patterns are compiled each time they are matched.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::terms::{Operation, Operator, Term, Value};
+use crate::visitor::{walk_operation, Visitor};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Regexes(HashMap<String, Regex>);
+
+impl Regexes {
+    /// Compile the valid string literal patterns in `term`.
+    pub(crate) fn add_literals(&mut self, term: &Term) {
+        for pattern in literal_patterns(term) {
+            if let Value::String(pattern) = pattern.value() {
+                self.add(pattern);
+            }
+        }
+    }
+
+    /// Compile `pattern`, if it's valid.
+    pub(crate) fn add(&mut self, pattern: &str) {
+        if !self.0.contains_key(pattern) {
+            if let Ok(regex) = Regex::new(pattern) {
+                self.0.insert(pattern.to_owned(), regex);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// The compiled regex for `pattern`.
+    pub(crate) fn get(&self, pattern: &str) -> Result<Cow<'_, Regex>, regex::Error> {
+        match self.0.get(pattern) {
+            Some(regex) => Ok(Cow::Borrowed(regex)),
+            None => Regex::new(pattern).map(Cow::Owned),
+        }
+    }
+}
+
+/// The string literals matched against with `matches_regex` in `term`.
+pub(crate) fn literal_patterns(term: &Term) -> Vec<Term> {
+    #[derive(Default)]
+    struct PatternVisitor {
+        patterns: Vec<Term>,
+    }
+
+    impl Visitor for PatternVisitor {
+        fn visit_operation(&mut self, o: &Operation) {
+            if let Operation {
+                operator: Operator::MatchesRegex,
+                args,
+            } = o
+            {
+                if let Some(pattern) = args.get(1) {
+                    if let Value::String(_) = pattern.value() {
+                        self.patterns.push(pattern.clone());
+                    }
+                }
+            }
+            walk_operation(self, o)
+        }
+    }
+
+    let mut visitor = PatternVisitor::default();
+    visitor.visit_term(term);
+    visitor.patterns
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use super::aggregate::{self, Aggregate};
 use super::folder::*;
 use super::kb::*;
 use super::rules::*;
@@ -73,6 +74,61 @@ impl<'kb> Rewriter<'kb> {
             _ => false,
         }
     }
+
+    /// Replace `now()` operands of `o` with a temp, and push a call to the built-in `now(t)`
+    /// that binds the temp.
+    fn rewrite_now(&mut self, mut o: Operation) -> Operation {
+        if !has_value_args(o.operator) {
+            return o;
+        }
+        for arg in o.args.iter_mut() {
+            if let Value::Call(Call {
+                name,
+                args,
+                kwargs: None,
+            }) = arg.value()
+            {
+                if name.0 != "now" || !args.is_empty() {
+                    continue;
+                }
+                let temp = Term::from(Value::Variable(self.kb.gensym("now")));
+                let call = Value::Call(Call {
+                    name: sym!("now"),
+                    args: vec![temp.clone()],
+                    kwargs: None,
+                });
+                self.stack
+                    .last_mut()
+                    .unwrap()
+                    .push(arg.clone_with_value(call));
+                *arg = arg.clone_with_value(temp.value().clone());
+            }
+        }
+        o
+    }
+}
+
+/// Whether the arguments of `operator` are values, in which `now()` is the current time.
+fn has_value_args(operator: Operator) -> bool {
+    use Operator::*;
+    matches!(
+        operator,
+        Add | Sub
+            | Mul
+            | Div
+            | Mod
+            | Rem
+            | Eq
+            | Neq
+            | Lt
+            | Leq
+            | Gt
+            | Geq
+            | Unify
+            | Assign
+            | In
+            | MatchesRegex
+    )
 }
 
 fn temp_name(o: &Operator) -> &'static str {
@@ -133,7 +189,7 @@ impl<'kb> Folder for Rewriter<'kb> {
             }
             Value::Expression(o) if self.needs_rewrite(o) => {
                 // Rewrite sub-expressions, then push a temp onto the args.
-                let mut new = fold_operation(o.clone(), self);
+                let mut new = fold_operation(self.rewrite_now(o.clone()), self);
                 let temp = Value::Variable(self.kb.gensym(temp_name(&o.operator)));
                 new.args.push(Term::from(temp.clone()));
 
@@ -209,7 +265,37 @@ impl<'kb> Folder for Rewriter<'kb> {
                 },
             },
 
-            _ => fold_operation(o, self),
+            _ => {
+                let o = self.rewrite_now(o);
+                fold_operation(o, self)
+            }
+        }
+    }
+
+    /// The goal of an aggregate is solved on its own, so temporary variables from inside it,
+    /// and from the value aggregated over its solutions, are inserted into the goal. A call with
+    /// a value instead of a goal is to a rule, and rewritten like any other call.
+    fn fold_call(&mut self, c: Call) -> Call {
+        if Aggregate::from_call(&c).is_none() || aggregate::is_value(&c.args[1]) {
+            return fold_call(c, self);
+        }
+        let Call { name, args, .. } = c;
+        let [template, goal, result]: [Term; 3] = args.try_into().unwrap();
+
+        self.stack.push(vec![]);
+        let goal = self.fold_term(goal);
+        let goal_rws = self.stack.pop().unwrap();
+
+        self.stack.push(vec![]);
+        let template = self.fold_term(template);
+        let template_rws = self.stack.pop().unwrap();
+
+        let goal = goal_rws.into_iter().rfold(goal, and_op_);
+        let goal = template_rws.into_iter().fold(goal, and_);
+        Call {
+            name: self.fold_name(name),
+            args: vec![template, goal, self.fold_term(result)],
+            kwargs: None,
         }
     }
 
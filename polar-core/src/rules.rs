@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
@@ -44,7 +44,7 @@ impl Rule {
         self.params.iter().all(|p| p.is_ground())
     }
 
-    pub(crate) fn parsed_context(&self) -> Option<&Context> {
+    pub fn parsed_context(&self) -> Option<&Context> {
         if let SourceInfo::Parser(context) = &self.source_info {
             Some(context)
         } else {
@@ -119,6 +119,10 @@ impl RuleTypes {
         self.0.get(name)
     }
 
+    pub fn names(&self) -> impl Iterator<Item = &Symbol> {
+        self.0.keys()
+    }
+
     pub fn add(&mut self, rule_type: Rule) {
         let name = rule_type.name.clone();
         // get rule types with this rule name
@@ -144,64 +148,162 @@ pub type Rules = Vec<Arc<Rule>>;
 
 type RuleSet = BTreeSet<u64>;
 
+/// What a rule parameter can match, as far as the index is concerned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ParamKey {
+    /// A ground parameter matches only an equal argument.
+    Value(Value),
+    /// A parameter specialized on a class matches only instances of that class.
+    Class(Symbol),
+    /// Any other parameter may match any argument.
+    Any,
+}
+
+impl ParamKey {
+    fn new(param: &Parameter) -> Self {
+        if param.is_ground() {
+            return Self::Value(param.parameter.value().clone());
+        }
+        match &param.specializer {
+            Some(specializer)
+                if specializer.is_actor_union() || specializer.is_resource_union() =>
+            {
+                Self::Any
+            }
+            Some(specializer) => match specializer.value() {
+                Value::Pattern(Pattern::Instance(InstanceLiteral { tag, .. })) => {
+                    Self::Class(tag.clone())
+                }
+                _ => Self::Any,
+            },
+            None => Self::Any,
+        }
+    }
+
+    fn may_match(&self, arg: &Argument) -> bool {
+        match (self, arg.value) {
+            (Self::Value(param), Some(arg)) => param == arg,
+            (Self::Class(tag), _) => arg.classes.as_ref().is_none_or(|c| c.contains(tag)),
+            _ => true,
+        }
+    }
+}
+
+/// What the index knows about an argument.
+struct Argument<'a> {
+    /// The argument's value, if it is ground.
+    value: Option<&'a Value>,
+    /// Every class the argument is an instance of, if known.
+    classes: Option<HashSet<Symbol>>,
+}
+
+/// The rules of one arity, indexed by the parameter at one position.
 #[derive(Clone, Default, Debug)]
-struct RuleIndex {
+struct ParamIndex {
+    values: HashMap<Value, RuleSet>,
+    /// Every rule in `values`.
+    ground: RuleSet,
+    classes: HashMap<Symbol, RuleSet>,
+    any: RuleSet,
+}
+
+impl ParamIndex {
+    fn insert(&mut self, rule_id: u64, key: &ParamKey) {
+        match key {
+            ParamKey::Value(value) => {
+                self.ground.insert(rule_id);
+                self.values.entry(value.clone()).or_default()
+            }
+            ParamKey::Class(tag) => self.classes.entry(tag.clone()).or_default(),
+            ParamKey::Any => &mut self.any,
+        }
+        .insert(rule_id);
+    }
+
+    /// Disjoint sets of rules whose union holds every rule that may match `arg`,
+    /// or `None` if every rule may match it.
+    fn candidates(&self, arg: &Argument) -> Option<Vec<&RuleSet>> {
+        let mut sets = vec![&self.any];
+        match (arg.value, &arg.classes) {
+            (None, None) => return None,
+            (Some(value), _) => sets.extend(self.values.get(value)),
+            // External instances may be equal to ground values, which the host decides.
+            (None, Some(_)) => sets.push(&self.ground),
+        }
+        match &arg.classes {
+            Some(classes) => sets.extend(classes.iter().filter_map(|tag| self.classes.get(tag))),
+            None => sets.extend(self.classes.values()),
+        }
+        Some(sets)
+    }
+}
+
+/// The rules of one arity, indexed by each of their parameters.
+#[derive(Clone, Default, Debug)]
+struct ArityIndex {
     rules: RuleSet,
-    index: HashMap<Option<Value>, RuleIndex>,
+    params: Vec<ParamIndex>,
+}
+
+#[derive(Clone, Default, Debug)]
+struct RuleIndex {
+    arities: HashMap<usize, ArityIndex>,
+    keys: HashMap<u64, Vec<ParamKey>>,
 }
 
 impl RuleIndex {
-    pub fn index_rule(&mut self, rule_id: u64, params: &[Parameter], i: usize) {
-        if i < params.len() {
-            self.index
-                .entry({
-                    if params[i].is_ground() {
-                        Some(params[i].parameter.value().clone())
-                    } else {
-                        None
-                    }
-                })
-                .or_default()
-                .index_rule(rule_id, params, i + 1);
-        } else {
-            self.rules.insert(rule_id);
+    pub fn index_rule(&mut self, rule_id: u64, params: &[Parameter]) {
+        let keys = params.iter().map(ParamKey::new).collect::<Vec<_>>();
+        let index = self
+            .arities
+            .entry(params.len())
+            .or_insert_with(|| ArityIndex {
+                rules: RuleSet::new(),
+                params: vec![ParamIndex::default(); params.len()],
+            });
+        index.rules.insert(rule_id);
+        for (param, key) in index.params.iter_mut().zip(keys.iter()) {
+            param.insert(rule_id, key);
         }
+        self.keys.insert(rule_id, keys);
     }
 
-    #[allow(clippy::comparison_chain)]
-    pub fn get_applicable_rules(&self, args: &[Term], i: usize) -> RuleSet {
-        if i < args.len() {
-            // Check this argument and recurse on the rest.
-            let filter_next_args =
-                |index: &RuleIndex| -> RuleSet { index.get_applicable_rules(args, i + 1) };
-            let arg = args[i].value();
-            if arg.is_ground() {
-                // Check the index for a ground argument.
-                let mut ruleset = self
-                    .index
-                    .get(&Some(arg.clone()))
-                    .map(filter_next_args)
-                    .unwrap_or_default();
-
-                // Extend for a variable parameter.
-                if let Some(index) = self.index.get(&None) {
-                    ruleset.extend(filter_next_args(index));
+    /// Select the rules that may match `args`. Candidates come from the argument that
+    /// narrows them down the most, and are then checked against the other arguments.
+    fn get_applicable_rules(&self, args: &[Argument]) -> RuleSet {
+        let index = match self.arities.get(&args.len()) {
+            Some(index) => index,
+            None => return RuleSet::new(),
+        };
+
+        let mut smallest: Option<(usize, Vec<&RuleSet>)> = None;
+        for (param, arg) in index.params.iter().zip(args) {
+            if let Some(sets) = param.candidates(arg) {
+                let size = sets.iter().map(|set| set.len()).sum();
+                if smallest
+                    .as_ref()
+                    .is_none_or(|(smallest, _)| size < *smallest)
+                {
+                    smallest = Some((size, sets));
                 }
-                ruleset
-            } else {
-                // Accumulate all indexed arguments.
-                self.index.values().fold(
-                    RuleSet::default(),
-                    |mut result: RuleSet, index: &RuleIndex| {
-                        result.extend(filter_next_args(index));
-                        result
-                    },
-                )
             }
-        } else {
-            // No more arguments.
-            self.rules.clone()
         }
+        let candidates = match smallest {
+            Some((_, sets)) => sets,
+            None => return index.rules.clone(),
+        };
+
+        candidates
+            .into_iter()
+            .flatten()
+            .filter(|id| {
+                self.keys[id]
+                    .iter()
+                    .zip(args)
+                    .all(|(key, arg)| key.may_match(arg))
+            })
+            .copied()
+            .collect()
     }
 }
 
@@ -236,19 +338,44 @@ impl GenericRule {
             self.rules.insert(rule_id, rule.clone()).is_none(),
             "Rule id already used."
         );
-        self.index.index_rule(rule_id, &rule.params[..], 0);
+        self.index.index_rule(rule_id, &rule.params[..]);
     }
 
     #[allow(clippy::ptr_arg)]
     pub fn get_applicable_rules(&self, args: &TermList) -> Rules {
+        self.get_applicable_rules_with_classes(args, |_| None)
+    }
+
+    /// Like [`GenericRule::get_applicable_rules`], but `classes` returns every class that an
+    /// argument is known to be an instance of, so that rules specialized on other classes
+    /// can be skipped. It returns `None` when the classes of an argument are unknown.
+    #[allow(clippy::ptr_arg)]
+    pub fn get_applicable_rules_with_classes<F>(&self, args: &TermList, classes: F) -> Rules
+    where
+        F: Fn(&Term) -> Option<HashSet<Symbol>>,
+    {
+        let args = args
+            .iter()
+            .map(|arg| Argument {
+                value: Some(arg.value()).filter(|value| value.is_ground()),
+                classes: classes(arg),
+            })
+            .collect::<Vec<_>>();
         self.index
-            .get_applicable_rules(args, 0)
+            .get_applicable_rules(&args)
             .iter()
             .map(|id| self.rules.get(id).expect("Rule missing"))
             .cloned()
             .collect()
     }
 
+    /// All rules, in the order they were added.
+    pub fn ordered_rules(&self) -> Rules {
+        let mut rules = self.rules.iter().collect::<Vec<_>>();
+        rules.sort_by_key(|(id, _)| **id);
+        rules.into_iter().map(|(_, rule)| rule.clone()).collect()
+    }
+
     fn next_rule_id(&mut self) -> u64 {
         let v = self.next_rule_id;
         self.next_rule_id += 1;
@@ -280,45 +407,103 @@ mod tests {
 
         let kb = polar.kb.read().unwrap();
         let generic_rule = kb.get_generic_rule(&sym!("f")).unwrap();
-        let index = &generic_rule.index;
-        assert!(index.rules.is_empty());
-
-        fn keys(index: &RuleIndex) -> HashSet<Option<Value>> {
-            index.index.keys().cloned().collect()
+        let index = &generic_rule.index.arities[&3];
+        assert_eq!(index.rules.len(), 5);
+
+        fn keys(index: &ParamIndex) -> HashSet<Option<Value>> {
+            let mut keys = index
+                .values
+                .keys()
+                .cloned()
+                .map(Some)
+                .collect::<HashSet<_>>();
+            if !index.any.is_empty() {
+                keys.insert(None);
+            }
+            keys
         }
 
         let mut args = HashSet::<Option<Value>>::new();
 
-        args.clear();
         args.insert(Some(value!(1)));
-        assert_eq!(args, keys(index));
+        assert_eq!(args, keys(&index.params[0]));
 
         args.clear();
         args.insert(None); // x
         args.insert(Some(value!(1)));
         args.insert(Some(value!(2)));
         args.insert(Some(value!(3)));
-        let index1 = index.index.get(&Some(value!(1))).unwrap();
-        assert_eq!(args, keys(index1));
+        assert_eq!(args, keys(&index.params[1]));
 
         args.clear();
         args.insert(Some(value!("x")));
         args.insert(Some(value!("y")));
-        let index11 = index1.index.get(&Some(value!(1))).unwrap();
-        assert_eq!(args, keys(index11));
+        args.insert(Some(value!(btreemap! {sym!("b") => term!("y")})));
+        args.insert(Some(value!(btreemap! {sym!("c") => term!("z")})));
+        assert_eq!(args, keys(&index.params[2]));
+
+        let applicable = |args: TermList| {
+            generic_rule
+                .get_applicable_rules(&args)
+                .iter()
+                .map(|rule| rule.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            applicable(vec![term!(1), term!(1), term!("y")]),
+            vec![r#"f(1, 1, "y");"#, r#"f(1, x, "y") if x = 2;"#]
+        );
+        assert_eq!(
+            applicable(vec![term!(1), term!(3), sym!("z").into()]),
+            vec![r#"f(1, x, "y") if x = 2;"#, r#"f(1, 3, {c: "z"});"#]
+        );
+        assert_eq!(
+            applicable(vec![term!(2), sym!("y").into(), term!("y")]).len(),
+            0
+        );
+        assert_eq!(applicable(vec![term!(1), term!(1)]).len(), 0);
+    }
+
+    #[test]
+    fn test_rule_index_classes() {
+        let polar = Polar::new();
+        polar
+            .load_str(
+                r#"
+            g(_: A, 1);
+            g(_: B, 2);
+            g(_x, 3);
+            g(1, 4);
+        "#,
+            )
+            .unwrap();
 
-        args.remove(&Some(value!("x")));
-        let index1_ = index1.index.get(&None).unwrap();
-        assert_eq!(args, keys(index1_));
+        let kb = polar.kb.read().unwrap();
+        let generic_rule = kb.get_generic_rule(&sym!("g")).unwrap();
+        let instance = Term::from(Value::ExternalInstance(ExternalInstance {
+            instance_id: 1,
+            constructor: None,
+            repr: None,
+            class_repr: None,
+            class_id: None,
+        }));
+        let args = vec![instance.clone(), sym!("y").into()];
+        let applicable = |classes: Option<HashSet<Symbol>>| {
+            generic_rule
+                .get_applicable_rules_with_classes(&args, |arg| {
+                    classes.clone().filter(|_| arg == &instance)
+                })
+                .iter()
+                .map(|rule| rule.params[1].parameter.clone())
+                .collect::<Vec<_>>()
+        };
 
-        args.clear();
-        args.insert(Some(value!(btreemap! {sym!("b") => term!("y")})));
-        let index12 = index1.index.get(&Some(value!(2))).unwrap();
-        assert_eq!(args, keys(index12));
+        // Rules specialized on classes the argument isn't an instance of are skipped. The
+        // host may consider the instance equal to a ground parameter, so those rules remain.
+        let classes = Some(hashset! {sym!("B")});
+        assert_eq!(applicable(classes), vec![term!(2), term!(3), term!(4)]);
 
-        args.clear();
-        args.insert(Some(value!(btreemap! {sym!("c") => term!("z")})));
-        let index13 = index1.index.get(&Some(value!(3))).unwrap();
-        assert_eq!(args, keys(index13));
+        // Unknown classes keep every rule.
+        assert_eq!(applicable(None).len(), 4);
     }
 }
@@ -0,0 +1,342 @@
+//! Tabling (memoization) of calls to selected rules.
+//!
+//! A call to a tabled rule is evaluated once per query for each *variant* of its arguments, i.e.,
+//! the arguments up to renaming of their variables. Its answers are stored in a table, and later
+//! calls that are variants of it read their answers from the table rather than evaluating the
+//! rule again.
+//!
+//! A call that is a variant of one that is still being evaluated (a recursive call) reads the
+//! answers found so far. Since these may be incomplete, the evaluation that started first (the
+//! *leader*) is repeated until no new answers are found, at which point the tables of the leader
+//! and of every call that read incomplete answers are complete. This makes left-recursive rules
+//! and rules over cyclic data terminate.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::counter::Counter;
+use crate::error::{PolarError, PolarResult};
+use crate::events::QueryEvent;
+use crate::folder::Folder;
+use crate::runnable::Runnable;
+use crate::terms::{Symbol, Term, TermList};
+use crate::vm::PolarVirtualMachine;
+
+/// Rename the variables in `terms` in order of appearance, so that variants are equal.
+pub(crate) fn canonicalize(terms: TermList) -> TermList {
+    struct Canonicalizer {
+        renames: HashMap<Symbol, Symbol>,
+    }
+
+    impl Canonicalizer {
+        fn rename(&mut self, v: Symbol) -> Symbol {
+            let next = self.renames.len();
+            self.renames
+                .entry(v)
+                .or_insert_with(|| Symbol(format!("_table_{}", next)))
+                .clone()
+        }
+    }
+
+    impl Folder for Canonicalizer {
+        fn fold_variable(&mut self, v: Symbol) -> Symbol {
+            self.rename(v)
+        }
+
+        fn fold_rest_variable(&mut self, v: Symbol) -> Symbol {
+            self.rename(v)
+        }
+    }
+
+    let mut canonicalizer = Canonicalizer {
+        renames: HashMap::new(),
+    };
+    terms
+        .into_iter()
+        .map(|term| canonicalizer.fold_term(term))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    /// Being evaluated by the evaluator at this depth of the evaluation stack.
+    Evaluating(usize),
+    /// Evaluated during `pass`, but read incomplete answers from the table being evaluated at
+    /// `depth`. Complete once that table is.
+    Incomplete {
+        pass: usize,
+        depth: usize,
+    },
+    Complete,
+}
+
+#[derive(Debug)]
+struct Table {
+    answers: Vec<TermList>,
+    seen: HashSet<TermList>,
+    status: Status,
+}
+
+/// A table being evaluated.
+#[derive(Debug)]
+struct Frame {
+    key: Term,
+    /// The shallowest depth of a table that this one read incomplete answers from during the
+    /// current pass, if any.
+    depends_on: Option<usize>,
+    /// Tables that are complete once this one is.
+    incomplete: Vec<Term>,
+}
+
+/// The tables of a query, shared by the VMs evaluating it.
+#[derive(Debug, Default)]
+pub(crate) struct Tables {
+    tables: HashMap<Term, Table>,
+    /// Tables being evaluated, outermost first.
+    stack: Vec<Frame>,
+    /// The number of answers found so far, to detect a fixpoint.
+    answer_count: usize,
+    /// The number of evaluation passes started so far.
+    pass: usize,
+}
+
+impl Tables {
+    /// Whether calls to `key` can read their answers from its table. If not, the call must be
+    /// evaluated with a [`TableEvaluator`].
+    pub(crate) fn lookup(&mut self, key: &Term) -> bool {
+        let depth = match self.tables.get(key).map(|table| table.status) {
+            None => return false,
+            Some(Status::Complete) => return true,
+            Some(Status::Evaluating(depth)) => depth,
+            Some(Status::Incomplete { pass, depth }) if pass == self.pass => depth,
+            // Incomplete answers from an earlier pass are re-evaluated.
+            Some(Status::Incomplete { .. }) => return false,
+        };
+        if let Some(frame) = self.stack.last_mut() {
+            frame.depends_on = Some(frame.depends_on.map_or(depth, |d| d.min(depth)));
+        }
+        true
+    }
+
+    pub(crate) fn answers(&self, key: &Term) -> Vec<TermList> {
+        self.tables
+            .get(key)
+            .map(|table| table.answers.clone())
+            .unwrap_or_default()
+    }
+
+    fn is_complete(&self, key: &Term) -> bool {
+        matches!(
+            self.tables.get(key),
+            Some(Table {
+                status: Status::Complete,
+                ..
+            })
+        )
+    }
+
+    /// Start evaluating `key`, returning its depth on the evaluation stack.
+    fn begin(&mut self, key: Term) -> usize {
+        let depth = self.stack.len();
+        let status = Status::Evaluating(depth);
+        self.tables
+            .entry(key.clone())
+            .and_modify(|table| table.status = status)
+            .or_insert_with(|| Table {
+                answers: vec![],
+                seen: HashSet::new(),
+                status,
+            });
+        self.stack.push(Frame {
+            key,
+            depends_on: None,
+            incomplete: vec![],
+        });
+        depth
+    }
+
+    /// Start a pass over the rules of the table at the top of the stack, returning the number of
+    /// answers found so far.
+    fn start_pass(&mut self) -> usize {
+        let frame = self.stack.last_mut().expect("table is being evaluated");
+        frame.depends_on = None;
+        frame.incomplete.clear();
+        self.pass += 1;
+        self.answer_count
+    }
+
+    fn add_answer(&mut self, key: &Term, answer: TermList) {
+        let table = self.tables.get_mut(key).expect("table is being evaluated");
+        if table.seen.insert(answer.clone()) {
+            table.answers.push(answer);
+            self.answer_count += 1;
+        }
+    }
+
+    /// Finish a pass over the rules of the table at `depth`, the top of the stack, that started
+    /// when `answer_count` answers had been found. Returns whether the evaluation is finished;
+    /// if not, the leader must make another pass.
+    fn finish_pass(&mut self, depth: usize, answer_count: usize) -> bool {
+        assert_eq!(self.stack.len(), depth + 1, "unbalanced table evaluation");
+        let frame = self.stack.pop().unwrap();
+        match frame.depends_on {
+            Some(depends_on) if depends_on < depth => {
+                // Read incomplete answers from an enclosing evaluation, which will repeat this
+                // one if needed.
+                self.set_status(
+                    &frame.key,
+                    Status::Incomplete {
+                        pass: self.pass,
+                        depth: depends_on,
+                    },
+                );
+                let parent = self.stack.last_mut().unwrap();
+                parent.depends_on =
+                    Some(parent.depends_on.map_or(depends_on, |d| d.min(depends_on)));
+                parent.incomplete.push(frame.key);
+                parent.incomplete.extend(frame.incomplete);
+                true
+            }
+            // Read its own incomplete answers, which may have grown since.
+            Some(_) if self.answer_count != answer_count => {
+                self.stack.push(frame);
+                false
+            }
+            _ => {
+                for key in std::iter::once(&frame.key).chain(&frame.incomplete) {
+                    self.set_status(key, Status::Complete);
+                }
+                true
+            }
+        }
+    }
+
+    /// Discard the evaluation at `depth` and every evaluation that depends on it, e.g. after an
+    /// error.
+    fn abandon(&mut self, depth: usize) {
+        self.stack.truncate(depth);
+        self.tables.retain(|_, table| match table.status {
+            Status::Evaluating(d) | Status::Incomplete { depth: d, .. } => d < depth,
+            Status::Complete => true,
+        });
+    }
+
+    fn set_status(&mut self, key: &Term, status: Status) {
+        if let Some(table) = self.tables.get_mut(key) {
+            table.status = status;
+        }
+    }
+}
+
+/// A `Runnable` that evaluates a call to a tabled rule until its table is complete, or until it
+/// depends on an enclosing evaluation.
+///
+/// The answers are left in the table for the parent VM to read.
+#[derive(Clone)]
+pub struct TableEvaluator {
+    /// A VM that evaluates the rules for the call once.
+    template: PolarVirtualMachine,
+    /// The VM for the current pass.
+    vm: Option<PolarVirtualMachine>,
+    key: Term,
+    args: TermList,
+    tables: Rc<RefCell<Tables>>,
+    /// Depth on the evaluation stack once started.
+    depth: Option<usize>,
+    /// The number of answers found before the current pass.
+    answer_count: usize,
+}
+
+impl TableEvaluator {
+    pub(crate) fn new(
+        template: PolarVirtualMachine,
+        key: Term,
+        args: TermList,
+        tables: Rc<RefCell<Tables>>,
+    ) -> Self {
+        Self {
+            template,
+            vm: None,
+            key,
+            args,
+            tables,
+            depth: None,
+            answer_count: 0,
+        }
+    }
+}
+
+impl Runnable for TableEvaluator {
+    fn run(&mut self, _: Option<&mut Counter>) -> PolarResult<QueryEvent> {
+        loop {
+            if self.vm.is_none() {
+                let mut tables = self.tables.borrow_mut();
+                if self.depth.is_none() {
+                    // A variant may have been completed since this call was made.
+                    if tables.is_complete(&self.key) {
+                        return Ok(QueryEvent::Done { result: true });
+                    }
+                    self.depth = Some(tables.begin(self.key.clone()));
+                }
+                self.answer_count = tables.start_pass();
+                self.vm = Some(self.template.clone());
+            }
+
+            let vm = self.vm.as_mut().unwrap();
+            match vm.run(None)? {
+                QueryEvent::Result { .. } => {
+                    let answer = vm.table_answer(&self.key, &self.args)?;
+                    self.tables.borrow_mut().add_answer(&self.key, answer);
+                }
+                QueryEvent::Done { .. } => {
+                    let depth = self.depth.unwrap();
+                    self.vm = None;
+                    if self
+                        .tables
+                        .borrow_mut()
+                        .finish_pass(depth, self.answer_count)
+                    {
+                        return Ok(QueryEvent::Done { result: true });
+                    }
+                }
+                event => return Ok(event),
+            }
+        }
+    }
+
+    fn external_question_result(&mut self, call_id: u64, answer: bool) -> PolarResult<()> {
+        self.vm
+            .as_mut()
+            .expect("evaluating")
+            .external_question_result(call_id, answer)
+    }
+
+    fn external_call_result(&mut self, call_id: u64, term: Option<Term>) -> PolarResult<()> {
+        self.vm
+            .as_mut()
+            .expect("evaluating")
+            .external_call_result(call_id, term)
+    }
+
+    fn debug_command(&mut self, command: &str) -> PolarResult<()> {
+        self.vm.as_mut().expect("evaluating").debug_command(command)
+    }
+
+    fn clone_runnable(&self) -> Box<dyn Runnable> {
+        Box::new(self.clone())
+    }
+
+    fn handle_error(&mut self, error: PolarError) -> PolarResult<QueryEvent> {
+        let result = match &mut self.vm {
+            Some(vm) => vm.handle_error(error),
+            None => Err(error),
+        };
+        if result.is_err() {
+            if let Some(depth) = self.depth {
+                self.tables.borrow_mut().abandon(depth);
+            }
+        }
+        result
+    }
+}
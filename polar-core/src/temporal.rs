@@ -0,0 +1,509 @@
+//! Timestamps and durations.
+//!
+//! A [`Timestamp`] is an instant in UTC, written in Polar as an RFC 3339 literal prefixed with
+//! `@`, e.g. `@2024-01-01T00:00:00Z` or `@2024-01-01`. A [`Duration`] is a signed span of time,
+//! written as an integer with a unit suffix, e.g. `30d`, `12h`, `15m`, `10s`, `250ms`, `10us` or
+//! `5ns`. Both have nanosecond precision.
+//!
+//! The built-in `now(t)` predicate (or `now()` as an operand) unifies `t` with the time of the
+//! knowledge base's [`Clock`].
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::terms::{Call, Term};
+use crate::visitor::{walk_call, Visitor};
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Duration units, largest first, with their length in nanoseconds.
+const UNITS: [(&str, i128); 7] = [
+    ("d", SECS_PER_DAY as i128 * NANOS_PER_SEC),
+    ("h", 3_600 * NANOS_PER_SEC),
+    ("m", 60 * NANOS_PER_SEC),
+    ("s", NANOS_PER_SEC),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Split `nanos` into whole seconds and non-negative subsecond nanoseconds.
+fn split_nanos(nanos: i128) -> Option<(i64, u32)> {
+    let secs = i64::try_from(nanos.div_euclid(NANOS_PER_SEC)).ok()?;
+    Some((secs, nanos.rem_euclid(NANOS_PER_SEC) as u32))
+}
+
+/// An instant, as the time elapsed since the Unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    pub const UNIX_EPOCH: Self = Self { secs: 0, nanos: 0 };
+
+    /// The timestamp `secs` seconds and `nanos` nanoseconds after the Unix epoch, if `nanos` is
+    /// less than a second.
+    pub fn from_unix(secs: i64, nanos: u32) -> Option<Self> {
+        if i128::from(nanos) < NANOS_PER_SEC {
+            Some(Self { secs, nanos })
+        } else {
+            None
+        }
+    }
+
+    pub fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        split_nanos(nanos).map(|(secs, nanos)| Self { secs, nanos })
+    }
+
+    /// Whole seconds since the Unix epoch, rounded down.
+    pub fn unix_secs(&self) -> i64 {
+        self.secs
+    }
+
+    /// Nanoseconds past [`Timestamp::unix_secs`].
+    pub fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    pub fn unix_nanos(&self) -> i128 {
+        i128::from(self.secs) * NANOS_PER_SEC + i128::from(self.nanos)
+    }
+
+    /// Parse an RFC 3339 timestamp like `2024-01-01T12:00:00.5+01:00`, or a date like
+    /// `2024-01-01`, which is midnight UTC.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (date, time) = match s.find(['T', 't']) {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (s, None),
+        };
+        let days = parse_date(date)?;
+        let (secs, nanos) = match time {
+            Some(time) => parse_time(time)?,
+            None => (0, 0),
+        };
+        let secs = days.checked_mul(SECS_PER_DAY)?.checked_add(secs)?;
+        Self::from_unix(secs, nanos)
+    }
+
+    pub fn checked_add(&self, duration: Duration) -> Option<Self> {
+        Self::from_unix_nanos(self.unix_nanos().checked_add(duration.as_nanos())?)
+    }
+
+    pub fn checked_sub(&self, duration: Duration) -> Option<Self> {
+        Self::from_unix_nanos(self.unix_nanos().checked_sub(duration.as_nanos())?)
+    }
+
+    /// The duration from `earlier` to this timestamp, which is negative if `earlier` is later.
+    pub fn duration_since(&self, earlier: Timestamp) -> Option<Duration> {
+        Duration::from_nanos(self.unix_nanos().checked_sub(earlier.unix_nanos())?)
+    }
+}
+
+/// Formats the timestamp in RFC 3339 format in UTC, with as many fractional digits as needed.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let days = self.secs.div_euclid(SECS_PER_DAY);
+        let secs = self.secs.rem_euclid(SECS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )?;
+        if self.nanos > 0 {
+            let fraction = format!("{:09}", self.nanos);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        write!(f, "Z")
+    }
+}
+
+impl TryFrom<SystemTime> for Timestamp {
+    type Error = ();
+
+    fn try_from(time: SystemTime) -> Result<Self, ()> {
+        let nanos = match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => i128::try_from(after.as_nanos()).map_err(|_| ())?,
+            Err(before) => -i128::try_from(before.duration().as_nanos()).map_err(|_| ())?,
+        };
+        Self::from_unix_nanos(nanos).ok_or(())
+    }
+}
+
+impl TryFrom<Timestamp> for SystemTime {
+    type Error = ();
+
+    fn try_from(timestamp: Timestamp) -> Result<Self, ()> {
+        let nanos = timestamp.unix_nanos();
+        let offset = std::time::Duration::new(
+            (nanos.abs() / NANOS_PER_SEC) as u64,
+            (nanos.abs() % NANOS_PER_SEC) as u32,
+        );
+        if nanos < 0 {
+            UNIX_EPOCH.checked_sub(offset)
+        } else {
+            UNIX_EPOCH.checked_add(offset)
+        }
+        .ok_or(())
+    }
+}
+
+/// A signed span of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Duration {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Duration {
+    pub const ZERO: Self = Self { secs: 0, nanos: 0 };
+    pub const MAX: Self = Self {
+        secs: i64::MAX,
+        nanos: 999_999_999,
+    };
+
+    pub fn from_nanos(nanos: i128) -> Option<Self> {
+        split_nanos(nanos).map(|(secs, nanos)| Self { secs, nanos })
+    }
+
+    pub fn from_secs(secs: i64) -> Self {
+        Self { secs, nanos: 0 }
+    }
+
+    /// The duration of `n` of `unit`, which is one of `d`, `h`, `m`, `s`, `ms`, `us` or `ns`.
+    pub fn from_unit(n: i64, unit: &str) -> Option<Self> {
+        let (_, nanos) = UNITS.iter().find(|(name, _)| *name == unit)?;
+        Self::from_nanos(i128::from(n).checked_mul(*nanos)?)
+    }
+
+    /// Whether `unit` is a duration unit.
+    pub fn is_unit(unit: &str) -> bool {
+        UNITS.iter().any(|(name, _)| *name == unit)
+    }
+
+    pub fn as_nanos(&self) -> i128 {
+        i128::from(self.secs) * NANOS_PER_SEC + i128::from(self.nanos)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.secs < 0
+    }
+
+    pub fn checked_add(&self, other: Duration) -> Option<Self> {
+        Self::from_nanos(self.as_nanos().checked_add(other.as_nanos())?)
+    }
+
+    pub fn checked_sub(&self, other: Duration) -> Option<Self> {
+        Self::from_nanos(self.as_nanos().checked_sub(other.as_nanos())?)
+    }
+
+    pub fn checked_mul(&self, n: i64) -> Option<Self> {
+        Self::from_nanos(self.as_nanos().checked_mul(i128::from(n))?)
+    }
+
+    /// Divide the duration by `n`, rounding towards zero.
+    pub fn checked_div(&self, n: i64) -> Option<Self> {
+        Self::from_nanos(self.as_nanos().checked_div(i128::from(n))?)
+    }
+}
+
+/// Formats the duration as a Polar literal in the largest unit that divides it, e.g. `90m`.
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let nanos = self.as_nanos();
+        if nanos == 0 {
+            return write!(f, "0s");
+        }
+        let (unit, per_unit) = UNITS
+            .iter()
+            .find(|(_, per_unit)| nanos % per_unit == 0)
+            .expect("every duration is a whole number of nanoseconds");
+        write!(f, "{}{}", nanos / per_unit, unit)
+    }
+}
+
+impl TryFrom<std::time::Duration> for Duration {
+    type Error = ();
+
+    fn try_from(duration: std::time::Duration) -> Result<Self, ()> {
+        let nanos = i128::try_from(duration.as_nanos()).map_err(|_| ())?;
+        Self::from_nanos(nanos).ok_or(())
+    }
+}
+
+/// Fails for negative durations.
+impl TryFrom<Duration> for std::time::Duration {
+    type Error = ();
+
+    fn try_from(duration: Duration) -> Result<Self, ()> {
+        if duration.is_negative() {
+            return Err(());
+        }
+        Ok(Self::new(duration.secs as u64, duration.nanos))
+    }
+}
+
+/// The source of the current time for `now`.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Timestamp;
+}
+
+/// The system's real-time clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> Timestamp {
+        Timestamp::try_from(SystemTime::now()).expect("the system time is out of range")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> Timestamp {
+        let millis = js_sys::Date::now() as i128;
+        Timestamp::from_unix_nanos(millis * 1_000_000).expect("the system time is out of range")
+    }
+}
+
+/// A clock that is stopped at a fixed time, for deterministic tests.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub Timestamp);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.0
+    }
+}
+
+/// Whether `call` is a call to the built-in `now(t)` predicate.
+pub(crate) fn is_now_call(call: &Call) -> bool {
+    call.name.0 == "now" && call.args.len() == 1 && call.kwargs.is_none()
+}
+
+/// Whether `term` calls `now(t)`, e.g. the body of a rule whose result depends on the time.
+pub(crate) fn calls_now(term: &Term) -> bool {
+    #[derive(Default)]
+    struct NowVisitor {
+        calls_now: bool,
+    }
+
+    impl Visitor for NowVisitor {
+        fn visit_call(&mut self, call: &Call) {
+            self.calls_now |= is_now_call(call);
+            walk_call(self, call)
+        }
+    }
+
+    let mut visitor = NowVisitor::default();
+    visitor.visit_term(term);
+    visitor.calls_now
+}
+
+/// Parse `YYYY-MM-DD` to days since the Unix epoch.
+fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let year = parse_digits(parts.next()?, 4)?;
+    let month = parse_digits(parts.next()?, 2)?;
+    let day = parse_digits(parts.next()?, 2)?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Parse `HH:MM:SS[.fraction](Z|±HH:MM)` to seconds past midnight UTC, which may be negative or
+/// past the end of the day because of the offset, and subsecond nanoseconds.
+fn parse_time(time: &str) -> Option<(i64, u32)> {
+    let offset_start = time.find(['Z', 'z', '+', '-'])?;
+    let (time, offset) = time.split_at(offset_start);
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+
+    let mut parts = time.splitn(3, ':');
+    let hour = parse_digits(parts.next()?, 2)?;
+    let minute = parse_digits(parts.next()?, 2)?;
+    let second = parse_digits(parts.next()?, 2)?;
+    // Leap seconds are not represented.
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let nanos = match fraction {
+        Some(fraction) if fraction.len() > 9 => return None,
+        Some(fraction) => {
+            let digits = parse_digits(fraction, fraction.len())?;
+            (digits * 10_i64.pow(9 - fraction.len() as u32)) as u32
+        }
+        None => 0,
+    };
+
+    let offset = match offset {
+        "Z" | "z" => 0,
+        _ => {
+            let (sign, offset) = offset.split_at(1);
+            let (hours, minutes) = offset.split_once(':')?;
+            let (hours, minutes) = (parse_digits(hours, 2)?, parse_digits(minutes, 2)?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if sign == "-" {
+                -offset
+            } else {
+                offset
+            }
+        }
+    };
+    Some((hour * 3600 + minute * 60 + second - offset, nanos))
+}
+
+/// Parse exactly `len` ASCII digits.
+fn parse_digits(s: &str, len: usize) -> Option<i64> {
+    if s.len() != len || len == 0 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian calendar.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date of a number of days since the Unix epoch. Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_parse_and_display() {
+        let parse = |s| Timestamp::parse(s).map(|t| t.to_string());
+        assert_eq!(
+            parse("1970-01-01T00:00:00Z").unwrap(),
+            "1970-01-01T00:00:00Z"
+        );
+        assert_eq!(parse("2024-02-29").unwrap(), "2024-02-29T00:00:00Z");
+        assert_eq!(
+            parse("2024-01-01T01:30:00.250+01:30").unwrap(),
+            "2024-01-01T00:00:00.25Z"
+        );
+        assert_eq!(
+            parse("1969-12-31T23:59:59Z").unwrap(),
+            "1969-12-31T23:59:59Z"
+        );
+        assert_eq!(
+            Timestamp::parse("1969-12-31T23:59:59Z")
+                .unwrap()
+                .unix_secs(),
+            -1
+        );
+        assert_eq!(
+            Timestamp::parse("2000-03-01T00:00:00Z")
+                .unwrap()
+                .unix_secs(),
+            951_868_800
+        );
+        for invalid in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00:00",
+            "2024-01-01T00:00:00.1234567890Z",
+            "2024-01-01T00:00Z",
+        ] {
+            assert!(Timestamp::parse(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_duration_display() {
+        let d = |n, unit| Duration::from_unit(n, unit).unwrap().to_string();
+        assert_eq!(d(30, "d"), "30d");
+        assert_eq!(d(90, "m"), "90m");
+        assert_eq!(d(120, "m"), "2h");
+        assert_eq!(d(-1500, "ms"), "-1500ms");
+        assert_eq!(d(0, "d"), "0s");
+        assert!(Duration::from_unit(i64::MAX, "d").is_none());
+        assert!(Duration::from_unit(1, "y").is_none());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let t = Timestamp::parse("2024-01-31T00:00:00Z").unwrap();
+        let day = Duration::from_unit(1, "d").unwrap();
+        assert_eq!(
+            t.checked_add(day).unwrap().to_string(),
+            "2024-02-01T00:00:00Z"
+        );
+        assert_eq!(
+            t.checked_sub(day.checked_mul(31).unwrap())
+                .unwrap()
+                .to_string(),
+            "2023-12-31T00:00:00Z"
+        );
+        let later = t.checked_add(day.checked_div(2).unwrap()).unwrap();
+        assert_eq!(later.duration_since(t).unwrap().to_string(), "12h");
+        assert_eq!(t.duration_since(later).unwrap().to_string(), "-12h");
+    }
+
+    #[test]
+    fn test_system_time_conversions() {
+        let t = Timestamp::parse("1969-12-31T23:59:59.5Z").unwrap();
+        let time = SystemTime::try_from(t).unwrap();
+        assert_eq!(Timestamp::try_from(time).unwrap(), t);
+
+        let d = Duration::from_unit(1500, "ms").unwrap();
+        let std = std::time::Duration::try_from(d).unwrap();
+        assert_eq!(std, std::time::Duration::from_millis(1500));
+        assert_eq!(Duration::try_from(std).unwrap(), d);
+        assert!(std::time::Duration::try_from(d.checked_mul(-1).unwrap()).is_err());
+    }
+}
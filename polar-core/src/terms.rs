@@ -9,8 +9,11 @@ use serde::{Deserialize, Serialize};
 
 use super::error::{unexpected_value, PolarResult};
 pub use super::numerics::Numeric;
+#[cfg(feature = "bignum")]
+pub use super::numerics::{BigDecimal, BigInt};
 use super::resource_block::{ACTOR_UNION_NAME, RESOURCE_UNION_NAME};
 use super::sources::{Context, Source, SourceInfo};
+pub use super::temporal::{Duration, Timestamp};
 use super::visitor::{walk_operation, walk_term, Visitor};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Eq, PartialEq, Hash)]
@@ -103,6 +106,7 @@ pub enum Operator {
     Neq,
     Gt,
     Lt,
+    MatchesRegex,
     Unify,
     Or,
     And,
@@ -128,6 +132,8 @@ pub enum Value {
     Number(Numeric),
     String(String),
     Boolean(bool),
+    Timestamp(Timestamp),
+    Duration(Duration),
     ExternalInstance(ExternalInstance),
     Dictionary(Dictionary),
     Pattern(Pattern),
@@ -145,7 +151,11 @@ impl Value {
             | Value::ExternalInstance(_)
             | Value::Variable(_)
             | Value::RestVariable(_) => false,
-            Value::Number(_) | Value::String(_) | Value::Boolean(_) => true,
+            Value::Number(_)
+            | Value::String(_)
+            | Value::Boolean(_)
+            | Value::Timestamp(_)
+            | Value::Duration(_) => true,
             Value::Pattern(_) => panic!("unexpected value type"),
             Value::Dictionary(Dictionary { fields }) => fields.values().all(|t| t.is_ground()),
             Value::List(terms) => terms.iter().all(|t| t.is_ground()),
@@ -322,7 +332,7 @@ impl Term {
 
     // TODO(gj): Parsed<T> type (or something) so we can remove this meaningless distinction
     // between terms & rules.
-    pub(crate) fn parsed_context(&self) -> Option<&Context> {
+    pub fn parsed_context(&self) -> Option<&Context> {
         if let SourceInfo::Parser(context) = self.source_info() {
             Some(context)
         } else {
@@ -1,9 +1,12 @@
 use std::collections::{HashMap, HashSet};
 
+use super::aggregate::Aggregate;
 use super::diagnostic::Diagnostic;
 use super::error::{PolarError, ValidationError};
 use super::kb::*;
+use super::regexes::literal_patterns;
 use super::rules::*;
+use super::temporal::is_now_call;
 use super::terms::*;
 use super::visitor::{walk_call, walk_rule, walk_term, Visitor};
 use super::warning::ValidationWarning;
@@ -193,8 +196,11 @@ impl<'kb> UndefinedRuleCallVisitor<'kb> {
         self.call_terms
             .into_iter()
             .filter(|term| {
-                term.as_call()
-                    .map_or(false, |call| !self.defined_rules.contains(&call.name))
+                term.as_call().map_or(false, |call| {
+                    !self.defined_rules.contains(&call.name)
+                        && Aggregate::from_call(call).is_none()
+                        && !is_now_call(call)
+                })
             })
             .map(|term| PolarError::from(ValidationError::UndefinedRuleCall { term }).into())
             .collect()
@@ -216,6 +222,22 @@ impl<'kb> Visitor for UndefinedRuleCallVisitor<'kb> {
     }
 }
 
+/// Check that the string literal patterns of `matches_regex` operations are valid regexes.
+pub fn check_regex_patterns(kb: &KnowledgeBase) -> Vec<Diagnostic> {
+    kb.get_rules()
+        .values()
+        .flat_map(|generic_rule| generic_rule.ordered_rules())
+        .flat_map(|rule| literal_patterns(&rule.body))
+        .filter_map(|term| {
+            let msg = match term.value() {
+                Value::String(pattern) => kb.regexes.get(pattern).err()?.to_string(),
+                _ => return None,
+            };
+            Some(PolarError::from(ValidationError::InvalidRegexPattern { term, msg }).into())
+        })
+        .collect()
+}
+
 pub fn check_undefined_rule_calls(kb: &KnowledgeBase) -> Vec<Diagnostic> {
     let mut visitor = UndefinedRuleCallVisitor::new(kb.get_rules().keys().collect());
     for rule in kb.get_rules().values() {
@@ -27,6 +27,8 @@ pub trait Visitor: Sized {
     fn visit_number(&mut self, _n: &Numeric) {}
     fn visit_string(&mut self, _s: &str) {}
     fn visit_boolean(&mut self, _b: &bool) {}
+    fn visit_timestamp(&mut self, _t: &Timestamp) {}
+    fn visit_duration(&mut self, _d: &Duration) {}
     fn visit_instance_id(&mut self, _i: &u64) {}
     fn visit_symbol(&mut self, _s: &Symbol) {}
     fn visit_variable(&mut self, _v: &Symbol) {}
@@ -106,6 +108,8 @@ pub fn walk_term<V: Visitor>(visitor: &mut V, term: &Term) {
         Value::Number(n) => visitor.visit_number(n),
         Value::String(s) => visitor.visit_string(s),
         Value::Boolean(b) => visitor.visit_boolean(b),
+        Value::Timestamp(t) => visitor.visit_timestamp(t),
+        Value::Duration(d) => visitor.visit_duration(d),
         Value::ExternalInstance(e) => visitor.visit_external_instance(e),
         Value::Dictionary(d) => visitor.visit_dictionary(d),
         Value::Pattern(p) => visitor.visit_pattern(p),
@@ -186,7 +190,7 @@ mod tests {
 
     impl Visitor for TestVisitor {
         fn visit_number(&mut self, n: &Numeric) {
-            self.push(Value::Number(*n));
+            self.push(Value::Number(n.clone()));
         }
         fn visit_string(&mut self, s: &str) {
             self.push(Value::String(s.to_string()));
@@ -194,6 +198,12 @@ mod tests {
         fn visit_boolean(&mut self, b: &bool) {
             self.push(Value::Boolean(*b));
         }
+        fn visit_timestamp(&mut self, t: &Timestamp) {
+            self.push(Value::Timestamp(*t));
+        }
+        fn visit_duration(&mut self, d: &Duration) {
+            self.push(Value::Duration(*d));
+        }
         fn visit_instance_id(&mut self, i: &u64) {
             self.push(Value::Number(Numeric::Integer(*i as i64)));
         }
@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::BTreeMap;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
@@ -9,6 +9,7 @@ use std::sync::{Arc, RwLock, RwLockReadGuard};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+use crate::aggregate::{self, Aggregate, Aggregator};
 use crate::bindings::{
     Binding, BindingManager, BindingStack, Bindings, Bsp, FollowerId, VariableState,
 };
@@ -17,16 +18,20 @@ use crate::data_filtering::partition_equivs;
 use crate::debugger::{get_binding_for_var, DebugEvent, Debugger};
 use crate::error::{invalid_state, unsupported, PolarError, PolarResult, RuntimeError};
 use crate::events::*;
+use crate::explain::{Explainer, Explanation};
 use crate::folder::Folder;
 use crate::inverter::Inverter;
 use crate::kb::*;
 use crate::messages::*;
 use crate::numerics::*;
 use crate::partial::{simplify_bindings_opt, simplify_partial, sub_this, IsaConstraintCheck};
+use crate::query_options::{CancellationHandle, QueryOptions};
 use crate::rewrites::Renamer;
 use crate::rules::*;
 use crate::runnable::Runnable;
 use crate::sources::Context;
+use crate::tabling::{canonicalize, TableEvaluator, Tables};
+use crate::temporal::is_now_call;
 use crate::terms::*;
 use crate::traces::*;
 use crate::visitor::{walk_term, Visitor};
@@ -121,6 +126,11 @@ pub enum Goal {
     TraceRule {
         trace: Rc<Trace>,
     },
+    /// Record for an explanation that `rule`'s parameter at `index` is being checked.
+    TraceParameter {
+        rule: Arc<Rule>,
+        index: usize,
+    },
     TraceStackPush,
     TraceStackPop,
     Unify {
@@ -133,6 +143,19 @@ pub enum Goal {
         runnable: Box<dyn Runnable>,
     },
 
+    /// Unify `args` with each answer in the table for `key`.
+    TableAnswers {
+        key: Term,
+        args: TermList,
+    },
+
+    /// Unify the result of the aggregate call `term` with the aggregate of `values`.
+    Aggregate {
+        aggregate: Aggregate,
+        term: Term,
+        values: Rc<RefCell<Vec<Term>>>,
+    },
+
     /// Add a new constraint
     AddConstraint {
         term: Term,
@@ -216,6 +239,8 @@ pub fn compare(
         (Number(l), Boolean(r)) => compare(op, l, &to_int(*r)),
         (Number(l), Number(r)) => compare(op, l, r),
         (String(l), String(r)) => compare(op, l, r),
+        (Timestamp(l), Timestamp(r)) => compare(op, l, r),
+        (Duration(l), Duration(r)) => compare(op, l, r),
         _ => {
             let context = context.expect("should only be None in Grounder, where we unwrap anyway");
             unsupported(context.to_string(), context)
@@ -223,6 +248,30 @@ pub fn compare(
     }
 }
 
+/// The result of an arithmetic operation on timestamps and durations: `None` if the operation
+/// isn't defined for its operands, and `Some(None)` if it overflows.
+fn temporal_arithmetic(op: Operator, left: &Term, right: &Term) -> Option<Option<Value>> {
+    use {Operator::*, Value::*};
+    let integer = |n: &Numeric| match n {
+        Numeric::Integer(i) => Some(*i),
+        _ => None,
+    };
+    Some(match (op, left.value(), right.value()) {
+        (Add, Timestamp(t), Duration(d)) | (Add, Duration(d), Timestamp(t)) => {
+            t.checked_add(*d).map(Timestamp)
+        }
+        (Sub, Timestamp(t), Duration(d)) => t.checked_sub(*d).map(Timestamp),
+        (Sub, Timestamp(l), Timestamp(r)) => l.duration_since(*r).map(Duration),
+        (Add, Duration(l), Duration(r)) => l.checked_add(*r).map(Duration),
+        (Sub, Duration(l), Duration(r)) => l.checked_sub(*r).map(Duration),
+        (Mul, Duration(d), Number(n)) | (Mul, Number(n), Duration(d)) => {
+            d.checked_mul(integer(n)?).map(Duration)
+        }
+        (Div, Duration(d), Number(n)) => d.checked_div(integer(n)?).map(Duration),
+        _ => return None,
+    })
+}
+
 #[derive(Clone)]
 pub struct PolarVirtualMachine {
     /// Stacks.
@@ -234,6 +283,10 @@ pub struct PolarVirtualMachine {
     pub tracing: bool,
     pub trace_stack: TraceStack, // Stack of traces higher up the tree.
     pub trace: Vec<Rc<Trace>>,   // Traces for the current level of the trace tree.
+    explainer: Option<Explainer>,
+
+    /// Answers to calls to tabled rules, shared with sub-VMs.
+    tables: Rc<RefCell<Tables>>,
 
     // Errors from outside the vm.
     pub external_error: Option<String>,
@@ -243,9 +296,18 @@ pub struct PolarVirtualMachine {
     #[cfg(target_arch = "wasm32")]
     query_start_time: Option<f64>,
     query_timeout_ms: u64,
+    /// Whether the timeout was set with `QueryOptions` rather than `POLAR_TIMEOUT_MS`.
+    timeout_from_options: bool,
 
     /// Maximum size of goal stack
     stack_limit: usize,
+    /// Maximum size of choice stack
+    choice_limit: usize,
+    /// Maximum number of goals to execute
+    goal_limit: Option<u64>,
+    /// Number of goals executed, shared with sub-VMs.
+    goal_count: Rc<Cell<u64>>,
+    cancellation: CancellationHandle,
 
     /// Binding stack constant below here.
     csp: Bsp,
@@ -317,13 +379,20 @@ impl PolarVirtualMachine {
             binding_manager: BindingManager::new(),
             query_start_time: None,
             query_timeout_ms,
+            timeout_from_options: false,
             stack_limit: MAX_STACK_SIZE,
+            choice_limit: MAX_STACK_SIZE,
+            goal_limit: None,
+            goal_count: Default::default(),
+            cancellation: Default::default(),
             csp: Bsp::default(),
             choices: vec![],
             queries: vec![],
             tracing,
             trace_stack: vec![],
             trace: vec![],
+            explainer: None,
+            tables: Default::default(),
             external_error: None,
             debugger: Debugger::default(),
             kb,
@@ -417,9 +486,52 @@ impl PolarVirtualMachine {
         vm.binding_manager.clone_from(&self.binding_manager);
         vm.query_contains_partial = self.query_contains_partial;
         vm.debugger = self.debugger.clone();
+        vm.tables = self.tables.clone();
+        vm.inherit_limits(self);
         vm
     }
 
+    /// Apply the limits of `vm` to this sub-VM, which solves part of its query: share its
+    /// deadline, goal count and cancellation, so that they cover the whole query.
+    fn inherit_limits(&mut self, vm: &Self) {
+        self.query_start_time = vm.query_start_time;
+        self.query_timeout_ms = vm.query_timeout_ms;
+        self.timeout_from_options = vm.timeout_from_options;
+        self.stack_limit = vm.stack_limit;
+        self.choice_limit = vm.choice_limit;
+        self.goal_limit = vm.goal_limit;
+        self.goal_count = vm.goal_count.clone();
+        self.cancellation = vm.cancellation.clone();
+    }
+
+    /// Apply the limits set in `options`.
+    pub fn set_options(&mut self, options: &QueryOptions) {
+        if let Some(timeout_ms) = options.timeout_ms {
+            self.query_timeout_ms = timeout_ms;
+            self.timeout_from_options = true;
+        }
+        self.stack_limit = options.max_stack_depth.unwrap_or(self.stack_limit);
+        self.choice_limit = options.max_choices.unwrap_or(self.choice_limit);
+        self.goal_limit = options.max_goals.or(self.goal_limit);
+    }
+
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        self.cancellation.clone()
+    }
+
+    /// Record what happens during the query so that it can be explained. Implies tracing.
+    pub fn explain(&mut self) {
+        self.tracing = true;
+        self.explainer = Some(Explainer::default());
+    }
+
+    /// Explain the result of `query`, if explanations are being recorded.
+    pub fn explanation(&self, query: &Term) -> Option<Explanation> {
+        self.explainer
+            .as_ref()
+            .map(|explainer| explainer.explanation(self, query))
+    }
+
     #[cfg(test)]
     fn set_stack_limit(&mut self, limit: usize) {
         self.stack_limit = limit;
@@ -461,7 +573,7 @@ impl PolarVirtualMachine {
     fn next(&mut self, goal: Rc<Goal>) -> PolarResult<QueryEvent> {
         self.log(LogLevel::Trace, || goal.to_string(), &[]);
 
-        self.check_timeout()?;
+        self.check_limits()?;
 
         match goal.as_ref() {
             Goal::Backtrack => self.backtrack()?,
@@ -523,6 +635,10 @@ impl PolarVirtualMachine {
                 let trace = Rc::make_mut(&mut trace);
                 trace.children.append(&mut children);
                 self.trace.push(Rc::new(trace.clone()));
+                if let (Node::Rule(rule), Some(explainer)) = (&trace.node, &mut self.explainer) {
+                    let levels = self.trace_stack.iter().map(|level| level.as_slice());
+                    explainer.rule_succeeded(levels.chain([self.trace.as_slice()]), rule);
+                }
                 self.maybe_break(DebugEvent::Pop)?;
             }
             Goal::TraceRule { trace } => {
@@ -532,6 +648,12 @@ impl PolarVirtualMachine {
                 self.trace.push(trace.clone());
                 self.maybe_break(DebugEvent::Rule)?;
             }
+            Goal::TraceParameter { rule, index } => {
+                if let Some(explainer) = &mut self.explainer {
+                    let levels = self.trace_stack.iter().map(|level| level.as_slice());
+                    explainer.parameter(levels.chain([self.trace.as_slice()]), rule, *index);
+                }
+            }
             Goal::Unify { left, right } => self.unify(left, right)?,
             Goal::AddConstraint { term } => self.add_constraint(term)?,
             Goal::AddConstraintsBatch { add_constraints } => {
@@ -541,6 +663,15 @@ impl PolarVirtualMachine {
                     .try_for_each(|(_, constraint)| self.add_constraint(&constraint))?
             }
             Goal::Run { runnable } => return self.run_runnable(runnable.clone_runnable()),
+            Goal::TableAnswers { key, args } => self.table_answers(key, args)?,
+            Goal::Aggregate {
+                aggregate,
+                term,
+                values,
+            } => {
+                let values = std::mem::take(&mut *values.borrow_mut());
+                self.aggregate(*aggregate, term, values)?
+            }
         }
         Ok(QueryEvent::None)
     }
@@ -581,7 +712,7 @@ impl PolarVirtualMachine {
             .rev()
             .map(GoalStack::new_reversed)
             .collect();
-        if self.choices.len() >= self.stack_limit {
+        if self.choices.len() >= self.choice_limit {
             let msg = "Too many choices.".to_owned();
             Err(RuntimeError::StackOverflow { msg }.into())
         } else {
@@ -889,6 +1020,19 @@ impl PolarVirtualMachine {
         self.query_timeout_ms == 0
     }
 
+    /// Check that the query hasn't been cancelled, and count a goal against its limits.
+    fn check_limits(&self) -> PolarResult<()> {
+        if self.cancellation.is_cancelled() {
+            return Err(RuntimeError::QueryCancelled.into());
+        }
+        let goal_count = self.goal_count.get() + 1;
+        self.goal_count.set(goal_count);
+        if let Some(limit) = self.goal_limit.filter(|limit| goal_count > *limit) {
+            return Err(RuntimeError::GoalLimitExceeded { limit }.into());
+        }
+        self.check_timeout()
+    }
+
     fn check_timeout(&self) -> PolarResult<()> {
         if self.is_query_timeout_disabled() {
             // Useful for debugging
@@ -898,7 +1042,12 @@ impl PolarVirtualMachine {
         let elapsed = self.query_duration();
         let timeout = self.query_timeout_ms;
         if elapsed > timeout {
-            return Err(RuntimeError::QueryTimeout { elapsed, timeout }.into());
+            return Err(RuntimeError::QueryTimeout {
+                elapsed,
+                timeout,
+                from_options: self.timeout_from_options,
+            }
+            .into());
         }
         Ok(())
     }
@@ -1504,11 +1653,23 @@ impl PolarVirtualMachine {
             node: Node::Term(term.clone()),
             children: vec![],
         }));
+        if let Some(explainer) = &mut self.explainer {
+            let levels = self.trace_stack.iter().map(|level| level.as_slice());
+            explainer.query(levels.chain([self.trace.as_slice()]));
+        }
 
         match &term.value() {
-            Value::Call(predicate) => {
-                self.query_for_predicate(predicate.clone())?;
-            }
+            Value::Call(predicate) => match self.builtin_aggregate(predicate) {
+                Some(aggregate) => self.query_aggregate(aggregate, term)?,
+                None if self.is_builtin_now(predicate) => {
+                    let now = self.kb().now();
+                    self.push_goal(Goal::Unify {
+                        left: predicate.args[0].clone(),
+                        right: term.clone_with_value(Value::Timestamp(now)),
+                    })?
+                }
+                None => self.query_for_predicate(predicate.clone())?,
+            },
             Value::Expression(_) => {
                 return self.query_for_operation(term);
             }
@@ -1548,9 +1709,7 @@ impl PolarVirtualMachine {
         Ok(QueryEvent::None)
     }
 
-    /// Select applicable rules for predicate.
-    /// Sort applicable rules by specificity.
-    /// Create a choice over the applicable rules.
+    /// Query for a predicate, reading its answers from a table if its rule is tabled.
     fn query_for_predicate(&mut self, predicate: Call) -> PolarResult<()> {
         if predicate.kwargs.is_some() {
             return invalid_state(format!(
@@ -1558,7 +1717,21 @@ impl PolarVirtualMachine {
                 predicate
             ));
         }
-        let goals = match self.kb.read().unwrap().get_generic_rule(&predicate.name) {
+        if self.kb().is_tabled(&predicate.name) {
+            // Constrained arguments can't be tabled, so evaluate those calls as usual.
+            if let Some(args) = self.table_variant(&predicate.args) {
+                return self.query_table(predicate, args);
+            }
+        }
+        self.query_rules(predicate)
+    }
+
+    /// Select applicable rules for predicate.
+    /// Sort applicable rules by specificity.
+    /// Create a choice over the applicable rules.
+    fn query_rules(&mut self, predicate: Call) -> PolarResult<()> {
+        let kb = self.kb.read().unwrap();
+        let goals = match kb.get_generic_rule(&predicate.name) {
             None => {
                 return Err(RuntimeError::QueryForUndefinedRule {
                     name: predicate.name.0.clone(),
@@ -1568,14 +1741,20 @@ impl PolarVirtualMachine {
             Some(generic_rule) => {
                 if generic_rule.name != predicate.name {
                     return invalid_state(format!(
-                        "query_for_predicate: different rule names: {} != {}",
+                        "query_rules: different rule names: {} != {}",
                         generic_rule.name, predicate.name
                     ));
                 }
 
-                // Pre-filter rules.
+                // Pre-filter rules. When explaining, filter every rule so that the reason each
+                // one doesn't apply is recorded.
                 let args = predicate.args.iter().map(|t| self.deref(t)).collect();
-                let pre_filter = generic_rule.get_applicable_rules(&args);
+                let pre_filter = if self.explainer.is_some() {
+                    generic_rule.ordered_rules()
+                } else {
+                    generic_rule
+                        .get_applicable_rules_with_classes(&args, |arg| kb.instance_classes(arg))
+                };
 
                 self.polar_trace_mute = true;
 
@@ -1591,9 +1770,219 @@ impl PolarVirtualMachine {
                 ]
             }
         };
+        drop(kb);
         self.append_goals(goals)
     }
 
+    /// Query for a call to a tabled rule whose arguments have the variant `variant`. If its
+    /// table can't be read yet, evaluate the call in a sub-VM first.
+    fn query_table(&mut self, predicate: Call, variant: TermList) -> PolarResult<()> {
+        let key = Term::from(Call {
+            name: predicate.name.clone(),
+            args: variant.clone(),
+            kwargs: None,
+        });
+        let read_table = Goal::TableAnswers {
+            key: key.clone(),
+            args: predicate.args,
+        };
+        if self.tables.borrow_mut().lookup(&key) {
+            return self.push_goal(read_table);
+        }
+
+        // The sub-VM evaluates the rules for the call as if it had been queried, but independently
+        // of the bindings of this one.
+        let mut vm = Self::new(self.kb.clone(), self.tracing, vec![], self.messages.clone());
+        vm.tables = self.tables.clone();
+        vm.inherit_limits(self);
+        vm.queries.push(key.clone());
+        vm.trace.push(Rc::new(Trace {
+            node: Node::Term(key.clone()),
+            children: vec![],
+        }));
+        vm.query_rules(Call {
+            name: predicate.name,
+            args: variant.clone(),
+            kwargs: None,
+        })?;
+        let evaluator = TableEvaluator::new(vm, key, variant, self.tables.clone());
+        self.append_goals(vec![
+            Goal::Run {
+                runnable: Box::new(evaluator),
+            },
+            read_table,
+        ])
+    }
+
+    /// Dereference `args` and rename their variables in order of appearance. Returns `None` if
+    /// an argument is constrained.
+    fn table_variant(&self, args: &[Term]) -> Option<TermList> {
+        let args = args.iter().map(|arg| self.deref(arg)).collect::<TermList>();
+        let mut variables = HashSet::new();
+        args.iter().for_each(|arg| arg.variables(&mut variables));
+        if variables
+            .iter()
+            .any(|v| self.binding_manager.is_constrained(v))
+        {
+            return None;
+        }
+        Some(canonicalize(args))
+    }
+
+    /// The answer to the tabled call `key` after evaluating it with arguments `args`.
+    pub(crate) fn table_answer(&self, key: &Term, args: &[Term]) -> PolarResult<TermList> {
+        self.table_variant(args).ok_or_else(|| {
+            RuntimeError::Unsupported {
+                msg: format!(
+                    "{} produced an answer with constraints, which can't be tabled",
+                    key
+                ),
+                term: key.clone(),
+            }
+            .into()
+        })
+    }
+
+    fn table_answers(&mut self, key: &Term, args: &[Term]) -> PolarResult<()> {
+        let answers = self.tables.borrow().answers(key);
+        let alternatives = {
+            let kb = self.kb();
+            answers
+                .into_iter()
+                .map(|answer| {
+                    // Each use of an answer gets fresh variables.
+                    let mut renamer = Renamer::new(&kb);
+                    args.iter()
+                        .zip(answer)
+                        .map(|(arg, value)| Goal::Unify {
+                            left: arg.clone(),
+                            right: renamer.fold_term(value),
+                        })
+                        .collect::<Goals>()
+                })
+                .collect::<Vec<_>>()
+        };
+        self.choose(alternatives)
+    }
+
+    /// The aggregate that `predicate` calls, unless a rule with its name is defined.
+    fn builtin_aggregate(&self, predicate: &Call) -> Option<Aggregate> {
+        Aggregate::from_call(predicate)
+            .filter(|_| self.kb().get_generic_rule(&predicate.name).is_none())
+    }
+
+    /// Whether `predicate` calls the built-in `now(t)`, i.e. no rule named `now` is defined.
+    fn is_builtin_now(&self, predicate: &Call) -> bool {
+        is_now_call(predicate) && self.kb().get_generic_rule(&predicate.name).is_none()
+    }
+
+    /// Query for the aggregate call `term` by finding every solution of its goal in a sub-VM.
+    fn query_aggregate(&mut self, aggregate: Aggregate, term: &Term) -> PolarResult<()> {
+        if self.query_contains_partial {
+            return unsupported(
+                format!("cannot use {} with partial evaluation", aggregate),
+                term,
+            );
+        }
+        let Call { args, .. } = term.as_call()?;
+        let goal = self.deref(&args[1]);
+        if aggregate::is_value(&goal) {
+            return self.type_error(
+                term,
+                format!("{} expects a query as its second argument, got {}", aggregate, goal),
+            );
+        }
+        let values = Rc::new(RefCell::new(vec![]));
+        let aggregator = Aggregator::new(
+            self,
+            args[0].clone(),
+            args[1].clone(),
+            term.clone(),
+            values.clone(),
+        );
+        self.append_goals(vec![
+            Goal::Run {
+                runnable: Box::new(aggregator),
+            },
+            Goal::Aggregate {
+                aggregate,
+                term: term.clone(),
+                values,
+            },
+        ])
+    }
+
+    /// The value of `template` in a solution of the goal of the aggregate call `term`.
+    pub(crate) fn aggregate_value(&self, template: &Term, term: &Term) -> PolarResult<Term> {
+        let value = self.deref(template);
+        let mut variables = HashSet::new();
+        value.variables(&mut variables);
+        if variables
+            .iter()
+            .any(|v| self.binding_manager.is_constrained(v))
+        {
+            return unsupported(
+                format!(
+                    "{} produced a value with constraints, which can't be aggregated",
+                    term
+                ),
+                term,
+            );
+        }
+        Ok(value)
+    }
+
+    /// Unify the last argument of the aggregate call `term` with the aggregate of `values`.
+    fn aggregate(
+        &mut self,
+        aggregate: Aggregate,
+        term: &Term,
+        values: TermList,
+    ) -> PolarResult<()> {
+        let result = term.as_call()?.args[2].clone();
+        let value = match aggregate {
+            Aggregate::Count => Value::Number(Numeric::Integer(values.len() as i64)),
+            Aggregate::Collect => Value::List(values),
+            Aggregate::Sum => {
+                let mut sum = Numeric::Integer(0);
+                for value in values {
+                    sum = match value.value() {
+                        Value::Number(n) => (sum + n.clone())
+                            .ok_or_else(|| RuntimeError::ArithmeticError { term: term.clone() })?,
+                        _ => {
+                            return self
+                                .type_error(&value, format!("can only sum numbers, not {}", value))
+                        }
+                    };
+                }
+                Value::Number(sum)
+            }
+            Aggregate::Min | Aggregate::Max => {
+                let op = if aggregate == Aggregate::Min {
+                    Operator::Lt
+                } else {
+                    Operator::Gt
+                };
+                let mut best: Option<Term> = None;
+                for value in values {
+                    best = match best {
+                        Some(best) if !compare(op, &value, &best, Some(term))? => Some(best),
+                        _ => Some(value),
+                    };
+                }
+                match best {
+                    Some(best) => best.value().clone(),
+                    // There is no minimum or maximum of no values.
+                    None => return self.push_goal(Goal::Backtrack),
+                }
+            }
+        };
+        self.push_goal(Goal::Unify {
+            left: result,
+            right: term.clone_with_value(value),
+        })
+    }
+
     fn query_for_operation(&mut self, term: &Term) -> PolarResult<QueryEvent> {
         let operation = term.as_expression().unwrap();
         let mut args = operation.args.clone();
@@ -1676,6 +2065,10 @@ impl PolarVirtualMachine {
                 return self.query_op_helper(term, Self::comparison_op_helper, true, true);
             }
 
+            Operator::MatchesRegex => {
+                return self.query_op_helper(term, Self::regex_op_helper, true, true);
+            }
+
             Operator::Add
             | Operator::Sub
             | Operator::Mul
@@ -1974,6 +2367,37 @@ impl PolarVirtualMachine {
         }
     }
 
+    /// Evaluate `matches_regex` on a string and a pattern.
+    fn regex_op_helper(&mut self, term: &Term) -> PolarResult<QueryEvent> {
+        let Operation { args, .. } = term.as_expression().unwrap();
+
+        if args.len() != 2 {
+            return invalid_state(format!("regex_op_helper: wrong arity: {}", term));
+        }
+        match (args[0].value(), args[1].value()) {
+            (Value::String(string), Value::String(pattern)) => {
+                let is_match = match self.kb().regexes.get(pattern) {
+                    Ok(regex) => regex.is_match(string),
+                    Err(error) => {
+                        return Err(RuntimeError::InvalidRegex {
+                            msg: error.to_string(),
+                            term: args[1].clone(),
+                        }
+                        .into())
+                    }
+                };
+                if !is_match {
+                    self.push_goal(Goal::Backtrack)?;
+                }
+                Ok(QueryEvent::None)
+            }
+            _ => self.type_error(
+                term,
+                format!("can only match a string against a regex, not {}", term),
+            ),
+        }
+    }
+
     // TODO(ap, dhatch): Rewrite 3-arg arithmetic ops as 2-arg + unify,
     // like we do for dots; e.g., `+(a, b, c)` → `c = +(a, b)`.
     /// Evaluate arithmetic operations.
@@ -1990,13 +2414,14 @@ impl PolarVirtualMachine {
 
         match (left.value(), right.value()) {
             (Value::Number(left), Value::Number(right)) => {
+                let (left, right) = (left.clone(), right.clone());
                 if let Some(answer) = match op {
-                    Operator::Add => *left + *right,
-                    Operator::Sub => *left - *right,
-                    Operator::Mul => *left * *right,
-                    Operator::Div => *left / *right,
-                    Operator::Mod => (*left).modulo(*right),
-                    Operator::Rem => *left % *right,
+                    Operator::Add => left + right,
+                    Operator::Sub => left - right,
+                    Operator::Mul => left * right,
+                    Operator::Div => left / right,
+                    Operator::Mod => left.modulo(right),
+                    Operator::Rem => left % right,
                     _ => return unsupported(format!("numeric operation {}", op), term),
                 } {
                     self.push_goal(Goal::Unify {
@@ -2008,6 +2433,20 @@ impl PolarVirtualMachine {
                     Err(RuntimeError::ArithmeticError { term: term.clone() }.into())
                 }
             }
+            (Value::Timestamp(_), _)
+            | (_, Value::Timestamp(_))
+            | (Value::Duration(_), _)
+            | (_, Value::Duration(_)) => match temporal_arithmetic(*op, left, right) {
+                Some(Some(answer)) => {
+                    self.push_goal(Goal::Unify {
+                        left: term.clone_with_value(answer),
+                        right: result.clone(),
+                    })?;
+                    Ok(QueryEvent::None)
+                }
+                Some(None) => Err(RuntimeError::ArithmeticError { term: term.clone() }.into()),
+                None => unsupported(format!("unsupported arithmetic operands: {}", term), term),
+            },
             (_, _) => unsupported(format!("unsupported arithmetic operands: {}", term), term),
         }
     }
@@ -2040,7 +2479,9 @@ impl PolarVirtualMachine {
             | Value::ExternalInstance(_)
             | Value::List(_)
             | Value::Number(_)
-            | Value::String(_) => {
+            | Value::String(_)
+            | Value::Timestamp(_)
+            | Value::Duration(_) => {
                 let answer = self.kb.read().unwrap().gensym("lookup_value");
                 let call_id = self.new_call_id(&answer);
                 self.append_goals(vec![
@@ -2352,6 +2793,19 @@ impl PolarVirtualMachine {
                 }
             }
 
+            // Unify timestamps and durations by value.
+            (Value::Timestamp(left), Value::Timestamp(right)) => {
+                if left != right {
+                    self.push_goal(Goal::Backtrack)?;
+                }
+            }
+
+            (Value::Duration(left), Value::Duration(right)) => {
+                if left != right {
+                    self.push_goal(Goal::Backtrack)?;
+                }
+            }
+
             (
                 Value::ExternalInstance(ExternalInstance {
                     instance_id: left, ..
@@ -2497,8 +2951,24 @@ impl PolarVirtualMachine {
                 inner: 1,
             })
         } else {
-            // Check one rule for applicability.
+            let mut applicable_rules = applicable_rules.clone();
             let mut unfiltered_rules = unfiltered_rules.clone();
+
+            // The prefilter already checks applicability for ground rules, so accept a run of
+            // them at once. When explaining, they are checked so that mismatches are recorded.
+            let is_ground = |rule: &Arc<Rule>| rule.is_ground() && rule.params.len() == args.len();
+            if self.explainer.is_none() && unfiltered_rules.last().is_some_and(is_ground) {
+                while unfiltered_rules.last().is_some_and(is_ground) {
+                    applicable_rules.push(unfiltered_rules.pop().unwrap());
+                }
+                return self.push_goal(Goal::FilterRules {
+                    args: args.clone(),
+                    applicable_rules,
+                    unfiltered_rules,
+                });
+            }
+
+            // Check one rule for applicability.
             let rule = unfiltered_rules.pop().unwrap();
 
             let inapplicable = Goal::FilterRules {
@@ -2510,7 +2980,6 @@ impl PolarVirtualMachine {
                 return self.push_goal(inapplicable); // wrong arity
             }
 
-            let mut applicable_rules = applicable_rules.clone();
             applicable_rules.push(rule.clone());
             let applicable = Goal::FilterRules {
                 args: args.clone(),
@@ -2518,16 +2987,17 @@ impl PolarVirtualMachine {
                 unfiltered_rules,
             };
 
-            // The prefilter already checks applicability for ground rules.
-            if rule.is_ground() {
-                return self.push_goal(applicable);
-            }
-
             // Rename the variables in the rule (but not the args).
             // This avoids clashes between arg vars and rule vars.
             let Rule { params, .. } = self.rename_rule_vars(&rule);
             let mut check_applicability = vec![];
-            for (arg, param) in args.iter().zip(params.iter()) {
+            for (index, (arg, param)) in args.iter().zip(params.iter()).enumerate() {
+                if self.explainer.is_some() {
+                    check_applicability.push(Goal::TraceParameter {
+                        rule: rule.clone(),
+                        index,
+                    });
+                }
                 check_applicability.push(Goal::Unify {
                     left: arg.clone(),
                     right: param.parameter.clone(),
@@ -2885,6 +3355,10 @@ impl Runnable for PolarVirtualMachine {
             }
         }
 
+        if let Some(explainer) = &mut self.explainer {
+            explainer.result(self.trace.first());
+        }
+
         let trace = if self.tracing {
             let trace = self.trace.first().cloned();
             trace.map(|trace| TraceResult {
@@ -87,7 +87,9 @@ const MISSING_HAS_PERMISSION_RULE_MSG: &str = indoc! {"
     For more information about resource blocks, see https://docs.osohq.com/any/reference/polar/polar-syntax.html#actor-and-resource-blocks
 "};
 
-fn common_specializer_misspellings(term: &Term) -> Option<&str> {
+/// The built-in class a specializer most likely meant when it uses a common name from another
+/// language, e.g., `Integer` for `int`.
+pub fn common_specializer_misspellings(term: &Term) -> Option<&str> {
     if let Value::Pattern(Pattern::Instance(InstanceLiteral { tag, .. })) = term.value() {
         let misspelled_type = match tag.0.as_ref() {
             "integer" => "Integer",
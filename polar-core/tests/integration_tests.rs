@@ -1,20 +1,26 @@
 mod mock_externals;
 
 use indoc::indoc;
-use maplit::btreemap;
+use maplit::{btreemap, hashset};
 use permutohedron::Heap;
 
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 
 use mock_externals::MockExternal;
 use polar_core::{
     error::{ParseErrorKind::*, RuntimeError::*, ValidationError::*, *},
     events::*,
+    explain::*,
     messages::*,
     polar::Polar,
     query::Query,
-    sym, term,
+    query_options::QueryOptions,
+    sources::Source,
+    sym,
+    temporal::FixedClock,
+    term,
     terms::*,
     traces::*,
     value, values,
@@ -502,6 +508,113 @@ fn test_trace() -> TestResult {
     Ok(())
 }
 
+/// Run an explained query to its first result and explain it.
+fn explain(p: &Polar, query_str: &str) -> Result<Explanation, PolarError> {
+    let mut query = p.new_explained_query(query_str)?;
+    match query.next_event()? {
+        QueryEvent::Result { .. } | QueryEvent::Done { .. } => (),
+        event => panic!("unexpected event: {:?}", event),
+    }
+    Ok(query.explanation().unwrap())
+}
+
+#[test]
+fn test_explain_success() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"allow(user, "read", doc) if has_role(user, "reader", doc);
+           has_role(user, role, doc) if role in doc.roles and user.name = "alice";"#,
+    )?;
+    let explanation = explain(&p, r#"allow({name: "alice"}, "read", {roles: ["reader"]})"#)?;
+    let json = serde_json::to_string(&explanation).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Explanation>(&json).unwrap(),
+        explanation
+    );
+
+    let proof = match explanation {
+        Explanation::Success { proof, .. } => proof,
+        failure => panic!("expected success, got {:?}", failure),
+    };
+    let rule = |proof: &Proof| match &proof.step {
+        Step::Rule { rule, .. } => rule.clone(),
+        step => panic!("expected a rule, got {:?}", step),
+    };
+    let allow = &proof.children[0];
+    assert_eq!(rule(allow), r#"allow(user, "read", doc)"#);
+    assert!(matches!(
+        &allow.children[0].step,
+        Step::Query { term, location: Some(Location { line: 1, column: 29, .. }) }
+            if term == r#"has_role(user, "reader", doc)"#
+    ));
+    assert_eq!(
+        rule(&allow.children[0].children[0]),
+        "has_role(user, role, doc)"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explain_failure() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"allow(user, "read", doc) if user.admin = true and doc.public = true;
+           allow(user, "write", doc) if has_role(user, "owner", doc);
+           allow(_user, "delete", _doc) if false;
+           has_role(user, role, doc) if role in doc.roles and user.name = "alice";"#,
+    )?;
+    let user = r#"{admin: true, name: "bob"}"#;
+    let candidates = |query: &str| match explain(&p, query).unwrap() {
+        Explanation::Failure { candidates, .. } => candidates,
+        success => panic!("expected failure, got {:?}", success),
+    };
+    let mismatch = |index, parameter: &str| Outcome::ParameterMismatch {
+        index,
+        parameter: parameter.to_owned(),
+    };
+
+    let read = candidates(&format!(r#"allow({}, "read", {{public: false}})"#, user));
+    assert_eq!(read.len(), 3);
+    assert!(matches!(
+        &read[0].outcome,
+        Outcome::ConditionFailed { index: 1, condition, candidates, .. }
+            if condition == "doc.public = true" && candidates.is_empty()
+    ));
+    assert_eq!(read[1].outcome, mismatch(1, r#""write""#));
+    assert_eq!(read[2].outcome, mismatch(1, r#""delete""#));
+    assert_eq!(read[2].rule, r#"allow(_user, "delete", _doc)"#);
+    assert_eq!(
+        read[2].location,
+        Some(Location {
+            filename: None,
+            line: 3,
+            column: 12
+        })
+    );
+
+    // The failing condition of a nested call is explained by that call's candidates.
+    let write = candidates(&format!(
+        r#"allow({}, "write", {{roles: ["owner"]}})"#,
+        user
+    ));
+    assert_eq!(write[0].outcome, mismatch(1, r#""read""#));
+    let nested = match &write[1].outcome {
+        Outcome::ConditionFailed {
+            index: 0,
+            condition,
+            candidates,
+            ..
+        } if condition == r#"has_role(user, "owner", doc)"# => candidates,
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    };
+    assert_eq!(nested.len(), 1);
+    assert!(matches!(
+        &nested[0].outcome,
+        Outcome::ConditionFailed { index: 1, condition, .. } if condition == r#"user.name = "alice""#
+    ));
+    Ok(())
+}
+
 #[test]
 fn test_nested_rule() -> TestResult {
     let p = polar();
@@ -1075,6 +1188,133 @@ fn test_load_str_with_query() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn test_reload() -> TestResult {
+    let p = polar();
+    let source = |filename: &str, src: &str| Source {
+        filename: Some(filename.to_owned()),
+        src: src.to_owned(),
+    };
+    p.load(vec![source("a.polar", "f(1);"), source("b.polar", "f(2);")])?;
+    qvar(&p, "f(x)", "x", values![1, 2]);
+
+    // Reloading a policy with errors keeps the previous one.
+    assert!(p.reload(vec![source("a.polar", "f(1)")]).is_err());
+    qvar(&p, "f(x)", "x", values![1, 2]);
+
+    p.reload(vec![source("a.polar", "f(3);")])?;
+    qvar(&p, "f(x)", "x", values![3]);
+
+    // Replaced sources keep their place in the policy.
+    p.replace_source(source("b.polar", "f(4);"))?;
+    p.replace_source(source("a.polar", "f(5);"))?;
+    qvar(&p, "f(x)", "x", values![5, 4]);
+    assert!(p.replace_source(source("b.polar", "f(")).is_err());
+    qvar(&p, "f(x)", "x", values![5, 4]);
+
+    p.remove_source("a.polar")?;
+    qvar(&p, "f(x)", "x", values![4]);
+    assert!(p.loaded_sources() == vec![source("b.polar", "f(4);")]);
+
+    // Concurrent reloads don't lose each other's changes.
+    let p = Arc::new(p);
+    let threads: Vec<_> = (0..8)
+        .map(|i| {
+            let p = p.clone();
+            std::thread::spawn(move || {
+                for j in 0..10 {
+                    let filename = format!("{}-{}.polar", i, j);
+                    p.replace_source(source(&filename, &format!("g({});", i * 10 + j)))
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(p.loaded_sources().len(), 81);
+    assert_eq!(vars(&p, "g(x)", &["x"]).len(), 80);
+    Ok(())
+}
+
+#[test]
+fn test_query_options() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"loop(x) if loop(x);
+           deep(0);
+           deep(n) if n > 0 and deep(n - 1) and true;
+           many(x) if x = 1 or x = 2 or x = 3 or x = 4;"#,
+    )?;
+    let error = |src: &str, options: QueryOptions| {
+        let mut query = p.new_query_with_options(src, false, &options).unwrap();
+        match query.next_event().unwrap_err().0 {
+            ErrorKind::Runtime(e) => e,
+            e => panic!("Expected ErrorKind::Runtime; was: {}", e),
+        }
+    };
+
+    assert!(matches!(
+        error(
+            "loop(1)",
+            QueryOptions {
+                max_goals: Some(100),
+                ..Default::default()
+            }
+        ),
+        GoalLimitExceeded { limit: 100 }
+    ));
+    assert!(matches!(
+        error(
+            "deep(100)",
+            QueryOptions {
+                max_stack_depth: Some(50),
+                ..Default::default()
+            }
+        ),
+        StackOverflow { .. }
+    ));
+    assert!(matches!(
+        error(
+            "many(x) and many(y) and x = 5",
+            QueryOptions {
+                max_choices: Some(1),
+                ..Default::default()
+            }
+        ),
+        StackOverflow { .. }
+    ));
+    let timeout = error(
+        "loop(1)",
+        QueryOptions {
+            timeout_ms: Some(10),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(timeout, QueryTimeout { timeout: 10, .. }));
+    // The hint names where the timeout was set.
+    assert!(timeout.to_string().contains("QueryOptions::timeout_ms"));
+
+    // Limits that aren't exceeded don't change results.
+    let options = QueryOptions {
+        max_goals: Some(10_000),
+        max_choices: Some(100),
+        ..Default::default()
+    };
+    let query = p.new_query_with_options("many(x)", false, &options)?;
+    assert_eq!(query_results!(query).len(), 4);
+
+    let mut query = p.new_query("loop(1)", false)?;
+    let handle = query.cancellation_handle();
+    std::thread::spawn(move || handle.cancel()).join().unwrap();
+    assert!(matches!(
+        query.next_event().unwrap_err().0,
+        ErrorKind::Runtime(QueryCancelled)
+    ));
+    Ok(())
+}
+
 /// Test using a constructor with positional + kwargs.
 #[test]
 fn test_make_external() -> TestResult {
@@ -1309,8 +1549,12 @@ fn test_arithmetic() -> TestResult {
     qeval(&p, "odd(3)");
     qnull(&p, "odd(4)");
 
-    qruntime!("9223372036854775807 + 1 > 0", ArithmeticError { .. });
-    qruntime!("-9223372036854775807 - 2 < 0", ArithmeticError { .. });
+    // With the `bignum` feature, integers that overflow are promoted to big integers instead.
+    #[cfg(not(feature = "bignum"))]
+    {
+        qruntime!("9223372036854775807 + 1 > 0", ArithmeticError { .. });
+        qruntime!("-9223372036854775807 - 2 < 0", ArithmeticError { .. });
+    }
 
     // x / 0 = ∞
     qvar(&p, "x=1/0", "x", values![f64::INFINITY]);
@@ -1321,6 +1565,55 @@ fn test_arithmetic() -> TestResult {
     Ok(())
 }
 
+#[cfg(feature = "bignum")]
+#[test]
+fn test_big_numbers() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"big(x) if x > 9223372036854775807;
+           total(items, sum) if sum(price, item in items and price = item.price, sum);"#,
+    )?;
+
+    // Integers that overflow are promoted, and demoted again when they fit.
+    qeval(&p, "9223372036854775807 + 1 = 9223372036854775808");
+    qeval(&p, "-9223372036854775807 - 2 = -9223372036854775809");
+    qeval(&p, "9223372036854775808 - 1 = 9223372036854775807");
+    qeval(
+        &p,
+        "x = 9223372036854775807 * 2 and x / 2 = 9223372036854775807.0",
+    );
+    qeval(&p, "big(18446744073709551616) and not big(1)");
+    qeval(&p, "18446744073709551616 matches Integer");
+    qeval(&p, "5N = 5 and 5N matches Integer");
+
+    // Decimals are exact.
+    qeval(&p, "0.1M + 0.2M = 0.3M");
+    qnull(&p, "0.1 + 0.2 = 0.3");
+    qeval(
+        &p,
+        "19.99M * 3 = 59.97M and 10M / 4 = 2.5M and 7.5M mod 2 = 1.5M",
+    );
+    qeval(&p, "-1.50M < 0 and 1.50M = 1.5M and 2M = 2");
+    qeval(&p, "1.5M = 1.5 and 0.1M != 0.1 and 0.1M > 0.1 - 1e-17");
+    qeval(&p, "0.1M + 0.5 = 0.6");
+    qeval(
+        &p,
+        "1M < 9223372036854775808 and 1e300 > 9223372036854775808",
+    );
+    qeval(&p, r#"total([{price: 0.10M}, {price: 0.20M}], 0.30M)"#);
+    qvar(
+        &p,
+        "x in [1.5M, 1.5, 2] and x = 1.5",
+        "x",
+        values![Value::Number(Numeric::Decimal("1.5".parse().unwrap())), 1.5],
+    );
+
+    qruntime!("1M / 0 = _", ArithmeticError { .. });
+    qruntime!("1M mod 0 = _", ArithmeticError { .. });
+    qruntime!("9223372036854775808 rem 0 = _", ArithmeticError { .. });
+    Ok(())
+}
+
 #[test]
 fn test_debug_break_on_error() -> TestResult {
     let p = polar();
@@ -2205,6 +2498,187 @@ fn test_forall() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn test_aggregates() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"owner(1, "alice");
+           owner(1, "bob");
+           owner(2, "carol");
+           score("alice", 3);
+           score("bob", 5);
+           few_owners(r) if count(u, owner(r, u), n) and n <= 2;
+           total(r, t) if sum(s, owner(r, u) and score(u, s), t);"#,
+    )?;
+
+    qvar(&p, "count(u, owner(1, u), n)", "n", values![2]);
+    qvar(&p, "count(u, owner(3, u), n)", "n", values![0]);
+    qvar(
+        &p,
+        "collect(u, owner(1, u), l)",
+        "l",
+        vec![value!(["alice", "bob"])],
+    );
+    qvar(&p, "collect(u, owner(3, u), l)", "l", vec![value!([])]);
+    qvar(&p, "total(1, t)", "t", values![8]);
+    qvar(&p, "total(2, t)", "t", values![0]);
+    qvar(&p, "min(s, score(_, s), m)", "m", values![3]);
+    qvar(&p, "max(s, score(_, s), m)", "m", values![5]);
+    qvar(&p, r#"max(u, owner(_, u), m)"#, "m", values!["carol"]);
+    qnull(&p, r#"max(s, score("carol", s), m)"#);
+    qeval(&p, "few_owners(1)");
+    qnull(&p, "count(u, owner(1, u), 3)");
+
+    // Bindings made while solving the goal are not kept.
+    qvar(&p, "count(u, owner(1, u), 2) and u = 1", "u", values![1]);
+
+    // Lookups in the value and the goal are made for each solution.
+    qvar(
+        &p,
+        "collect(x.n, x in [{n: 1}, {n: 2}, {n: 3}] and x.n > 1, l)",
+        "l",
+        vec![value!([2, 3])],
+    );
+    qvar(
+        &p,
+        "d = {owners: [1, 2]} and count(u, u in d.owners, n)",
+        "n",
+        values![2],
+    );
+
+    let mut q = p.new_query(r#"sum(x, x in [1, "two"], s)"#, false)?;
+    let err = q.next_event().unwrap_err();
+    assert!(matches!(
+        err.0,
+        ErrorKind::Runtime(RuntimeError::TypeError { .. })
+    ));
+
+    // A rule with the name of an aggregate takes precedence.
+    p.clear_rules();
+    p.load_str("count(_, _, 42);")?;
+    qvar(&p, "count(u, owner(1, u), n)", "n", values![42]);
+
+    // And may be called with values rather than a goal.
+    p.clear_rules();
+    p.load_str(
+        r#"max(a, b, a) if a >= b;
+           max(a, b, b) if a < b;
+           f(m) if max(1, 2, m);
+           g(x, m) if max(x.n, x.n + 1, m);"#,
+    )?;
+    qvar(&p, "f(m)", "m", values![2]);
+    qvar(&p, "g({n: 3}, m)", "m", values![4]);
+
+    // The built-in aggregates need a goal.
+    p.clear_rules();
+    for query in ["max(x, 2, m)", "d = {n: 1} and count(x, d.n, n)"] {
+        let mut q = p.new_query(query, false)?;
+        let err = q.next_event().unwrap_err();
+        assert!(matches!(
+            err.0,
+            ErrorKind::Runtime(RuntimeError::TypeError { .. })
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_matches_regex() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"slug(s) if s matches_regex "^[a-z0-9-]+$";
+           not_slug(s) if not s matches_regex "^[a-z0-9-]+$";"#,
+    )?;
+
+    qeval(&p, r#""oso" matches_regex "s""#);
+    qnull(&p, r#""oso" matches_regex "^s""#);
+    qeval(&p, r#"slug("polar-core")"#);
+    qnull(&p, r#"slug("Polar Core")"#);
+    qeval(&p, r#"not_slug("Polar Core")"#);
+    qvar(
+        &p,
+        r#"x in ["a1", "b2", "c"] and x matches_regex "\\d""#,
+        "x",
+        values!["a1", "b2"],
+    );
+
+    // Patterns computed at runtime are compiled when they are matched.
+    qeval(&p, r#"p = "^o" and "oso" matches_regex p"#);
+    let mut q = p.new_query(r#"p = "(" and "oso" matches_regex p"#, false)?;
+    let err = q.next_event().unwrap_err();
+    assert!(matches!(
+        err.0,
+        ErrorKind::Runtime(RuntimeError::InvalidRegex { .. })
+    ));
+
+    let mut q = p.new_query(r#"1 matches_regex "1""#, false)?;
+    let err = q.next_event().unwrap_err();
+    assert!(matches!(
+        err.0,
+        ErrorKind::Runtime(RuntimeError::TypeError { .. })
+    ));
+
+    // Invalid literal patterns are rejected when the policy is loaded.
+    qvalidation!(
+        r#"f(s) if s matches_regex "[a-";"#,
+        InvalidRegexPattern { .. },
+        "Invalid regex"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_timestamps_and_durations() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"expired(token) if token.expires_at < now();
+           recent(t) if now() - t <= 30d;"#,
+    )?;
+
+    qeval(&p, "@2024-01-01 = @2024-01-01T00:00:00Z");
+    qeval(&p, "@2024-01-01T02:00:00+02:00 = @2024-01-01T00:00:00Z");
+    qeval(&p, "@2024-01-01 < @2024-01-01T00:00:00.000000001Z");
+    qeval(&p, "90m = 1h + 30m and 1h > 59m and -1s < 0s");
+    qeval(&p, "@2024-01-01 + 30d = @2024-01-31");
+    qeval(&p, "30d + @2024-01-01 = @2024-01-31");
+    qeval(&p, "@2024-03-01 - @2024-02-01 = 29d");
+    qeval(&p, "1d * 7 = 7 * 1d and 1d / 4 = 6h and 250ms * 4 = 1s");
+    qnull(&p, "1d = 86400");
+
+    // `now()` reads the clock set on the knowledge base.
+    p.set_clock(Arc::new(FixedClock(
+        Timestamp::from_unix(1_704_067_200, 0).unwrap(),
+    )));
+    qeval(&p, "now() = @2024-01-01");
+    qeval(&p, "now(t) and t = @2024-01-01");
+    qeval(&p, "recent(@2023-12-15)");
+    qnull(&p, "recent(@2023-11-15)");
+    qeval(&p, "expired({expires_at: @2023-12-31T23:59:59Z})");
+    qnull(&p, "expired({expires_at: @2024-01-01T00:00:01Z})");
+
+    qruntime!(
+        "d = 1d * 100000000000000 and @2024-01-01 + d + d = _",
+        ArithmeticError { .. }
+    );
+    qruntime!("1d * 9223372036854775807 = _", ArithmeticError { .. });
+    qruntime!("1d / 0 = _", ArithmeticError { .. });
+    qruntime!("@2024-01-01 * 2 = _", Unsupported { .. });
+
+    qparse!("f(x) if x = @2024-13-01;", InvalidTimestamp { .. });
+    qparse!("f(x) if x = @tomorrow;", InvalidTimestamp { .. });
+    qparse!("f(x) if x = 9223372036854775807d;", DurationOverflow { .. });
+    Ok(())
+}
+
+#[test]
+fn test_now_rule_overrides_builtin() -> TestResult {
+    let p = polar();
+    p.load_str("now(@2000-01-01);")?;
+    qeval(&p, "now(t) and t = @2000-01-01");
+    qeval(&p, "now() = @2000-01-01");
+    Ok(())
+}
+
 #[test]
 fn test_emoji_policy() -> TestResult {
     let p = polar();
@@ -2765,3 +3239,127 @@ fn test_internal_isa_check() -> TestResult {
     assert_eq!(results.len(), 1);
     Ok(())
 }
+
+#[test]
+fn test_tabled_recursion() -> TestResult {
+    let p = polar();
+    p.load_str(
+        r#"edge(1, 2);
+           edge(2, 3);
+           edge(3, 1);
+           edge(3, 4);
+           left(x, y) if left(x, z) and edge(z, y);
+           left(x, y) if edge(x, y);
+           right(x, y) if edge(x, y);
+           right(x, y) if edge(x, z) and right(z, y);
+           even(x, y) if x = y;
+           even(x, y) if edge(x, z) and odd(z, y);
+           odd(x, y) if edge(x, z) and even(z, y);"#,
+    )?;
+    for rule in ["left", "right", "even", "odd"] {
+        p.table_rule(sym!(rule));
+    }
+    for rule in ["left", "right"] {
+        let reachable = var(&p, &format!("{}(1, y)", rule), "y");
+        assert_eq!(reachable.len(), 4);
+        assert_eq!(
+            HashSet::from_iter(reachable),
+            hashset! {value!(1), value!(2), value!(3), value!(4)}
+        );
+        qnull(&p, &format!("{}(4, _)", rule));
+        qeval(&p, &format!("{}(2, 4)", rule));
+        assert_eq!(vars(&p, &format!("{}(x, y)", rule), &["x", "y"]).len(), 12);
+    }
+
+    // Mutually recursive rules.
+    let odd = var(&p, "odd(1, y)", "y");
+    assert_eq!(odd.len(), 4);
+    assert_eq!(
+        HashSet::from_iter(odd),
+        hashset! {value!(1), value!(2), value!(3), value!(4)}
+    );
+    Ok(())
+}
+
+#[test]
+fn test_tabled_query_limits() -> TestResult {
+    let p = polar();
+    let edges: String = (0..200)
+        .map(|i| format!("edge({}, {});", i, i + 1))
+        .collect();
+    p.load_str(&format!(
+        r#"{}
+           path(x, y) if edge(x, y);
+           path(x, y) if path(x, z) and edge(z, y);
+           loop(x) if loop(x);
+           spin(x) if loop(x);"#,
+        edges
+    ))?;
+    p.table_rule(sym!("path"));
+    p.table_rule(sym!("spin"));
+    let error = |query: &mut Query| match query.next_event().unwrap_err().0 {
+        ErrorKind::Runtime(e) => e,
+        e => panic!("Expected ErrorKind::Runtime; was: {}", e),
+    };
+
+    // Tabled rules are evaluated in a sub-VM, which counts against the limits of the query.
+    let options = QueryOptions {
+        max_goals: Some(50),
+        ..Default::default()
+    };
+    let mut query = p.new_query_with_options("path(0, 150)", false, &options)?;
+    assert!(matches!(error(&mut query), GoalLimitExceeded { limit: 50 }));
+
+    let options = QueryOptions {
+        timeout_ms: Some(10),
+        ..Default::default()
+    };
+    let mut query = p.new_query_with_options("spin(1)", false, &options)?;
+    assert!(matches!(
+        error(&mut query),
+        QueryTimeout { timeout: 10, .. }
+    ));
+
+    let mut query = p.new_query("spin(1)", false)?;
+    let handle = query.cancellation_handle();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        handle.cancel();
+    });
+    assert!(matches!(error(&mut query), QueryCancelled));
+    canceller.join().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_tabled_answers_are_reused() -> TestResult {
+    let p = polar();
+    p.register_constant(
+        sym!("store"),
+        term!(Value::ExternalInstance(ExternalInstance {
+            instance_id: 1,
+            constructor: None,
+            repr: None,
+            class_repr: None,
+            class_id: None,
+        })),
+    )?;
+    p.load_str("price(x) if x = store.price;")?;
+
+    let count_lookups = |p: &Polar| {
+        let mut lookups = 0;
+        let query = p
+            .new_query("price(x) and price(y) and x = y", false)
+            .unwrap();
+        let results = query_results!(query, |_, _, _, _, _| {
+            lookups += 1;
+            Some(term!(10))
+        });
+        assert_eq!(results.len(), 1);
+        lookups
+    };
+    assert_eq!(count_lookups(&p), 2);
+    p.table_rule(sym!("price"));
+    assert_eq!(count_lookups(&p), 1);
+    Ok(())
+}
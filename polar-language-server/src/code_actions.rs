@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use lsp_types::{
+    CodeAction, CodeActionKind, Diagnostic, NumberOrString, Range, TextEdit, Url, WorkspaceEdit,
+};
+use polar_core::{
+    diagnostic::Diagnostic as PolarDiagnostic,
+    error::{ErrorKind::Validation, ValidationError::SingletonVariable},
+    kb::KnowledgeBase,
+    resource_block::{ACTOR_UNION_NAME, RESOURCE_UNION_NAME},
+    sources::Context,
+    terms::{Symbol, Term, Value},
+    warning::{common_specializer_misspellings, ValidationWarning::*},
+};
+
+use crate::{
+    helpers::{is_registered_class, range_from_context, range_from_polar_diagnostic_context},
+    navigation::name_context,
+};
+
+/// The rule recommended by the `MissingAllowRule` & `MissingHasPermissionRule` warnings.
+const ALLOW_RULE: &str = "allow(actor, action, resource) if
+  has_permission(actor, action, resource);
+
+";
+
+/// Quick fixes for the `diagnostics` that overlap `range` in the doc at `uri`. Fixes link to the
+/// matching diagnostics in `reported`, the diagnostics the client reported for `range`.
+pub(crate) fn code_actions(
+    kb: &KnowledgeBase,
+    diagnostics: &[PolarDiagnostic],
+    uri: &Url,
+    range: Range,
+    reported: &[Diagnostic],
+) -> Vec<CodeAction> {
+    let mut actions: Vec<CodeAction> = vec![];
+    for diagnostic in diagnostics {
+        // Diagnostics without a context apply to every doc.
+        let context = diagnostic.get_context();
+        let filename = context.as_ref().and_then(|c| c.source.filename.as_deref());
+        if context.is_some() && filename != Some(uri.as_str()) {
+            continue;
+        }
+        let diagnostic_range = range_from_polar_diagnostic_context(diagnostic);
+        if !overlaps(diagnostic_range, range) {
+            continue;
+        }
+
+        let (title, edits) = match fix(kb, diagnostic) {
+            Some(fix) => fix,
+            None => continue,
+        };
+        // E.g., `MissingAllowRule` & `MissingHasPermissionRule` share a fix.
+        if actions.iter().any(|action| action.title == title) {
+            continue;
+        }
+
+        let code = Some(NumberOrString::String(diagnostic.kind()));
+        let linked: Vec<_> = reported
+            .iter()
+            .filter(|reported| reported.range == diagnostic_range && reported.code == code)
+            .cloned()
+            .collect();
+        actions.push(CodeAction {
+            title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: (!linked.is_empty()).then_some(linked),
+            edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+            ..Default::default()
+        });
+    }
+    actions
+}
+
+/// The title & edits of the fix for `diagnostic`, if there is one.
+fn fix(kb: &KnowledgeBase, diagnostic: &PolarDiagnostic) -> Option<(String, Vec<TextEdit>)> {
+    match diagnostic {
+        PolarDiagnostic::Warning(warning) => match &warning.0 {
+            MissingAllowRule | MissingHasPermissionRule => {
+                let edit = TextEdit::new(Range::default(), ALLOW_RULE.to_owned());
+                let title = "Add an `allow` rule that calls `has_permission`";
+                Some((title.to_owned(), vec![edit]))
+            }
+            AmbiguousPrecedence { term } => {
+                let context = term.parsed_context()?;
+                let edits = vec![
+                    insert(context, context.left, "("),
+                    insert(context, context.right, ")"),
+                ];
+                Some(("Add parentheses".to_owned(), edits))
+            }
+            UnknownSpecializer { term, sym } => {
+                let class = closest_class(kb, term, sym)?;
+                let context = name_context(term.parsed_context()?, sym)?;
+                let edit = TextEdit::new(range_from_context(&context), class.clone());
+                Some((format!("Change to `{}`", class), vec![edit]))
+            }
+        },
+        PolarDiagnostic::Error(error) => match &error.0 {
+            Validation(SingletonVariable { term }) => {
+                let name = match term.value() {
+                    Value::Variable(name) | Value::RestVariable(name) => name,
+                    _ => return None,
+                };
+                let context = term.parsed_context()?;
+                // Rest variables are prefixed after the `*`, e.g., `*_rest`.
+                let src = context.source.src.get(context.left..)?;
+                let offset = context.left + src.starts_with('*') as usize;
+                let title = format!("Prefix `{}` with `_`", name);
+                Some((title, vec![insert(context, offset, "_")]))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// The class `sym` most likely meant: a built-in class for a common name from another language,
+/// e.g., `Integer` for `int`, or else the closest registered class, resource block, or union
+/// within a few edits of `sym`. `None` if `sym` is already one of those.
+fn closest_class(kb: &KnowledgeBase, term: &Term, sym: &Symbol) -> Option<String> {
+    if let Some(class) = common_specializer_misspellings(term) {
+        return Some(class.to_owned());
+    }
+
+    let registered = kb.get_registered_constants().iter();
+    let registered =
+        registered.filter_map(|(name, value)| is_registered_class(value).then_some(name));
+    let blocks = kb.resource_blocks.declarations().keys();
+    let blocks = blocks.filter_map(|resource| match resource.value() {
+        Value::Variable(name) => Some(name),
+        _ => None,
+    });
+    let mut classes: Vec<_> = registered
+        .chain(blocks)
+        .map(|name| name.0.clone())
+        .collect();
+    classes.extend([ACTOR_UNION_NAME, RESOURCE_UNION_NAME].map(str::to_owned));
+    if classes.contains(&sym.0) {
+        return None;
+    }
+
+    let max_distance = (sym.0.chars().count() / 3).max(1);
+    let sym = sym.0.to_lowercase();
+    classes
+        .into_iter()
+        .map(|class| (edit_distance(&sym, &class.to_lowercase()), class))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, class)| class)
+}
+
+/// The Levenshtein distance between `a` & `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut row: Vec<_> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + (a != *b) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+fn overlaps(l: Range, r: Range) -> bool {
+    l.start <= r.end && r.start <= l.end
+}
+
+/// Insert `text` at `offset` in the source of `context`.
+fn insert(context: &Context, offset: usize, text: &str) -> TextEdit {
+    let context = Context {
+        source: context.source.clone(),
+        left: offset,
+        right: offset,
+    };
+    TextEdit::new(range_from_context(&context), text.to_owned())
+}
@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Position, Range, TextEdit,
+};
+use polar_core::{
+    kb::KnowledgeBase,
+    resource_block::{Declaration, ACTOR_UNION_NAME, RESOURCE_UNION_NAME},
+    terms::{Term, Value},
+    KEYWORDS,
+};
+
+use crate::{helpers::is_registered_class, hover::rule_type};
+
+/// Completions at `offset` in `src`, the contents of the source file named `filename`.
+///
+/// Inside a resource block, suggests the block's declaration keywords and the roles, permissions,
+/// and relations that can appear at that point in a shorthand rule. Elsewhere, suggests rules,
+/// classes, constants, and keywords.
+pub(crate) fn completions(
+    kb: &KnowledgeBase,
+    filename: &str,
+    src: &str,
+    offset: usize,
+    position: Position,
+) -> Vec<CompletionItem> {
+    let line_start = src[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = src[offset..].find('\n').map_or(src.len(), |i| offset + i);
+    let before = &src[line_start..offset];
+    let after = &src[offset..line_end];
+
+    let (in_string, in_comment) = scan(before);
+    if in_comment {
+        return vec![];
+    }
+
+    // The partially typed word before the cursor, including its opening quote if it's a string.
+    let partial = if in_string {
+        &before[before.rfind('"').unwrap_or_default()..]
+    } else {
+        let word = before.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+        &before[word.len()..]
+    };
+    let preceding = &before[..before.len() - partial.len()];
+
+    let items = if let Some(resource) = enclosing_block(kb, filename, src, offset) {
+        // Replace the partial string and an auto-inserted closing quote.
+        let start = position.character - partial.chars().count() as u32;
+        let end = position.character + (in_string && after.starts_with('"')) as u32;
+        let range = Range::new(
+            Position::new(position.line, start),
+            Position::new(position.line, end),
+        );
+        block_completions(kb, resource, preceding, after, range, in_string)
+    } else if in_string {
+        vec![]
+    } else {
+        policy_completions(kb, preceding)
+    };
+
+    // Deduplicate by label.
+    let items = items.into_iter().map(|item| (item.label.clone(), item));
+    items.collect::<BTreeMap<_, _>>().into_values().collect()
+}
+
+/// Whether the end of `line` is inside a string or comment.
+fn scan(line: &str) -> (bool, bool) {
+    let mut in_string = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            '#' if !in_string => return (false, true),
+            _ => (),
+        }
+    }
+    (in_string, false)
+}
+
+/// The resource whose block's braces contain `offset`.
+fn enclosing_block<'kb>(
+    kb: &'kb KnowledgeBase,
+    filename: &str,
+    src: &str,
+    offset: usize,
+) -> Option<&'kb Term> {
+    kb.resource_blocks.declarations().keys().find(|resource| {
+        resource.parsed_context().is_some_and(|context| {
+            let in_file = context.source.filename.as_deref() == Some(filename);
+            let header = src.get(context.left..offset).unwrap_or_default();
+            in_file && context.left < offset && offset < context.right && header.contains('{')
+        })
+    })
+}
+
+fn block_completions(
+    kb: &KnowledgeBase,
+    resource: &Term,
+    preceding: &str,
+    after: &str,
+    range: Range,
+    in_string: bool,
+) -> Vec<CompletionItem> {
+    let blocks = &kb.resource_blocks;
+    let mut items = vec![];
+
+    // E.g., `"reader" if "owner" on "parent";` has the head `"reader"`, the implier `"owner"`,
+    // and the relation `"parent"`.
+    let (resource, kinds): (&Term, fn(&Declaration) -> bool) =
+        match preceding.split_whitespace().last() {
+            // Heads are roles or permissions.
+            None => {
+                if !in_string {
+                    for keyword in ["roles", "permissions", "relations"] {
+                        items.push(keyword_item(keyword));
+                    }
+                }
+                (resource, |d| !matches!(d, Declaration::Relation(_)))
+            }
+            // Impliers are declared in the related resource's block when followed by a relation.
+            Some("if") => {
+                let related = relation_after(after).and_then(|relation| {
+                    let relation = Term::new_temporary(Value::String(relation.to_owned()));
+                    let related = blocks.get_relation_type_in_resource_block(&relation, resource);
+                    related.ok()
+                });
+                (related.unwrap_or(resource), |_| true)
+            }
+            Some("on") => (resource, |d| matches!(d, Declaration::Relation(_))),
+            _ => return items,
+        };
+
+    let declarations = blocks.declarations().get(resource);
+    for (name, declaration) in declarations.into_iter().flatten() {
+        if !kinds(declaration) {
+            continue;
+        }
+        let (kind, detail) = match declaration {
+            Declaration::Role => (CompletionItemKind::EnumMember, "role".to_owned()),
+            Declaration::Permission => (CompletionItemKind::EnumMember, "permission".to_owned()),
+            Declaration::Relation(related) => (
+                CompletionItemKind::Field,
+                format!("relation to {}", related),
+            ),
+        };
+        let label = name.to_string();
+        items.push(CompletionItem {
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                range,
+                label.clone(),
+            ))),
+            label,
+            kind: Some(kind),
+            detail: Some(format!("{} in {}", detail, resource)),
+            ..Default::default()
+        });
+    }
+    items
+}
+
+/// The relation following the implier in a shorthand rule, e.g., `parent` in `"owner" on "parent";`.
+fn relation_after(after: &str) -> Option<&str> {
+    let mut words = after.split_whitespace().skip_while(|word| *word != "on");
+    words.next()?;
+    let relation = words.next()?.trim_end_matches(';');
+    relation.strip_prefix('"')?.strip_suffix('"')
+}
+
+fn policy_completions(kb: &KnowledgeBase, preceding: &str) -> Vec<CompletionItem> {
+    let preceding = preceding.trim_end();
+    let specializer = preceding.ends_with(':') || preceding.ends_with("matches");
+
+    let mut items = vec![];
+
+    let blocks = &kb.resource_blocks;
+    for resource in blocks.declarations().keys() {
+        let detail = if blocks.actors.contains(resource) {
+            "actor"
+        } else {
+            "resource"
+        };
+        items.push(item(
+            resource.to_string(),
+            CompletionItemKind::Class,
+            detail,
+        ));
+    }
+    if !blocks.resources.is_empty() {
+        for union in [ACTOR_UNION_NAME, RESOURCE_UNION_NAME] {
+            items.push(item(union.to_owned(), CompletionItemKind::Class, "union"));
+        }
+    }
+    for (name, value) in kb.get_registered_constants() {
+        if is_registered_class(value) {
+            items.push(item(name.to_string(), CompletionItemKind::Class, "class"));
+        } else if !specializer {
+            items.push(item(
+                name.to_string(),
+                CompletionItemKind::Constant,
+                "constant",
+            ));
+        }
+    }
+
+    if specializer {
+        return items;
+    }
+
+    let rule_names = kb.get_rules().keys().chain(kb.get_rule_type_names());
+    for name in rule_names {
+        let detail = match kb.get_rule_types(name).and_then(|types| types.first()) {
+            Some(first) => rule_type(first),
+            None => "rule".to_owned(),
+        };
+        items.push(CompletionItem {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::Function),
+            detail: Some(detail),
+            ..Default::default()
+        });
+    }
+    for keyword in KEYWORDS {
+        items.push(keyword_item(keyword));
+    }
+    items
+}
+
+fn item(label: String, kind: CompletionItemKind, detail: &str) -> CompletionItem {
+    CompletionItem {
+        label,
+        kind: Some(kind),
+        detail: Some(detail.to_owned()),
+        ..Default::default()
+    }
+}
+
+fn keyword_item(keyword: &str) -> CompletionItem {
+    item(keyword.to_owned(), CompletionItemKind::Keyword, "keyword")
+}
@@ -1,7 +1,11 @@
 use std::collections::{BTreeMap, HashSet};
 
-use lsp_types::{Position, PublishDiagnosticsParams, Range, TextDocumentItem, Url};
-use polar_core::diagnostic::Diagnostic;
+use lsp_types::{Location, Position, PublishDiagnosticsParams, Range, TextDocumentItem, Url};
+use polar_core::{
+    diagnostic::Diagnostic,
+    sources::Context,
+    terms::{ExternalInstance, Term, Value},
+};
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
@@ -26,20 +30,44 @@ pub(crate) type Documents = BTreeMap<Url, TextDocumentItem>;
 pub(crate) type Diagnostics = BTreeMap<Url, PublishDiagnosticsParams>;
 
 pub(crate) fn range_from_polar_diagnostic_context(diagnostic: &Diagnostic) -> Range {
-    use polar_core::loc_to_pos;
-
     diagnostic
         .get_context()
-        .map(|context| {
-            let (row, column) = loc_to_pos(&context.source.src, context.left);
-            let start = Position::new(row as _, column as _);
-            let (row, column) = loc_to_pos(&context.source.src, context.right);
-            let end = Position::new(row as _, column as _);
-            Range { start, end }
-        })
+        .map(|context| range_from_context(&context))
         .unwrap_or_default()
 }
 
+pub(crate) fn range_from_context(context: &Context) -> Range {
+    use polar_core::loc_to_pos;
+
+    let (row, column) = loc_to_pos(&context.source.src, context.left);
+    let start = Position::new(row as _, column as _);
+    let (row, column) = loc_to_pos(&context.source.src, context.right);
+    let end = Position::new(row as _, column as _);
+    Range { start, end }
+}
+
+/// Returns `None` if `context`'s source doesn't have a filename that parses as a URI.
+pub(crate) fn location_from_context(context: &Context) -> Option<Location> {
+    let filename = context.source.filename.as_ref()?;
+    let uri = Url::parse(filename).ok()?;
+    Some(Location::new(uri, range_from_context(context)))
+}
+
+/// Translate a `Position` in `src` into a byte offset, the unit used by parsed `Context`s.
+///
+/// Positions past the end of a line or document are clamped to the end of that line or document.
+pub(crate) fn offset_from_position(src: &str, position: Position) -> usize {
+    let lines = src.split_inclusive('\n');
+    let line_start: usize = lines.take(position.line as _).map(str::len).sum();
+    let line = src[line_start..].split('\n').next().unwrap_or_default();
+    let column = line
+        .char_indices()
+        .map(|(i, _)| i)
+        .nth(position.character as _)
+        .unwrap_or(line.len());
+    line_start + column
+}
+
 pub(crate) fn uri_from_polar_diagnostic_context(diagnostic: &Diagnostic) -> Option<Url> {
     if let Some(context) = diagnostic.get_context() {
         if let Some(filename) = context.source.filename.as_ref() {
@@ -71,6 +99,22 @@ pub(crate) fn empty_diagnostics_for_doc(
     (uri.clone(), params)
 }
 
+/// Replace the contents of `line` in `src` with spaces, preserving the byte offsets of everything
+/// else in `src`.
+pub(crate) fn blank_line(src: &str, line: u32) -> String {
+    src.split_inclusive('\n')
+        .enumerate()
+        .map(|(i, text)| {
+            if i == line as usize {
+                let (text, newline) = text.split_at(text.trim_end_matches(['\r', '\n']).len());
+                " ".repeat(text.len()) + newline
+            } else {
+                text.to_owned()
+            }
+        })
+        .collect()
+}
+
 #[derive(Default, Serialize)]
 pub(crate) struct LspEvent<'a> {
     pub(crate) lsp_method: &'a str,
@@ -83,3 +127,12 @@ pub(crate) fn unique_extensions(uris: &[&Url]) -> HashSet<String> {
         .map(|(_, suffix)| suffix.into())
         .collect()
 }
+
+/// Whether `value`, a registered constant, is a class rather than an instance.
+pub(crate) fn is_registered_class(value: &Term) -> bool {
+    matches!(
+        value.value(),
+        Value::ExternalInstance(ExternalInstance { class_id: Some(class_id), instance_id, .. })
+            if class_id == instance_id
+    )
+}
@@ -0,0 +1,177 @@
+use polar_core::{
+    kb::KnowledgeBase,
+    resource_block::Declaration,
+    rules::Rule,
+    sources::Context,
+    terms::{InstanceLiteral, Pattern, Symbol, Term, Value},
+};
+
+use crate::navigation::Target;
+
+/// Markdown describing `target`, or `None` if there's nothing to say about it.
+pub(crate) fn hover(kb: &KnowledgeBase, target: &Target) -> Option<String> {
+    match target {
+        Target::Rule(name) => Some(rule_hover(kb, name)),
+        Target::Declaration(resource, declaration) => declaration_hover(kb, resource, declaration),
+        Target::Class(class) => class_hover(kb, class),
+    }
+}
+
+/// The rule types for `name` followed by the location & leading comment block of each rule.
+fn rule_hover(kb: &KnowledgeBase, name: &Symbol) -> String {
+    let mut sections = vec![];
+
+    if let Some(rule_types) = kb.get_rule_types(name) {
+        let rule_types = rule_types.iter().map(rule_type);
+        sections.push(polar_block(rule_types.collect::<Vec<_>>().join("\n")));
+    }
+
+    let mut contexts: Vec<_> = kb
+        .get_generic_rule(name)
+        .map_or_else(Vec::new, |generic_rule| {
+            let rules = generic_rule.rules.values();
+            rules.filter_map(|rule| rule.parsed_context()).collect()
+        });
+    contexts.sort_by(|l, r| (&l.source.filename, l.left).cmp(&(&r.source.filename, r.left)));
+
+    let count = match contexts.len() {
+        0 => "No rules".to_owned(),
+        1 => "1 rule".to_owned(),
+        n => format!("{} rules", n),
+    };
+    sections.push(format!("{} named `{}`", count, name));
+
+    for context in contexts {
+        let mut entry = format!("- {}", link(context));
+        if let Some(comment) = leading_comment(context) {
+            entry += &format!("\n\n  {}", comment.replace('\n', "\n  "));
+        }
+        sections.push(entry);
+    }
+
+    sections.join("\n\n")
+}
+
+/// Format a rule type as it would appear in a policy, e.g., `type f(x: Foo);` instead of
+/// `type f(x: Foo{});`.
+pub(crate) fn rule_type(rule_type: &Rule) -> String {
+    let params = rule_type
+        .params
+        .iter()
+        .map(|param| match &param.specializer {
+            Some(specializer) => match specializer.value() {
+                Value::Pattern(Pattern::Instance(InstanceLiteral { tag, fields }))
+                    if fields.fields.is_empty() =>
+                {
+                    format!("{}: {}", param.parameter, tag)
+                }
+                _ => param.to_string(),
+            },
+            None => param.to_string(),
+        });
+    let params = params.collect::<Vec<_>>().join(", ");
+    format!("type {}({});", rule_type.name, params)
+}
+
+/// The declaration as it appears in its resource block.
+fn declaration_hover(kb: &KnowledgeBase, resource: &Term, declaration: &Term) -> Option<String> {
+    let declarations = kb.resource_blocks.declarations().get(resource)?;
+    let declared = match declarations.get(declaration)? {
+        Declaration::Role => format!("roles = [{}];", declaration),
+        Declaration::Permission => format!("permissions = [{}];", declaration),
+        Declaration::Relation(related) => match declaration.value() {
+            Value::String(relation) => format!("relations = {{ {}: {} }};", relation, related),
+            _ => return None,
+        },
+    };
+    let block = format!(
+        "{} {} {{\n  {}\n}}",
+        keyword(kb, resource),
+        resource,
+        declared
+    );
+    Some(polar_block(block))
+}
+
+/// The declarations in the resource block for `class`.
+fn class_hover(kb: &KnowledgeBase, class: &Symbol) -> Option<String> {
+    let (resource, declarations) =
+        kb.resource_blocks.declarations().iter().find(
+            |(resource, _)| matches!(resource.value(), Value::Variable(name) if name == class),
+        )?;
+
+    let mut roles = vec![];
+    let mut permissions = vec![];
+    let mut relations = vec![];
+    for (declaration, kind) in declarations {
+        match (kind, declaration.value()) {
+            (Declaration::Role, _) => roles.push(declaration.to_string()),
+            (Declaration::Permission, _) => permissions.push(declaration.to_string()),
+            (Declaration::Relation(related), Value::String(relation)) => {
+                relations.push(format!("{}: {}", relation, related))
+            }
+            _ => (),
+        }
+    }
+    roles.sort();
+    permissions.sort();
+    relations.sort();
+
+    let mut lines = vec![format!("{} {} {{", keyword(kb, resource), resource)];
+    if !roles.is_empty() {
+        lines.push(format!("  roles = [{}];", roles.join(", ")));
+    }
+    if !permissions.is_empty() {
+        lines.push(format!("  permissions = [{}];", permissions.join(", ")));
+    }
+    if !relations.is_empty() {
+        lines.push(format!("  relations = {{ {} }};", relations.join(", ")));
+    }
+    if lines.len() == 1 {
+        lines[0].push('}');
+    } else {
+        lines.push("}".to_owned());
+    }
+    Some(polar_block(lines.join("\n")))
+}
+
+fn keyword(kb: &KnowledgeBase, resource: &Term) -> &'static str {
+    if kb.resource_blocks.actors.contains(resource) {
+        "actor"
+    } else {
+        "resource"
+    }
+}
+
+fn polar_block(polar: String) -> String {
+    format!("```polar\n{}\n```", polar)
+}
+
+/// A Markdown link to the line where `context` starts, e.g., `[a.polar:2](file:///a.polar#L2)`.
+fn link(context: &Context) -> String {
+    let filename = context.source.filename.as_deref().unwrap_or_default();
+    let basename = filename.rsplit('/').next().unwrap_or_default();
+    let line = polar_core::loc_to_pos(&context.source.src, context.left).0 + 1;
+    format!("[{}:{}]({}#L{})", basename, line, filename, line)
+}
+
+/// The block of `#` comments on the lines immediately preceding `context`, if any.
+fn leading_comment(context: &Context) -> Option<String> {
+    let before = context.source.src.get(..context.left)?;
+    let (before, indentation) = before.rsplit_once('\n').unwrap_or(("", before));
+    // Comments only belong to a term that begins its line.
+    if !indentation.trim().is_empty() {
+        return None;
+    }
+
+    let mut comment: Vec<_> = before
+        .lines()
+        .rev()
+        .map(str::trim)
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| line.trim_start_matches('#').trim())
+        .collect();
+    comment.reverse();
+    let comment = comment.join("\n");
+    (!comment.trim().is_empty()).then_some(comment)
+}
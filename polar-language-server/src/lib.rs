@@ -5,21 +5,40 @@ use lsp_types::{
         DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidDeleteFiles,
         DidOpenTextDocument, DidSaveTextDocument, Initialized, Notification,
     },
-    DeleteFilesParams, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
-    DidChangeWatchedFilesParams, DidOpenTextDocumentParams, FileChangeType, FileDelete, FileEvent,
-    NumberOrString, PublishDiagnosticsParams, TextDocumentItem, Url,
-    VersionedTextDocumentIdentifier,
+    request::{
+        CodeActionRequest, Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest,
+        References, Request, WorkspaceSymbol,
+    },
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CompletionItem,
+    CompletionParams, CompletionResponse, DeleteFilesParams, Diagnostic, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidChangeWatchedFilesParams, DidOpenTextDocumentParams,
+    DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, FileChangeType, FileDelete,
+    FileEvent, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    Location, MarkupContent, MarkupKind, NumberOrString, PublishDiagnosticsParams, ReferenceParams,
+    SymbolInformation, TextDocumentItem, TextDocumentPositionParams, Url,
+    VersionedTextDocumentIdentifier, WorkspaceSymbolParams,
+};
+use polar_core::{
+    diagnostic::Diagnostic as PolarDiagnostic,
+    polar::Polar,
+    sources::{Context, Source},
 };
-use polar_core::{diagnostic::Diagnostic as PolarDiagnostic, polar::Polar, sources::Source};
 use serde::Serialize;
 use serde_wasm_bindgen::{from_value, to_value};
 use wasm_bindgen::prelude::*;
 
 mod helpers;
 use helpers::{
-    empty_diagnostics_for_doc, log, range_from_polar_diagnostic_context, unique_extensions,
-    uri_from_polar_diagnostic_context, Diagnostics, Documents, LspEvent,
+    blank_line, empty_diagnostics_for_doc, log, offset_from_position, range_from_context,
+    range_from_polar_diagnostic_context, unique_extensions, uri_from_polar_diagnostic_context,
+    Diagnostics, Documents, LspEvent,
 };
+mod code_actions;
+mod completion;
+mod hover;
+mod navigation;
+use navigation::Target;
+mod symbols;
 
 #[wasm_bindgen]
 pub struct PolarLanguageServer {
@@ -169,6 +188,87 @@ impl PolarLanguageServer {
             _ => log("unexpected notification"),
         }
     }
+
+    /// Catch-all handler for requests sent by the LSP client.
+    ///
+    /// This function receives a request's `method` and `params`, dispatches to the appropriate
+    /// handler function based on `method`, and returns the handler's response. Requests we can't
+    /// answer get a `null` response.
+    #[allow(unused_variables)]
+    #[wasm_bindgen(js_class = PolarLanguageServer, js_name = onRequest)]
+    pub fn on_request(&self, method: &str, params: JsValue) -> JsValue {
+        log(method);
+
+        match method {
+            GotoDefinition::METHOD => {
+                let GotoDefinitionParams {
+                    text_document_position_params,
+                    ..
+                } = from_value(params).unwrap();
+                let locations = self.on_goto_definition(text_document_position_params);
+                locations.map_or(JsValue::NULL, |locations| {
+                    to_value(&GotoDefinitionResponse::Array(locations)).unwrap()
+                })
+            }
+
+            References::METHOD => {
+                let ReferenceParams {
+                    text_document_position,
+                    context,
+                    ..
+                } = from_value(params).unwrap();
+                let locations =
+                    self.on_references(text_document_position, context.include_declaration);
+                locations.map_or(JsValue::NULL, |locations| to_value(&locations).unwrap())
+            }
+
+            HoverRequest::METHOD => {
+                let HoverParams {
+                    text_document_position_params,
+                    ..
+                } = from_value(params).unwrap();
+                let hover = self.on_hover(text_document_position_params);
+                hover.map_or(JsValue::NULL, |hover| to_value(&hover).unwrap())
+            }
+
+            Completion::METHOD => {
+                let CompletionParams {
+                    text_document_position,
+                    ..
+                } = from_value(params).unwrap();
+                let items = self.on_completion(text_document_position);
+                items.map_or(JsValue::NULL, |items| {
+                    to_value(&CompletionResponse::Array(items)).unwrap()
+                })
+            }
+
+            DocumentSymbolRequest::METHOD => {
+                let DocumentSymbolParams { text_document, .. } = from_value(params).unwrap();
+                let symbols = self.on_document_symbol(&text_document.uri);
+                symbols.map_or(JsValue::NULL, |symbols| {
+                    to_value(&DocumentSymbolResponse::Nested(symbols)).unwrap()
+                })
+            }
+
+            WorkspaceSymbol::METHOD => {
+                let WorkspaceSymbolParams { query, .. } = from_value(params).unwrap();
+                to_value(&self.on_workspace_symbol(&query)).unwrap()
+            }
+
+            CodeActionRequest::METHOD => {
+                let actions = self.on_code_action(from_value(params).unwrap());
+                actions.map_or(JsValue::NULL, |actions| {
+                    let actions = actions.into_iter().map(CodeActionOrCommand::CodeAction);
+                    to_value(&actions.collect::<Vec<_>>()).unwrap()
+                })
+            }
+
+            _ => {
+                log("unexpected request");
+                JsValue::NULL
+            }
+        }
+    }
 }
 
 /// Individual LSP notification handlers.
@@ -250,8 +350,110 @@ impl PolarLanguageServer {
     }
 }
 
+/// Individual LSP request handlers.
+impl PolarLanguageServer {
+    /// Returns `None` if there's nothing to navigate from at the requested position.
+    fn on_goto_definition(&self, params: TextDocumentPositionParams) -> Option<Vec<Location>> {
+        let (target, _) = self.target_at(&params)?;
+        let kb = self.polar.kb.read().unwrap();
+        Some(navigation::definitions(&kb, &target))
+    }
+
+    /// Returns `None` if there's nothing to navigate from at the requested position.
+    fn on_references(
+        &self,
+        params: TextDocumentPositionParams,
+        include_declaration: bool,
+    ) -> Option<Vec<Location>> {
+        let (target, _) = self.target_at(&params)?;
+        let kb = self.polar.kb.read().unwrap();
+        Some(navigation::references(&kb, &target, include_declaration))
+    }
+
+    /// Returns `None` if there's nothing to describe at the requested position.
+    fn on_hover(&self, params: TextDocumentPositionParams) -> Option<Hover> {
+        let (target, context) = self.target_at(&params)?;
+        let kb = self.polar.kb.read().unwrap();
+        let value = hover::hover(&kb, &target)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range_from_context(&context)),
+        })
+    }
+
+    /// Returns `None` if the requested doc isn't tracked.
+    fn on_completion(&self, params: TextDocumentPositionParams) -> Option<Vec<CompletionItem>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params;
+        let doc = self.documents.get(&text_document.uri)?;
+        let offset = offset_from_position(&doc.text, position);
+        let polar = self.load_documents_without_line(&doc.uri, position.line);
+        let kb = polar.kb.read().unwrap();
+        let uri = doc.uri.as_str();
+        Some(completion::completions(
+            &kb, uri, &doc.text, offset, position,
+        ))
+    }
+
+    /// Returns `None` if the requested doc isn't tracked.
+    fn on_document_symbol(&self, uri: &Url) -> Option<Vec<DocumentSymbol>> {
+        let doc = self.documents.get(uri)?;
+        let kb = self.polar.kb.read().unwrap();
+        Some(symbols::document_symbols(&kb, doc.uri.as_str()))
+    }
+
+    fn on_workspace_symbol(&self, query: &str) -> Vec<SymbolInformation> {
+        let kb = self.polar.kb.read().unwrap();
+        symbols::workspace_symbols(&kb, self.documents.keys(), query)
+    }
+
+    /// Returns `None` if the requested doc isn't tracked.
+    fn on_code_action(&self, params: CodeActionParams) -> Option<Vec<CodeAction>> {
+        let CodeActionParams {
+            text_document,
+            range,
+            context,
+            ..
+        } = params;
+        let doc = self.documents.get(&text_document.uri)?;
+        if let Some(only) = context.only {
+            if !only.contains(&CodeActionKind::QUICKFIX) {
+                return Some(vec![]);
+            }
+        }
+        // Load a fresh `Polar` instance to get every diagnostic, including those that
+        // `diagnostics_from_polar_diagnostic` ignores because they depend on app data.
+        let polar = Polar::default();
+        let diagnostics = polar.diagnostic_load(self.documents_to_polar_sources());
+        let kb = polar.kb.read().unwrap();
+        let reported = &context.diagnostics;
+        Some(code_actions::code_actions(
+            &kb,
+            &diagnostics,
+            &doc.uri,
+            range,
+            reported,
+        ))
+    }
+}
+
 /// Helper methods.
 impl PolarLanguageServer {
+    /// Find the rule, class, or resource block declaration referenced at a position in a tracked
+    /// doc along with the context of the referencing term.
+    fn target_at(&self, params: &TextDocumentPositionParams) -> Option<(Target, Context)> {
+        let uri = &params.text_document.uri;
+        let doc = self.documents.get(uri)?;
+        let offset = offset_from_position(&doc.text, params.position);
+        let kb = self.polar.kb.read().unwrap();
+        navigation::target_at(&kb, uri.as_str(), offset)
+    }
+
     fn upsert_document(&mut self, doc: TextDocumentItem) -> Option<TextDocumentItem> {
         self.documents.insert(doc.uri.clone(), doc)
     }
@@ -299,6 +501,7 @@ impl PolarLanguageServer {
             polar_chars: usize,
             polar_files: usize,
             rule_types: usize,
+            tests: usize,
             total_rules: usize,
         }
 
@@ -402,6 +605,7 @@ impl PolarLanguageServer {
                     }
                 }
                 Line::RuleType(_) => event.policy_stats.rule_types += 1,
+                Line::Test(_) => event.policy_stats.tests += 1,
                 Line::Rule(_) => {
                     event.policy_stats.longhand_rules += 1;
                     event.policy_stats.total_rules += 1;
@@ -507,6 +711,22 @@ impl PolarLanguageServer {
             .collect()
     }
 
+    /// Load tracked documents into a fresh `Polar` instance with `line` of `uri` blanked out. The
+    /// line being edited is often incomplete, and we don't want it to prevent the rest of its
+    /// document from loading.
+    fn load_documents_without_line(&self, uri: &Url, line: u32) -> Polar {
+        let polar = Polar::default();
+        let sources = self.documents.values().map(|doc| {
+            if &doc.uri == uri {
+                Source::new_with_name(&doc.uri, blank_line(&doc.text, line))
+            } else {
+                Source::new_with_name(&doc.uri, &doc.text)
+            }
+        });
+        polar.diagnostic_load(sources.collect());
+        polar
+    }
+
     fn load_documents(&self) -> Vec<PolarDiagnostic> {
         self.polar.clear_rules();
         self.polar
@@ -540,7 +760,9 @@ impl PolarLanguageServer {
 
 #[cfg(test)]
 mod tests {
-    use lsp_types::Position;
+    use lsp_types::{
+        CodeActionContext, CompletionTextEdit, Position, Range, TextDocumentIdentifier, TextEdit,
+    };
     use wasm_bindgen_test::*;
 
     use super::*;
@@ -597,6 +819,27 @@ mod tests {
         TextDocumentItem::new(doc.uri, doc.language_id, doc.version + 1, text.into())
     }
 
+    #[track_caller]
+    fn position_params(
+        doc: &TextDocumentItem,
+        line: u32,
+        character: u32,
+    ) -> TextDocumentPositionParams {
+        let text_document = TextDocumentIdentifier::new(doc.uri.clone());
+        TextDocumentPositionParams::new(text_document, Position::new(line, character))
+    }
+
+    #[track_caller]
+    fn starts(locations: Option<Vec<Location>>) -> Vec<(Url, Position)> {
+        let locations = locations.unwrap().into_iter();
+        locations.map(|l| (l.uri, l.range.start)).collect()
+    }
+
+    #[track_caller]
+    fn start(doc: &TextDocumentItem, line: u32, character: u32) -> (Url, Position) {
+        (doc.uri.clone(), Position::new(line, character))
+    }
+
     #[track_caller]
     fn assert_missing_semicolon_error(diagnostics: &Diagnostics, docs: Vec<&TextDocumentItem>) {
         for doc in docs {
@@ -1024,4 +1267,528 @@ mod tests {
             undeclared_term
         );
     }
+
+    #[wasm_bindgen_test]
+    fn test_rule_definitions_and_references() {
+        let mut pls = new_pls();
+
+        let a = polar_doc(
+            "a",
+            "allow(actor, _action, _resource) if is_admin(actor);\n\
+             is_admin(actor: User) if actor.admin;\n"
+                .to_owned(),
+        );
+        let b = polar_doc(
+            "b",
+            "is_admin(actor: User) if actor.superuser;\n\
+             allow(actor, \"read\", _resource) if is_admin(actor) or is_admin(actor);\n"
+                .to_owned(),
+        );
+        pls.on_did_open_text_document(a.clone());
+        pls.on_did_open_text_document(b.clone());
+
+        // Every rule of the generic rule is a definition of a call.
+        let definitions = pls.on_goto_definition(position_params(&a, 0, 40)).unwrap();
+        let range = Range::new(Position::new(1, 0), Position::new(1, 21));
+        assert_eq!(definitions[0], Location::new(a.uri.clone(), range));
+        assert_eq!(
+            starts(Some(definitions)),
+            vec![start(&a, 1, 0), start(&b, 0, 0)]
+        );
+
+        // Navigating from a rule head.
+        let definitions = pls.on_goto_definition(position_params(&b, 0, 8));
+        assert_eq!(starts(definitions), vec![start(&a, 1, 0), start(&b, 0, 0)]);
+
+        // References from a call or rule head are the calls.
+        let calls = vec![start(&a, 0, 36), start(&b, 1, 35), start(&b, 1, 54)];
+        let references = pls.on_references(position_params(&a, 0, 36), false);
+        assert_eq!(starts(references), calls);
+        let references = pls.on_references(position_params(&b, 0, 0), false);
+        assert_eq!(starts(references), calls);
+
+        // ...optionally including the rules.
+        let references = pls.on_references(position_params(&b, 1, 60), true);
+        let expected = vec![
+            start(&a, 0, 36),
+            start(&a, 1, 0),
+            start(&b, 0, 0),
+            start(&b, 1, 35),
+            start(&b, 1, 54),
+        ];
+        assert_eq!(starts(references), expected);
+
+        // Arguments & whitespace aren't navigable.
+        assert!(pls.on_goto_definition(position_params(&a, 0, 46)).is_none());
+        assert!(pls.on_references(position_params(&a, 2, 0), true).is_none());
+
+        // Untracked docs aren't navigable.
+        let c = polar_doc("c", "".to_owned());
+        assert!(pls.on_goto_definition(position_params(&c, 0, 0)).is_none());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_resource_block_definitions_and_references() {
+        let mut pls = new_pls();
+
+        let policy = r#"allow(actor, action, resource) if
+  has_permission(actor, action, resource);
+
+actor User {}
+
+resource Org {
+  roles = ["owner"];
+}
+
+resource Repo {
+  roles = ["reader"];
+  permissions = ["read"];
+  relations = { parent: Org };
+
+  "read" if "reader";
+  "reader" if "owner" on "parent";
+}
+
+has_relation(org: Org, "parent", repo: Repo) if org = repo.org;
+"#;
+        let doc = polar_doc("whatever", policy.to_owned());
+        pls.on_did_open_text_document(doc.clone());
+
+        // Declared roles, permissions, and relations.
+        let definitions = pls.on_goto_definition(position_params(&doc, 14, 13));
+        assert_eq!(starts(definitions), vec![start(&doc, 10, 11)]);
+        let definitions = pls.on_goto_definition(position_params(&doc, 14, 3));
+        assert_eq!(starts(definitions), vec![start(&doc, 11, 17)]);
+        let definitions = pls.on_goto_definition(position_params(&doc, 15, 26));
+        assert_eq!(starts(definitions), vec![start(&doc, 12, 24)]);
+
+        // Roles on the other side of a relation are declared in the related resource's block.
+        let definitions = pls.on_goto_definition(position_params(&doc, 15, 15));
+        assert_eq!(starts(definitions), vec![start(&doc, 6, 11)]);
+
+        // Rules rewritten from shorthand rules are defined by the shorthand rule.
+        let definitions = pls.on_goto_definition(position_params(&doc, 1, 3));
+        assert_eq!(starts(definitions), vec![start(&doc, 14, 2)]);
+
+        let references = pls.on_references(position_params(&doc, 10, 12), false);
+        let expected = vec![start(&doc, 14, 12), start(&doc, 15, 2)];
+        assert_eq!(starts(references), expected);
+        let references = pls.on_references(position_params(&doc, 15, 3), true);
+        let expected = vec![start(&doc, 10, 11), start(&doc, 14, 12), start(&doc, 15, 2)];
+        assert_eq!(starts(references), expected);
+        let references = pls.on_references(position_params(&doc, 6, 12), false);
+        assert_eq!(starts(references), vec![start(&doc, 15, 14)]);
+
+        // The relation keyword isn't navigable.
+        assert!(pls
+            .on_goto_definition(position_params(&doc, 15, 23))
+            .is_none());
+    }
+
+    #[track_caller]
+    fn hover_markdown(hover: Option<Hover>) -> String {
+        match hover.unwrap().contents {
+            HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }) => value,
+            contents => panic!("unexpected hover contents: {:?}", contents),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_hover() {
+        let mut pls = new_pls();
+
+        let policy = r#"allow(actor, action, resource) if
+  has_permission(actor, action, resource);
+
+actor User {}
+
+resource Repo {
+  roles = ["reader"];
+  permissions = ["read"];
+  relations = { parent: Repo };
+
+  # Readers can read.
+  "read" if "reader";
+}
+
+# Repos are nested.
+#
+# Every repo has a parent.
+has_relation(parent: Repo, "parent", repo: Repo) if parent = repo.parent;
+"#;
+        let doc = polar_doc("whatever", policy.to_owned());
+        pls.on_did_open_text_document(doc.clone());
+
+        // Rule calls show the rule types, rules, and comments.
+        let hover = pls.on_hover(position_params(&doc, 1, 5));
+        let range = Range::new(Position::new(1, 2), Position::new(1, 16));
+        assert_eq!(hover.as_ref().unwrap().range, Some(range));
+        let expected = "```polar
+type has_permission(actor: Actor, _permission: String, resource: Resource);
+```
+
+1 rule named `has_permission`
+
+- [whatever.polar:12](file:///whatever.polar#L12)
+
+  Readers can read.";
+        assert_eq!(hover_markdown(hover), expected);
+
+        // As do rule heads.
+        let hover = pls.on_hover(position_params(&doc, 17, 0));
+        let expected = "```polar
+type has_relation(subject: Repo, \"parent\", object: Repo);
+```
+
+1 rule named `has_relation`
+
+- [whatever.polar:18](file:///whatever.polar#L18)
+
+  Repos are nested.
+  \n  Every repo has a parent.";
+        assert_eq!(hover_markdown(hover), expected);
+
+        // Resource block declarations show their kind.
+        let hover = pls.on_hover(position_params(&doc, 11, 14));
+        let expected = "```polar\nresource Repo {\n  roles = [\"reader\"];\n}\n```";
+        assert_eq!(hover_markdown(hover), expected);
+        let hover = pls.on_hover(position_params(&doc, 8, 24));
+        let expected = "```polar\nresource Repo {\n  relations = { parent: Repo };\n}\n```";
+        assert_eq!(hover_markdown(hover), expected);
+
+        // Specializers show the resource block for their class.
+        let hover = pls.on_hover(position_params(&doc, 17, 23));
+        let expected = "```polar
+resource Repo {
+  roles = [\"reader\"];
+  permissions = [\"read\"];
+  relations = { parent: Repo };
+}
+```";
+        assert_eq!(hover_markdown(hover), expected);
+        let hover = pls.on_hover(position_params(&doc, 3, 7));
+        assert_eq!(hover_markdown(hover), "```polar\nactor User {}\n```");
+
+        // Nothing to show for variables or whitespace.
+        assert!(pls.on_hover(position_params(&doc, 1, 19)).is_none());
+        assert!(pls.on_hover(position_params(&doc, 2, 0)).is_none());
+    }
+
+    /// Open a doc containing `policy` with the cursor at `|` and return the completion labels.
+    #[track_caller]
+    fn completion_labels(pls: &mut PolarLanguageServer, policy: &str) -> Vec<String> {
+        let offset = policy.find('|').unwrap();
+        let (line, character) = polar_core::loc_to_pos(policy, offset);
+        let doc = polar_doc("whatever", policy.replacen('|', "", 1));
+        pls.on_did_open_text_document(doc.clone());
+        let params = position_params(&doc, line as u32, character as u32);
+        let items = pls.on_completion(params).unwrap();
+        items.into_iter().map(|item| item.label).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_completion() {
+        let mut pls = new_pls();
+
+        let policy = r#"actor User {}
+
+resource Org {
+  roles = ["owner"];
+}
+
+resource Repo {
+  roles = ["reader"];
+  permissions = ["read"];
+  relations = { parent: Org };
+
+  CURSOR
+}
+
+allow(actor, action, resource) if
+  has_permission(actor, action, resource);
+
+CURSOR
+"#;
+        let block = |line: &str| policy.replacen("CURSOR", line, 1).replacen("CURSOR", "", 1);
+        let top_level = |line: &str| policy.replacen("CURSOR", "", 1).replacen("CURSOR", line, 1);
+
+        // Shorthand rule heads are roles or permissions.
+        let labels = completion_labels(&mut pls, &block("|"));
+        let expected = [
+            "\"read\"",
+            "\"reader\"",
+            "permissions",
+            "relations",
+            "roles",
+        ];
+        assert_eq!(labels, expected);
+        let labels = completion_labels(&mut pls, &block("\"re|\""));
+        assert_eq!(labels, ["\"read\"", "\"reader\""]);
+
+        // Impliers come from the related resource's block when followed by a relation.
+        let labels = completion_labels(&mut pls, &block("\"read\" if \"|\";"));
+        assert_eq!(labels, ["\"parent\"", "\"read\"", "\"reader\""]);
+        let labels = completion_labels(&mut pls, &block("\"reader\" if \"|\" on \"parent\";"));
+        assert_eq!(labels, ["\"owner\""]);
+        let labels = completion_labels(&mut pls, &block("\"reader\" if \"owner\" on \"|\";"));
+        assert_eq!(labels, ["\"parent\""]);
+
+        // The text edit replaces the partial string & its closing quote.
+        let policy = block("\"reader\" if \"ow|\" on \"parent\";");
+        let offset = policy.find('|').unwrap();
+        let (line, character) = polar_core::loc_to_pos(&policy, offset);
+        let doc = polar_doc("whatever", policy.replacen('|', "", 1));
+        pls.on_did_open_text_document(doc.clone());
+        let items = pls.on_completion(position_params(&doc, line as u32, character as u32));
+        let item = items.unwrap().pop().unwrap();
+        assert_eq!(item.detail.as_deref(), Some("role in Org"));
+        let edit = match item.text_edit.unwrap() {
+            CompletionTextEdit::Edit(edit) => edit,
+            edit => panic!("unexpected text edit: {:?}", edit),
+        };
+        let range = Range::new(Position::new(11, 14), Position::new(11, 18));
+        assert_eq!(edit, TextEdit::new(range, "\"owner\"".to_owned()));
+
+        // Specializers are classes.
+        let labels = completion_labels(&mut pls, &top_level("is_admin(user: |"));
+        assert_eq!(labels, ["Actor", "Org", "Repo", "Resource", "User"]);
+
+        // Elsewhere, rules (including those with resource-specific rule types), classes, and
+        // keywords.
+        let labels = completion_labels(&mut pls, &top_level("is_admin(user: User) if |"));
+        for label in [
+            "allow",
+            "has_permission",
+            "has_relation",
+            "has_role",
+            "Repo",
+            "matches",
+        ] {
+            assert!(labels.contains(&label.to_owned()), "{}", label);
+        }
+
+        // Nothing inside comments or strings outside of resource blocks.
+        assert!(completion_labels(&mut pls, &top_level("# has_|")).is_empty());
+        assert!(completion_labels(&mut pls, &top_level("x(\"has_|\");")).is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_symbols() {
+        let mut pls = new_pls();
+
+        let policy = r#"allow(actor, action, resource) if
+  has_permission(actor, action, resource);
+
+actor User {}
+
+resource Repo {
+  roles = ["reader"];
+  permissions = ["read"];
+  relations = { parent: Repo };
+
+  "read" if "reader";
+  "reader" if "reader" on "parent";
+}
+
+has_relation(parent: Repo, "parent", repo: Repo) if parent = repo.parent;
+
+allow(_: User, "read", _: Repo);
+"#;
+        let doc = polar_doc("whatever", policy.to_owned());
+        pls.on_did_open_text_document(doc.clone());
+
+        // Rules are grouped by name & resource blocks contain their declarations and shorthand
+        // rules.
+        let symbols = pls.on_document_symbol(&doc.uri).unwrap();
+        let names: Vec<_> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["allow", "User", "Repo", "has_relation"]);
+
+        let allow = &symbols[0];
+        assert_eq!(allow.detail.as_deref(), Some("2 rules"));
+        let range = Range::new(Position::new(0, 0), Position::new(16, 31));
+        assert_eq!(allow.range, range);
+        let range = Range::new(Position::new(0, 0), Position::new(0, 5));
+        assert_eq!(allow.selection_range, range);
+        let rules = allow.children.as_ref().unwrap();
+        let names: Vec<_> = rules.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "allow(actor, action, resource)",
+                "allow(_: User, \"read\", _: Repo)"
+            ]
+        );
+        let range = Range::new(Position::new(0, 0), Position::new(1, 41));
+        assert_eq!(rules[0].range, range);
+
+        let user = &symbols[1];
+        assert_eq!(user.detail.as_deref(), Some("actor"));
+        let range = Range::new(Position::new(3, 6), Position::new(3, 10));
+        assert_eq!(user.selection_range, range);
+        assert!(user.children.as_ref().unwrap().is_empty());
+
+        let repo = &symbols[2];
+        let range = Range::new(Position::new(5, 0), Position::new(12, 1));
+        assert_eq!(repo.range, range);
+        let children = repo.children.as_ref().unwrap();
+        let children: Vec<_> = children
+            .iter()
+            .map(|s| (s.name.as_str(), s.detail.as_deref(), s.range.start.line))
+            .collect();
+        let expected = [
+            ("\"reader\"", Some("role"), 6),
+            ("\"read\"", Some("permission"), 7),
+            ("\"parent\"", Some("relation to Repo"), 8),
+            ("\"read\" if \"reader\"", None, 10),
+            ("\"reader\" if \"reader\" on \"parent\"", None, 11),
+        ];
+        assert_eq!(children, expected);
+
+        // Workspace symbols are flattened & filtered by name.
+        let symbols = pls.on_workspace_symbol("READ");
+        let symbols: Vec<_> = symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.container_name.as_deref()))
+            .collect();
+        let expected = [
+            ("allow(_: User, \"read\", _: Repo)", Some("allow")),
+            ("\"reader\"", Some("Repo")),
+            ("\"read\"", Some("Repo")),
+            ("\"read\" if \"reader\"", Some("Repo")),
+            ("\"reader\" if \"reader\" on \"parent\"", Some("Repo")),
+        ];
+        assert_eq!(symbols, expected);
+        assert_eq!(pls.on_workspace_symbol("").len(), 12);
+
+        // Untracked docs have no symbols.
+        assert!(pls.on_document_symbol(&polar_uri("untracked")).is_none());
+    }
+
+    /// The title & edits of each code action for the `range` in `doc`.
+    #[track_caller]
+    fn code_action_edits(
+        pls: &PolarLanguageServer,
+        doc: &TextDocumentItem,
+        range: Range,
+    ) -> Vec<(String, Vec<TextEdit>)> {
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(doc.uri.clone()),
+            range,
+            context: Default::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = pls.on_code_action(params).unwrap().into_iter();
+        actions
+            .map(|action| {
+                let mut changes = action.edit.unwrap().changes.unwrap();
+                (action.title, changes.remove(&doc.uri).unwrap())
+            })
+            .collect()
+    }
+
+    fn at(line: u32, character: u32) -> Range {
+        Range::new(
+            Position::new(line, character),
+            Position::new(line, character),
+        )
+    }
+
+    fn insert_at(line: u32, character: u32, text: &str) -> TextEdit {
+        TextEdit::new(at(line, character), text.to_owned())
+    }
+
+    #[wasm_bindgen_test]
+    fn test_code_actions() {
+        let mut pls = new_pls();
+
+        let policy = r#"actor User {}
+
+resource Repo {
+  roles = ["reader"];
+  permissions = ["read"];
+  "read" if "reader";
+}
+
+has_role(user: Usr, name: String, repo: Repo) if
+  x = 1 and name = "reader" or repo = user;
+
+first([head, *tail]) if head = 1;
+
+positive(n: int) if n > 0;
+"#;
+        let doc = polar_doc("whatever", policy.to_owned());
+        let diagnostics = pls.on_did_open_text_document(doc.clone());
+
+        // `MissingAllowRule` & `MissingHasPermissionRule` share a fix.
+        let allow = "allow(actor, action, resource) if
+  has_permission(actor, action, resource);
+
+";
+        let expected = vec![(
+            "Add an `allow` rule that calls `has_permission`".to_owned(),
+            vec![insert_at(0, 0, allow)],
+        )];
+        assert_eq!(code_action_edits(&pls, &doc, at(0, 0)), expected);
+
+        // Unknown specializers are replaced with the closest known class.
+        let range = Range::new(Position::new(8, 15), Position::new(8, 18));
+        let expected = vec![(
+            "Change to `User`".to_owned(),
+            vec![TextEdit::new(range, "User".to_owned())],
+        )];
+        assert_eq!(code_action_edits(&pls, &doc, at(8, 16)), expected);
+        let range = Range::new(Position::new(13, 12), Position::new(13, 15));
+        let expected = vec![(
+            "Change to `Integer`".to_owned(),
+            vec![TextEdit::new(range, "Integer".to_owned())],
+        )];
+        assert_eq!(code_action_edits(&pls, &doc, at(13, 13)), expected);
+        // No fix for specializers with no close match.
+        assert!(code_action_edits(&pls, &doc, at(8, 27)).is_empty());
+
+        // Singleton variables are prefixed with `_`, and ambiguous expressions are parenthesized.
+        let expected = vec![
+            ("Prefix `x` with `_`".to_owned(), vec![insert_at(9, 2, "_")]),
+            (
+                "Add parentheses".to_owned(),
+                vec![insert_at(9, 2, "("), insert_at(9, 27, ")")],
+            ),
+        ];
+        assert_eq!(code_action_edits(&pls, &doc, at(9, 2)), expected);
+        let expected = vec![(
+            "Prefix `tail` with `_`".to_owned(),
+            vec![insert_at(11, 14, "_")],
+        )];
+        assert_eq!(code_action_edits(&pls, &doc, at(11, 15)), expected);
+
+        // Fixes link to the diagnostics reported by the client.
+        let reported = diagnostics.get(&doc.uri).unwrap().diagnostics.clone();
+        let mut params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(doc.uri.clone()),
+            range: at(9, 10),
+            context: CodeActionContext {
+                diagnostics: reported.clone(),
+                only: None,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = pls.on_code_action(params.clone()).unwrap();
+        let linked = actions[0].diagnostics.as_ref().unwrap();
+        assert_eq!(linked.len(), 1);
+        assert_eq!(
+            linked[0].code,
+            Some(NumberOrString::String(
+                "ValidationWarning::AmbiguousPrecedence".to_owned()
+            ))
+        );
+
+        // Only quick fixes are offered.
+        params.context.only = Some(vec![CodeActionKind::REFACTOR]);
+        assert!(pls.on_code_action(params).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,258 @@
+use lsp_types::Location;
+use polar_core::{
+    kb::KnowledgeBase,
+    resource_block::ShorthandRule,
+    rules::Rule,
+    sources::Context,
+    terms::{Call, InstanceLiteral, Pattern, Symbol, Term, Value},
+    visitor::{walk_term, Visitor},
+};
+
+use crate::helpers::location_from_context;
+
+/// Something in a policy that can be navigated to and from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// A rule, referenced by name in rule heads & calls.
+    Rule(Symbol),
+    /// A role, permission, or relation. The first `Term` is the resource (`Symbol`) whose block
+    /// declares the second `Term` (`String`).
+    Declaration(Term, Term),
+    /// A class, referenced by name in specializers & resource block headers.
+    Class(Symbol),
+}
+
+/// A reference to (or definition of) a `Target`.
+struct Occurrence {
+    target: Target,
+    context: Context,
+    is_definition: bool,
+}
+
+/// Find the `Target` at `offset` in the source file named `filename` along with the context of
+/// the term that references it.
+pub(crate) fn target_at(
+    kb: &KnowledgeBase,
+    filename: &str,
+    offset: usize,
+) -> Option<(Target, Context)> {
+    occurrences(kb)
+        .into_iter()
+        .find(|o| is_at(&o.context, filename, offset))
+        .map(|o| (o.target, o.context))
+}
+
+/// Locations where `target` is defined: every `Rule` of a `GenericRule`, the resource block
+/// declaration of a role, permission, or relation, or the resource blocks for a class.
+pub(crate) fn definitions(kb: &KnowledgeBase, target: &Target) -> Vec<Location> {
+    let contexts = match target {
+        Target::Rule(name) => kb
+            .get_generic_rule(name)
+            .map_or_else(Vec::new, |generic_rule| {
+                let rules = generic_rule.rules.values();
+                rules.filter_map(|r| r.parsed_context()).cloned().collect()
+            }),
+        Target::Declaration(resource, declaration) => {
+            let declarations = kb.resource_blocks.declarations().get(resource);
+            let declared = declarations.and_then(|ds| ds.get_key_value(declaration));
+            declared
+                .and_then(|(declaration, _)| declaration.parsed_context())
+                .into_iter()
+                .cloned()
+                .collect()
+        }
+        Target::Class(_) => occurrences(kb)
+            .into_iter()
+            .filter(|o| o.is_definition && &o.target == target)
+            .map(|o| o.context)
+            .collect(),
+    };
+    locations(contexts)
+}
+
+/// Locations where `target` is referenced, optionally including the locations where it's defined.
+pub(crate) fn references(
+    kb: &KnowledgeBase,
+    target: &Target,
+    include_definitions: bool,
+) -> Vec<Location> {
+    let contexts = occurrences(kb)
+        .into_iter()
+        .filter(|o| !o.is_definition && &o.target == target)
+        .map(|o| o.context)
+        .collect();
+    let mut locations = locations(contexts);
+    if include_definitions {
+        locations.extend(definitions(kb, target));
+        sort(&mut locations);
+        locations.dedup();
+    }
+    locations
+}
+
+fn rules(kb: &KnowledgeBase) -> impl Iterator<Item = &Rule> {
+    let generic_rules = kb.get_rules().values();
+    generic_rules.flat_map(|generic_rule| generic_rule.rules.values().map(AsRef::as_ref))
+}
+
+/// Collects the name & parsed context of every `Call` in the visited terms.
+#[derive(Default)]
+struct CallCollector {
+    calls: Vec<(Symbol, Context)>,
+}
+
+impl Visitor for CallCollector {
+    fn visit_term(&mut self, term: &Term) {
+        if let (Value::Call(Call { name, .. }), Some(context)) =
+            (term.value(), term.parsed_context())
+        {
+            self.calls.push((name.clone(), context.clone()));
+        }
+        walk_term(self, term)
+    }
+}
+
+fn calls(kb: &KnowledgeBase) -> Vec<(Symbol, Context)> {
+    let mut collector = CallCollector::default();
+    for generic_rule in kb.get_rules().values() {
+        collector.visit_generic_rule(generic_rule);
+    }
+    collector.calls
+}
+
+/// The class & parsed context of every class specializer in a rule head, e.g., `User` in
+/// `is_admin(actor: User)`.
+fn specializers(kb: &KnowledgeBase) -> Vec<(Symbol, Context)> {
+    let params = rules(kb).flat_map(|rule| &rule.params);
+    let specializers = params.filter_map(|param| param.specializer.as_ref());
+    specializers
+        .filter_map(
+            |specializer| match (specializer.value(), specializer.parsed_context()) {
+                (Value::Pattern(Pattern::Instance(InstanceLiteral { tag, .. })), Some(context)) => {
+                    Some((tag.clone(), context.clone()))
+                }
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// Every navigable term in the policy. Rules & classes are referenced by name, so their contexts
+/// are narrowed to the name. Rules rewritten from shorthand rules borrow the context of the
+/// shorthand rule's head, which doesn't begin with the rule's name, so they're skipped here and
+/// handled as resource block references instead.
+fn occurrences(kb: &KnowledgeBase) -> Vec<Occurrence> {
+    let mut occurrences = vec![];
+    let mut push_name = |target: Target, name: &Symbol, context: &Context, is_definition| {
+        if let Some(context) = name_context(context, name) {
+            occurrences.push(Occurrence {
+                target,
+                context,
+                is_definition,
+            });
+        }
+    };
+
+    for rule in rules(kb) {
+        if let Some(context) = rule.parsed_context() {
+            push_name(Target::Rule(rule.name.clone()), &rule.name, context, true);
+        }
+    }
+    for (name, context) in calls(kb) {
+        push_name(Target::Rule(name.clone()), &name, &context, false);
+    }
+    for (class, context) in specializers(kb) {
+        push_name(Target::Class(class.clone()), &class, &context, false);
+    }
+    for resource in kb.resource_blocks.declarations().keys() {
+        if let (Value::Variable(class), Some(context)) =
+            (resource.value(), resource.parsed_context())
+        {
+            if let Some(context) = block_name_context(context, class) {
+                push_name(Target::Class(class.clone()), class, &context, true);
+            }
+        }
+    }
+
+    occurrences.extend(resource_block_references(kb));
+    occurrences
+}
+
+/// Every declaration in & reference from a resource block. A shorthand rule's implier resolves
+/// against the related resource's block when the rule traverses a relation, e.g., `"owner"` in
+/// `"reader" if "owner" on "parent";`.
+fn resource_block_references(kb: &KnowledgeBase) -> Vec<Occurrence> {
+    let blocks = &kb.resource_blocks;
+    let mut references = vec![];
+    let mut push = |resource: &Term, term: &Term, is_definition| {
+        if let Some(context) = term.parsed_context() {
+            references.push(Occurrence {
+                target: Target::Declaration(resource.clone(), term.clone()),
+                context: context.clone(),
+                is_definition,
+            });
+        }
+    };
+
+    for (resource, declarations) in blocks.declarations() {
+        for declaration in declarations.keys() {
+            push(resource, declaration, true);
+        }
+    }
+
+    for (resource, shorthand_rules) in &blocks.shorthand_rules {
+        for ShorthandRule { head, body } in shorthand_rules {
+            push(resource, head, false);
+            match body {
+                (implier, None) => push(resource, implier, false),
+                (implier, Some((_, relation))) => {
+                    push(resource, relation, false);
+                    if let Ok(related) =
+                        blocks.get_relation_type_in_resource_block(relation, resource)
+                    {
+                        push(related, implier, false);
+                    }
+                }
+            }
+        }
+    }
+
+    references
+}
+
+fn is_at(context: &Context, filename: &str, offset: usize) -> bool {
+    let in_file = context.source.filename.as_deref() == Some(filename);
+    in_file && context.left <= offset && offset <= context.right
+}
+
+/// Narrow `context` to `name` if `context` begins with `name`.
+pub(crate) fn name_context(context: &Context, name: &Symbol) -> Option<Context> {
+    let src = context.source.src.get(context.left..)?;
+    src.starts_with(&name.0).then(|| Context {
+        source: context.source.clone(),
+        left: context.left,
+        right: context.left + name.0.len(),
+    })
+}
+
+/// Narrow the context of a resource block, e.g., `resource Repo { ... }`, to the resource's name.
+pub(crate) fn block_name_context(context: &Context, name: &Symbol) -> Option<Context> {
+    let src = context.source.src.get(context.left..context.right)?;
+    let (header, _) = src.split_once('{')?;
+    let header = header.trim_end();
+    header.ends_with(&name.0).then(|| Context {
+        source: context.source.clone(),
+        left: context.left + header.len() - name.0.len(),
+        right: context.left + header.len(),
+    })
+}
+
+fn locations(contexts: Vec<Context>) -> Vec<Location> {
+    let mut locations: Vec<_> = contexts.iter().filter_map(location_from_context).collect();
+    sort(&mut locations);
+    locations
+}
+
+fn sort(locations: &mut [Location]) {
+    locations.sort_by(|l, r| (&l.uri, l.range.start).cmp(&(&r.uri, r.range.start)));
+}
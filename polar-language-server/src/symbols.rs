@@ -0,0 +1,253 @@
+use lsp_types::{DocumentSymbol, Location, Range, SymbolInformation, SymbolKind, Url};
+use polar_core::{
+    kb::KnowledgeBase,
+    resource_block::{Declaration, ShorthandRule},
+    rules::Rule,
+    sources::Context,
+    terms::Value,
+};
+
+use crate::{
+    helpers::range_from_context,
+    navigation::{block_name_context, name_context},
+};
+
+/// Symbols for the rules & resource blocks in the source file named `filename`, in the order they
+/// appear. Rules are grouped by name, and resource blocks contain their declarations & shorthand
+/// rules.
+pub(crate) fn document_symbols(kb: &KnowledgeBase, filename: &str) -> Vec<DocumentSymbol> {
+    let mut symbols = rule_symbols(kb, filename);
+    symbols.extend(resource_block_symbols(kb, filename));
+    sort(&mut symbols);
+    symbols
+}
+
+/// Symbols whose names contain `query` (ignoring case) across the source files at `uris`. Nested
+/// symbols are flattened and name the symbol that contains them.
+pub(crate) fn workspace_symbols<'a>(
+    kb: &KnowledgeBase,
+    uris: impl IntoIterator<Item = &'a Url>,
+    query: &str,
+) -> Vec<SymbolInformation> {
+    let query = query.to_lowercase();
+    let mut symbols = vec![];
+    for uri in uris {
+        for symbol in document_symbols(kb, uri.as_str()) {
+            flatten(uri, symbol, None, &mut symbols);
+        }
+    }
+    symbols.retain(|symbol| symbol.name.to_lowercase().contains(&query));
+    symbols
+}
+
+fn flatten(
+    uri: &Url,
+    symbol: DocumentSymbol,
+    container_name: Option<String>,
+    symbols: &mut Vec<SymbolInformation>,
+) {
+    let DocumentSymbol {
+        name,
+        kind,
+        range,
+        children,
+        ..
+    } = symbol;
+    #[allow(deprecated)]
+    symbols.push(SymbolInformation {
+        name: name.clone(),
+        kind,
+        tags: None,
+        deprecated: None,
+        location: Location::new(uri.clone(), range),
+        container_name,
+    });
+    for child in children.into_iter().flatten() {
+        flatten(uri, child, Some(name.clone()), symbols);
+    }
+}
+
+/// One symbol per rule name, containing a symbol for each rule with that name. Rules rewritten
+/// from shorthand rules borrow the context of the shorthand rule's head, which doesn't begin with
+/// the rule's name, so they're left to their resource block.
+fn rule_symbols(kb: &KnowledgeBase, filename: &str) -> Vec<DocumentSymbol> {
+    let mut symbols = vec![];
+    for (name, generic_rule) in kb.get_rules() {
+        let mut rules: Vec<_> = generic_rule
+            .rules
+            .values()
+            .filter_map(|rule| {
+                let context = rule.parsed_context()?;
+                let name_context = name_context(context, &rule.name)?;
+                in_file(context, filename).then(|| rule_symbol(rule, context, &name_context))
+            })
+            .collect();
+        if rules.is_empty() {
+            continue;
+        }
+        sort(&mut rules);
+
+        let start = rules[0].range.start;
+        let end = rules
+            .iter()
+            .map(|rule| rule.range.end)
+            .max()
+            .unwrap_or(start);
+        let selection_range = rules[0].selection_range;
+        let detail = match rules.len() {
+            1 => "1 rule".to_owned(),
+            n => format!("{} rules", n),
+        };
+        symbols.push(symbol(
+            name.to_string(),
+            Some(detail),
+            SymbolKind::Function,
+            Range::new(start, end),
+            selection_range,
+            Some(rules),
+        ));
+    }
+    symbols
+}
+
+/// A rule is named by its head as written and spans from its head to the end of its body.
+fn rule_symbol(rule: &Rule, context: &Context, name_context: &Context) -> DocumentSymbol {
+    let body = rule.body.parsed_context().map(|body| body.right);
+    let right = body.map_or(context.right, |right| right.max(context.right));
+    symbol(
+        source_text(context, context.right),
+        None,
+        SymbolKind::Method,
+        span(context, right),
+        range_from_context(name_context),
+        None,
+    )
+}
+
+fn resource_block_symbols(kb: &KnowledgeBase, filename: &str) -> Vec<DocumentSymbol> {
+    let blocks = &kb.resource_blocks;
+    let mut symbols = vec![];
+    for (resource, declarations) in blocks.declarations() {
+        let (class, context) = match (resource.value(), resource.parsed_context()) {
+            (Value::Variable(class), Some(context)) if in_file(context, filename) => {
+                (class, context)
+            }
+            _ => continue,
+        };
+        let name_context = match block_name_context(context, class) {
+            Some(name_context) => name_context,
+            None => continue,
+        };
+
+        let mut children: Vec<_> = declarations
+            .iter()
+            .filter_map(|(name, declaration)| {
+                let context = name.parsed_context()?;
+                let (kind, detail) = match declaration {
+                    Declaration::Role => (SymbolKind::EnumMember, "role".to_owned()),
+                    Declaration::Permission => (SymbolKind::EnumMember, "permission".to_owned()),
+                    Declaration::Relation(related) => {
+                        (SymbolKind::Field, format!("relation to {}", related))
+                    }
+                };
+                let range = range_from_context(context);
+                Some(symbol(
+                    name.to_string(),
+                    Some(detail),
+                    kind,
+                    range,
+                    range,
+                    None,
+                ))
+            })
+            .collect();
+        let shorthand_rules = blocks.shorthand_rules.get(resource).into_iter().flatten();
+        children.extend(shorthand_rules.filter_map(shorthand_rule_symbol));
+        sort(&mut children);
+
+        let keyword = if blocks.actors.contains(resource) {
+            "actor"
+        } else {
+            "resource"
+        };
+        symbols.push(symbol(
+            class.to_string(),
+            Some(keyword.to_owned()),
+            SymbolKind::Class,
+            range_from_context(context),
+            range_from_context(&name_context),
+            Some(children),
+        ));
+    }
+    symbols
+}
+
+/// A shorthand rule is named as written and spans from its head to its implier or relation, e.g.,
+/// `"reader" if "owner" on "parent"`.
+fn shorthand_rule_symbol(shorthand_rule: &ShorthandRule) -> Option<DocumentSymbol> {
+    let ShorthandRule { head, body } = shorthand_rule;
+    let (implier, relation) = body;
+    let last = relation.as_ref().map_or(implier, |(_, relation)| relation);
+    let context = head.parsed_context()?;
+    let right = last
+        .parsed_context()
+        .map_or(context.right, |last| last.right);
+
+    let selection_range = range_from_context(context);
+    Some(symbol(
+        source_text(context, right),
+        None,
+        SymbolKind::Method,
+        span(context, right),
+        selection_range,
+        None,
+    ))
+}
+
+fn symbol(
+    name: String,
+    detail: Option<String>,
+    kind: SymbolKind,
+    range: Range,
+    selection_range: Range,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range,
+        children,
+    }
+}
+
+fn in_file(context: &Context, filename: &str) -> bool {
+    context.source.filename.as_deref() == Some(filename)
+}
+
+/// The source text from the start of `context` to `right` with whitespace collapsed.
+fn source_text(context: &Context, right: usize) -> String {
+    let text = context
+        .source
+        .src
+        .get(context.left..right)
+        .unwrap_or_default();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The range from the start of `context` to `right`.
+fn span(context: &Context, right: usize) -> Range {
+    range_from_context(&Context {
+        source: context.source.clone(),
+        left: context.left,
+        right,
+    })
+}
+
+fn sort(symbols: &mut [DocumentSymbol]) {
+    symbols.sort_by_key(|symbol| symbol.range.start);
+}
@@ -1,4 +1,4 @@
-use polar_core::{polar, sources::Source, terms::Symbol};
+use polar_core::{polar, query_options::QueryOptions, sources::Source, terms::Symbol};
 use wasm_bindgen::prelude::*;
 
 use crate::errors::{serialization_error, Error};
@@ -26,6 +26,15 @@ impl Polar {
             .map_err(Error::into)
     }
 
+    #[wasm_bindgen(js_class = Polar, js_name = reload)]
+    pub fn wasm_reload(&self, sources: JsValue) -> JsResult<()> {
+        let sources: Vec<Source> = serde_wasm_bindgen::from_value(sources)?;
+        self.0
+            .reload(sources)
+            .map_err(Error::from)
+            .map_err(Error::into)
+    }
+
     #[wasm_bindgen(js_class = Polar, js_name = clearRules)]
     pub fn wasm_clear_rules(&self) {
         self.0.clear_rules()
@@ -54,6 +63,16 @@ impl Polar {
             .map_err(Error::into)
     }
 
+    #[wasm_bindgen(js_class = Polar, js_name = newQueryWithOptions)]
+    pub fn wasm_new_query_with_options(&self, src: &str, options: JsValue) -> JsResult<Query> {
+        let options: QueryOptions = serde_wasm_bindgen::from_value(options)?;
+        self.0
+            .new_query_with_options(src, false, &options)
+            .map(Query::from)
+            .map_err(Error::from)
+            .map_err(Error::into)
+    }
+
     #[wasm_bindgen(js_class = Polar, js_name = newQueryFromTerm)]
     pub fn wasm_new_query_from_term(&self, term: JsValue) -> JsResult<Query> {
         let term = serde_wasm_bindgen::from_value(term)?;
@@ -80,6 +99,11 @@ impl Polar {
             .map_err(Error::into)
     }
 
+    #[wasm_bindgen(js_class = Polar, js_name = tableRule)]
+    pub fn wasm_table_rule(&self, name: &str) {
+        self.0.table_rule(Symbol::new(name))
+    }
+
     #[wasm_bindgen(js_class = Polar, js_name = buildDataFilter)]
     pub fn wasm_build_data_filter(
         &self,
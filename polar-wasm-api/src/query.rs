@@ -80,6 +80,12 @@ impl Query {
             .map_err(Error::into)
     }
 
+    /// Cancels the query, which fails with a `QueryCancelled` error on its next event.
+    #[wasm_bindgen(js_class = Query, js_name = cancel)]
+    pub fn wasm_cancel(&self) {
+        self.0.cancellation_handle().cancel();
+    }
+
     #[cfg(target_arch = "wasm32")]
     #[wasm_bindgen(js_class = Query, js_name = setLoggingOptions)]
     pub fn wasm_set_logging_options(
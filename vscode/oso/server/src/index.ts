@@ -1,4 +1,5 @@
 import {
+  CodeActionKind,
   createConnection,
   ProposedFeatures,
   PublishDiagnosticsParams,
@@ -16,6 +17,7 @@ const telemetryCallback = (event: unknown) =>
 const pls = new PolarLanguageServer(sendDiagnosticsCallback, telemetryCallback);
 
 connection.onNotification((...args) => pls.onNotification(...args));
+connection.onRequest((...args) => pls.onRequest(...args)); // eslint-disable-line @typescript-eslint/no-unsafe-return
 
 connection.onInitialize(() => {
   return {
@@ -28,6 +30,13 @@ connection.onInitialize(() => {
         save: false,
         change: TextDocumentSyncKind.Full,
       },
+      codeActionProvider: { codeActionKinds: [CodeActionKind.QuickFix] },
+      completionProvider: { triggerCharacters: ['"'] },
+      definitionProvider: true,
+      documentSymbolProvider: true,
+      hoverProvider: true,
+      referencesProvider: true,
+      workspaceSymbolProvider: true,
       workspace: {
         workspaceFolders: { supported: true },
         // NOTE(gj): There's [an open issue][1] when specifying the `matches`